@@ -1,9 +1,28 @@
 const path = require('path');
+const { execSync } = require('child_process');
 const programDir = path.join(__dirname, '');
 const idlDir = path.join(__dirname, '../frontend/program', 'idl');
 const sdkDir = path.join(__dirname, '../frontend/program', 'generated');
 const binaryInstallDir = path.join(__dirname, '.crates');
 
+// shank only declares the instructions, their accounts come from the account contexts of the program
+const idlHook = (idl) => {
+    const output = execSync('cargo run --quiet --features no-entrypoint --example idl_accounts', { cwd: programDir });
+    const accounts = JSON.parse(output.toString());
+    for (const ix of idl.instructions) {
+        if (!(ix.name in accounts)) {
+            throw new Error(`No account context for instruction ${ix.name}`);
+        }
+        ix.accounts = accounts[ix.name];
+        delete accounts[ix.name];
+    }
+    const unknown = Object.keys(accounts);
+    if (unknown.length > 0) {
+        throw new Error(`Account contexts without instruction: ${unknown.join(', ')}`);
+    }
+    return idl;
+};
+
 module.exports = {
     idlGenerator: 'shank',
     programName: 'vrf_betting',
@@ -11,4 +30,5 @@ module.exports = {
     sdkDir,
     binaryInstallDir,
     programDir,
-};
\ No newline at end of file
+    idlHook,
+};
@@ -0,0 +1,381 @@
+//! Derive macro generating the account parsing and validation of the instruction contexts.
+//!
+//! ```ignore
+//! #[derive(Accounts)]
+//! #[accounts(args = GameCreateArgs)]
+//! pub struct GameCreateAccounts<'a, 'info> {
+//!     #[account(signer, writable, address = host_user_account_state.authority, name = "host", desc = "The wallet account of the host")]
+//!     pub host_account_info: &'a AccountInfo<'info>,
+//!     #[account(writable, seeds = [b"UserAccount", host_user_account_state.authority], name = "user_account", desc = "User Betting Account of the host")]
+//!     pub host_user_account_info: &'a AccountInfo<'info>,
+//!     #[state(host_user_account_info)]
+//!     pub host_user_account_state: UserAccount,
+//! }
+//! ```
+//!
+//! The accounts are read in field order, the states are deserialized before any check runs so that the checks may
+//! refer to them. States typed `RefMut<'a, _>` are fixed-layout accounts borrowed in place, and `stored_bump = EXPR`
+//! verifies the seeds with the bump kept in such a state instead of finding the canonical one. Accounts typed
+//! `Option<_>` are read after the checks of the required accounts, only when their `optional` condition holds. The
+//! generated `IDL_ACCOUNTS` lists the IDL entry of every account, the contexts are the only source of the instruction
+//! accounts in the IDL.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Data, DeriveInput, Error, Expr, Fields, GenericArgument, Ident, LitStr, PathArguments, Result, Token, Type,
+};
+
+#[proc_macro_derive(Accounts, attributes(accounts, account, state))]
+pub fn derive_accounts(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+/// An item of the `#[account(...)]` attribute
+enum AccountArg {
+    Signer,
+    Writable,
+    Address(Expr),
+    Seeds(Vec<Expr>),
+    Bump(Ident),
+    StoredBump(Expr),
+    Authority(Expr),
+    Constraint(Box<(Expr, Expr)>),
+    Optional(Expr),
+    Name(LitStr),
+    Desc(LitStr),
+}
+
+impl Parse for AccountArg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key: Ident = input.parse()?;
+        match key.to_string().as_str() {
+            "signer" => Ok(AccountArg::Signer),
+            "writable" => Ok(AccountArg::Writable),
+            "address" => {
+                input.parse::<Token![=]>()?;
+                Ok(AccountArg::Address(input.parse()?))
+            }
+            "seeds" => {
+                input.parse::<Token![=]>()?;
+                let content;
+                bracketed!(content in input);
+                let seeds = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+                Ok(AccountArg::Seeds(seeds.into_iter().collect()))
+            }
+            "bump" => {
+                input.parse::<Token![=]>()?;
+                Ok(AccountArg::Bump(input.parse()?))
+            }
+            "stored_bump" => {
+                input.parse::<Token![=]>()?;
+                Ok(AccountArg::StoredBump(input.parse()?))
+            }
+            "authority" => {
+                input.parse::<Token![=]>()?;
+                Ok(AccountArg::Authority(input.parse()?))
+            }
+            "constraint" => {
+                input.parse::<Token![=]>()?;
+                let constraint = input.parse()?;
+                input.parse::<Token![@]>()?;
+                Ok(AccountArg::Constraint(Box::new((constraint, input.parse()?))))
+            }
+            "optional" => {
+                input.parse::<Token![=]>()?;
+                Ok(AccountArg::Optional(input.parse()?))
+            }
+            "name" => {
+                input.parse::<Token![=]>()?;
+                Ok(AccountArg::Name(input.parse()?))
+            }
+            "desc" => {
+                input.parse::<Token![=]>()?;
+                Ok(AccountArg::Desc(input.parse()?))
+            }
+            _ => Err(Error::new(key.span(), "unknown account argument")),
+        }
+    }
+}
+
+struct AccountField {
+    ident: Ident,
+    is_optional: bool,
+    signer: bool,
+    writable: bool,
+    address: Option<Expr>,
+    seeds: Option<Vec<Expr>>,
+    bump: Option<Ident>,
+    stored_bump: Option<Expr>,
+    authority: Option<Expr>,
+    constraints: Vec<(Expr, Expr)>,
+    condition: Option<Expr>,
+    name: String,
+    desc: String,
+}
+
+struct StateField {
+    ident: Ident,
+    ty: Type,
+    account: Ident,
+}
+
+/// The first type argument of a type named `wrapper`
+fn wrapped_type<'t>(ty: &'t Type, wrapper: &str) -> Option<&'t Type> {
+    if let Type::Path(type_path) = ty {
+        let segment = type_path.path.segments.last()?;
+        if segment.ident == wrapper {
+            if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                return args.args.iter().find_map(|arg| match arg {
+                    GenericArgument::Type(inner) => Some(inner),
+                    _ => None,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    wrapped_type(ty, "Option")
+}
+
+fn parse_account_field(ident: Ident, ty: &Type, args: Vec<AccountArg>) -> Result<AccountField> {
+    let mut field = AccountField {
+        name: ident.to_string().trim_end_matches("_account_info").to_string(),
+        ident,
+        is_optional: option_inner(ty).is_some(),
+        signer: false,
+        writable: false,
+        address: None,
+        seeds: None,
+        bump: None,
+        stored_bump: None,
+        authority: None,
+        constraints: vec![],
+        condition: None,
+        desc: String::new(),
+    };
+    for arg in args {
+        match arg {
+            AccountArg::Signer => field.signer = true,
+            AccountArg::Writable => field.writable = true,
+            AccountArg::Address(address) => field.address = Some(address),
+            AccountArg::Seeds(seeds) => field.seeds = Some(seeds),
+            AccountArg::Bump(bump) => field.bump = Some(bump),
+            AccountArg::StoredBump(stored_bump) => field.stored_bump = Some(stored_bump),
+            AccountArg::Authority(authority) => field.authority = Some(authority),
+            AccountArg::Constraint(constraint) => field.constraints.push(*constraint),
+            AccountArg::Optional(condition) => field.condition = Some(condition),
+            AccountArg::Name(name) => field.name = name.value(),
+            AccountArg::Desc(desc) => field.desc = desc.value(),
+        }
+    }
+    if field.desc.is_empty() {
+        return Err(Error::new(field.ident.span(), "missing `desc` for the IDL"));
+    }
+    if field.is_optional != field.condition.is_some() {
+        return Err(Error::new(field.ident.span(), "`Option` accounts need an `optional` condition and only them"));
+    }
+    if field.bump.is_some() && field.seeds.is_none() {
+        return Err(Error::new(field.ident.span(), "`bump` needs `seeds`"));
+    }
+    if field.stored_bump.is_some() && (field.seeds.is_none() || field.bump.is_some()) {
+        return Err(Error::new(field.ident.span(), "`stored_bump` needs `seeds` and excludes `bump`"));
+    }
+    Ok(field)
+}
+
+/// The checks of an account, in the order signer, writable, address, seeds, authority and constraints
+fn account_checks(field: &AccountField) -> TokenStream2 {
+    let ident = &field.ident;
+    let mut checks = vec![];
+    if field.signer {
+        checks.push(quote! { crate::checks::check_is_signer(#ident)?; });
+    }
+    if field.writable {
+        checks.push(quote! { crate::checks::check_is_writable(#ident)?; });
+    }
+    if let Some(address) = &field.address {
+        checks.push(quote! { crate::checks::check_pubkey_eq(#ident, &(#address))?; });
+    }
+    if let Some(seeds) = &field.seeds {
+        let seeds = seeds.iter().map(|seed| quote! { AsRef::<[u8]>::as_ref(&(#seed)) });
+        checks.push(match (&field.bump, &field.stored_bump) {
+            (_, Some(stored_bump)) => {
+                quote! { crate::checks::check_pda_stored_bump(#ident, &[#(#seeds),*], #stored_bump)?; }
+            }
+            (Some(bump), None) => quote! { let #bump = crate::checks::check_pda_cannonical_bump(#ident, &[#(#seeds),*])?; },
+            (None, None) => quote! { crate::checks::check_pda_cannonical_bump(#ident, &[#(#seeds),*])?; },
+        });
+    }
+    if let Some(authority) = &field.authority {
+        checks.push(quote! {
+            if #ident.key != &(#authority) {
+                ::solana_program::msg!("Expect account {} to have authority over {}", #ident.key, stringify!(#authority));
+                return Err(::solana_program::program_error::ProgramError::from(crate::error::BettingError::NoAuthority));
+            }
+        });
+    }
+    for (constraint, error) in &field.constraints {
+        checks.push(quote! {
+            if !(#constraint) {
+                ::solana_program::msg!("Expect account {} to satisfy {}", #ident.key, stringify!(#constraint));
+                return Err(::solana_program::program_error::ProgramError::from(#error));
+            }
+        });
+    }
+    quote! { #(#checks)* }
+}
+
+/// The IDL entry of an account, the shank instruction enum only declares the instructions
+fn idl_account(field: &AccountField) -> TokenStream2 {
+    let name = &field.name;
+    let desc = &field.desc;
+    let (is_mut, is_signer, is_optional) = (field.writable, field.signer, field.is_optional);
+    quote! {
+        crate::instructions::IdlAccount {
+            name: #name,
+            is_mut: #is_mut,
+            is_signer: #is_signer,
+            is_optional: #is_optional,
+            desc: #desc,
+        }
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let struct_ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new(input.span(), "expected named fields")),
+        },
+        _ => return Err(Error::new(input.span(), "expected a struct")),
+    };
+    let mut lifetimes = input.generics.lifetimes().map(|lifetime_def| &lifetime_def.lifetime);
+    let (slice_lifetime, info_lifetime) = match (lifetimes.next(), lifetimes.next()) {
+        (Some(slice_lifetime), Some(info_lifetime)) => (slice_lifetime, info_lifetime),
+        _ => {
+            return Err(Error::new(
+                input.generics.span(),
+                "expected the lifetimes of the account slice and of the account infos",
+            ))
+        }
+    };
+    // instruction arguments the checks may refer to
+    let mut args_ty: Option<Type> = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("accounts")) {
+        attr.parse_args_with(|input: ParseStream| {
+            let key: Ident = input.parse()?;
+            if key != "args" {
+                return Err(Error::new(key.span(), "unknown accounts argument"));
+            }
+            input.parse::<Token![=]>()?;
+            args_ty = Some(input.parse()?);
+            Ok(())
+        })?;
+    }
+
+    let mut accounts = vec![];
+    let mut states = vec![];
+    let mut plain_fields = vec![];
+    for field in fields {
+        let ident = field.ident.clone().unwrap();
+        if let Some(attr) = field.attrs.iter().find(|attr| attr.path.is_ident("account")) {
+            let args = attr.parse_args_with(Punctuated::<AccountArg, Token![,]>::parse_terminated)?;
+            accounts.push(parse_account_field(ident, &field.ty, args.into_iter().collect())?);
+        } else if let Some(attr) = field.attrs.iter().find(|attr| attr.path.is_ident("state")) {
+            states.push(StateField {
+                ident,
+                ty: field.ty.clone(),
+                account: attr.parse_args()?,
+            });
+        } else {
+            plain_fields.push(ident);
+        }
+    }
+    // the remaining fields must be the bumps of the seeds
+    for ident in &plain_fields {
+        if !accounts.iter().any(|account| account.bump.as_ref() == Some(ident)) {
+            return Err(Error::new(ident.span(), "expected `#[account(...)]`, `#[state(...)]` or a `bump` of the seeds"));
+        }
+    }
+    for state in &states {
+        let account = accounts.iter().find(|account| account.ident == state.account);
+        match account {
+            Some(account) if account.is_optional == option_inner(&state.ty).is_some() => {}
+            Some(_) => return Err(Error::new(state.ty.span(), "the state of an `Option` account must be an `Option`")),
+            None => return Err(Error::new(state.account.span(), "unknown account")),
+        }
+    }
+
+    let load_state = |state: &StateField| {
+        let ident = &state.ident;
+        let account = &state.account;
+        let ty = option_inner(&state.ty).unwrap_or(&state.ty);
+        match wrapped_type(ty, "RefMut") {
+            Some(ty) => quote! { let #ident = <#ty as crate::state::ZeroCopyAccount>::load_mut(#account)?; },
+            None => quote! { let #ident = <#ty as crate::state::BettingAccount>::try_from_account_info(#account)?; },
+        }
+    };
+    let mut body = vec![];
+    // get the required accounts and their states
+    for account in accounts.iter().filter(|account| !account.is_optional) {
+        let ident = &account.ident;
+        body.push(quote! { let #ident = ::solana_program::account_info::next_account_info(iter)?; });
+    }
+    for state in states.iter().filter(|state| option_inner(&state.ty).is_none()) {
+        body.push(load_state(state));
+    }
+    // check the required accounts
+    for account in accounts.iter().filter(|account| !account.is_optional) {
+        body.push(account_checks(account));
+    }
+    // get and check the optional accounts whose condition holds
+    for account in accounts.iter().filter(|account| account.is_optional) {
+        let ident = &account.ident;
+        let condition = account.condition.as_ref().unwrap();
+        let account_states: Vec<_> = states.iter().filter(|state| state.account == *ident).collect();
+        let state_idents: Vec<_> = account_states.iter().map(|state| &state.ident).collect();
+        let load_states = account_states.iter().map(|state| load_state(state));
+        let no_states = account_states.iter().map(|_| quote! { None });
+        let checks = account_checks(account);
+        body.push(quote! {
+            let (#ident, #(#state_idents),*) = if #condition {
+                let #ident = ::solana_program::account_info::next_account_info(iter)?;
+                #(#load_states)*
+                #checks
+                (Some(#ident), #(Some(#state_idents)),*)
+            } else {
+                (None, #(#no_states),*)
+            };
+        });
+    }
+    let field_idents = fields.iter().map(|field| field.ident.as_ref().unwrap());
+    let args_param = args_ty.map(|ty| quote! { , args: &#ty });
+    let idl_accounts = accounts.iter().map(idl_account);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #struct_ident #ty_generics #where_clause {
+            /// The accounts of the instruction in the IDL
+            pub const IDL_ACCOUNTS: &'static [crate::instructions::IdlAccount] = &[#(#idl_accounts),*];
+
+            pub fn try_from_accounts(
+                accounts: &#slice_lifetime [::solana_program::account_info::AccountInfo<#info_lifetime>] #args_param
+            ) -> Result<Self, ::solana_program::program_error::ProgramError> {
+                let iter = &mut accounts.iter();
+                #(#body)*
+
+                Ok(Self { #(#field_idents),* })
+            }
+        }
+    })
+}
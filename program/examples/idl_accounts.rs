@@ -0,0 +1,40 @@
+//! Prints the accounts of every instruction as the JSON object the IDL build merges into the shank IDL, see
+//! `.solitarc.js`.
+use vrf_betting::instructions::{IdlAccount, INSTRUCTION_ACCOUNTS};
+
+/// The camelCase name shank gives to the accounts in the IDL
+fn camel_case(name: &str) -> String {
+    let mut words = name.split('_');
+    let mut camel = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+fn account_json(account: &IdlAccount) -> String {
+    let optional = if account.is_optional { r#","optional":true"# } else { "" };
+    format!(
+        r#"{{"name":{:?},"isMut":{},"isSigner":{},"desc":{:?}{}}}"#,
+        camel_case(account.name),
+        account.is_mut,
+        account.is_signer,
+        account.desc,
+        optional
+    )
+}
+
+fn main() {
+    let instructions: Vec<String> = INSTRUCTION_ACCOUNTS
+        .iter()
+        .map(|(name, accounts)| {
+            let accounts: Vec<String> = accounts.iter().map(account_json).collect();
+            format!("{:?}:[{}]", name, accounts.join(","))
+        })
+        .collect();
+    println!("{{{}}}", instructions.join(","));
+}
@@ -0,0 +1,391 @@
+//! Keeper duties of the betting program: resolving the fullfilled bets and closing the ones marked for close.
+//!
+//! Both instructions are permissionless, the keeper signs them only to collect the crank tips of the resolves and the close
+//! tips of the closes.
+use std::collections::HashMap;
+
+use borsh::BorshSerialize;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use vrf_betting::{
+    constants::OPERATOR_PUBKEY,
+    instructions::BettingInstruction,
+    state::{game::Game, randomness::Randomness, stats::Stats, user_account::UserAccount, vrf_result::VrfResult, BettingAccount, StateAccountType},
+};
+
+/// Closes packed in a transaction, the resolves are sent one per transaction so a failing bet holds back no other
+pub const MAX_INSTRUCTIONS_PER_TRANSACTION: usize = 4;
+
+/// Deserialize the program accounts of the given type, skipping the ones that do not parse
+pub fn parse_accounts<T: BettingAccount>(accounts: &[(Pubkey, Account)]) -> Vec<(Pubkey, T)> {
+    accounts
+        .iter()
+        .filter(|(_, account)| account.owner == vrf_betting::id() && account.data.first() == Some(&(T::ACCOUNT_TYPE as u8)))
+        .filter_map(|(pubkey, account)| T::unpack(&account.data).ok().map(|state| (*pubkey, state)))
+        .collect()
+}
+
+/// Instruction of the betting program, serialized with the borsh version of the program rather than the one of the SDK
+fn betting_instruction(instruction: &BettingInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+    // the data of the instructions sent by the keeper is a single tag byte, their serialization never fails
+    Instruction::new_with_bytes(vrf_betting::id(), &instruction.try_to_vec().unwrap(), accounts)
+}
+
+/// Whether `GameResolveVrfResult` settles the bet, a hash chain bet only once its host revealed the server seed
+/// and an oracle bet only once enough oracles contributed, the keeper can not fullfill either of them
+pub fn is_resolvable(vrf_result_state: &VrfResult) -> bool {
+    if !vrf_result_state.is_fullfilled || vrf_result_state.is_used {
+        return false;
+    }
+    match vrf_result_state.randomness {
+        Randomness::Vrf | Randomness::HashChain { .. } => true,
+        Randomness::Oracles { .. } => vrf_result_state.randomness.is_complete(),
+    }
+}
+
+/// Resolve a fullfilled bet, the keeper signs after the accounts of the instruction to take the crank tip
+pub fn resolve_instruction(keeper: &Pubkey, vrf_result: &Pubkey, vrf_result_state: &VrfResult) -> Instruction {
+    let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), vrf_result_state.game.as_ref()], &vrf_betting::id());
+    let (bettor_user_account_pda, _) = UserAccount::find_pda(&vrf_result_state.owner);
+    let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &vrf_betting::id());
+    betting_instruction(
+        &BettingInstruction::GameResolveVrfResult,
+        vec![
+            AccountMeta::new(vrf_result_state.game, false),
+            AccountMeta::new(*vrf_result, false),
+            AccountMeta::new(game_bankroll_pda, false),
+            AccountMeta::new(bettor_user_account_pda, false),
+            AccountMeta::new(Stats::find_pda().0, false),
+            AccountMeta::new(bettor_user_limits_pda, false),
+            AccountMeta::new(OPERATOR_PUBKEY, false),
+            AccountMeta::new(*keeper, true),
+        ],
+    )
+}
+
+/// Close a settled bet marked for close, its rent goes back to the bettor's wallet but for the close tip of the keeper
+pub fn close_instruction(keeper: &Pubkey, vrf_result: &Pubkey, vrf_result_state: &VrfResult) -> Instruction {
+    betting_instruction(
+        &BettingInstruction::VrfResultClose,
+        vec![
+            AccountMeta::new(*vrf_result, false),
+            AccountMeta::new(vrf_result_state.owner, false),
+            AccountMeta::new(UserAccount::find_pda(&vrf_result_state.owner).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(*keeper, true),
+        ],
+    )
+}
+
+/// The resolves and closes due, bets of games that no longer exist are left alone since they can not be resolved
+pub fn duties(keeper: &Pubkey, vrf_results: &[(Pubkey, VrfResult)], games: &HashMap<Pubkey, Game>) -> (Vec<Instruction>, Vec<Instruction>) {
+    let mut vrf_results: Vec<_> = vrf_results.iter().collect();
+    // the resolves of a game follow each other, they write to the same game and bankroll
+    vrf_results.sort_by_key(|(_, vrf_result_state)| (vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id));
+    let resolves = vrf_results
+        .iter()
+        .filter(|(_, vrf_result_state)| is_resolvable(vrf_result_state) && games.contains_key(&vrf_result_state.game))
+        .map(|(vrf_result, vrf_result_state)| resolve_instruction(keeper, vrf_result, vrf_result_state))
+        .collect();
+    // a bet resolved in this round is closed in the next one, once its account shows it used
+    let closes = vrf_results
+        .iter()
+        .filter(|(_, vrf_result_state)| vrf_result_state.marked_for_close && vrf_result_state.is_fullfilled && vrf_result_state.is_used)
+        .map(|(vrf_result, vrf_result_state)| close_instruction(keeper, vrf_result, vrf_result_state))
+        .collect();
+    (resolves, closes)
+}
+
+/// Split closes into the transactions to send, a failing close only holds back its own batch
+pub fn batches(instructions: Vec<Instruction>) -> Vec<Vec<Instruction>> {
+    instructions.chunks(MAX_INSTRUCTIONS_PER_TRANSACTION).map(<[Instruction]>::to_vec).collect()
+}
+
+/// Filter of `getProgramAccounts` on the account type, the first byte of every state account
+pub fn account_type_bytes(account_type: StateAccountType) -> Vec<u8> {
+    vec![account_type as u8]
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use borsh::BorshSerialize;
+    use solana_program_test::{processor, tokio, BanksClient, ProgramTest};
+    use solana_sdk::{
+        account::Account, hash::hashv, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, rent::Rent, signature::Keypair, signer::Signer, transaction::Transaction,
+    };
+    use vrf_betting::{
+        constants::CLOSE_CRANK_TIP_LAMPORTS,
+        state::{
+            bankroll::GameBankroll,
+            game::{
+                coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+                BetInput, Game, GameTypeConfig,
+            },
+            randomness::{Randomness, RandomnessSourceConfig},
+            stats::Stats,
+            user_account::UserAccount,
+            vrf_result::VrfResult,
+            BettingAccount,
+        },
+    };
+
+    use super::{batches, duties, parse_accounts};
+
+    /// Deserialize a state account with the borsh version of the program, the banks client uses the one of the SDK
+    async fn get_state<T: BettingAccount>(banks_client: &mut BanksClient, pubkey: Pubkey) -> T {
+        T::unpack(&banks_client.get_account(pubkey).await.unwrap().unwrap().data).unwrap()
+    }
+
+    fn program_account<T: BorshSerialize>(state: &T, extra_lamports: u64) -> Account {
+        let data = state.try_to_vec().unwrap();
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()) + extra_lamports,
+            data,
+            owner: vrf_betting::id(),
+            ..Default::default()
+        }
+    }
+
+    fn coin_flip_game(host: Pubkey) -> Game {
+        Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        )
+    }
+
+    fn coin_flip_bet(bettor: Pubkey, game: Pubkey, bet_id: u32) -> VrfResult {
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game,
+            bet_id,
+            [0; 72],
+            2000,
+            1980,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state
+    }
+
+    #[tokio::test]
+    async fn test_keeper_resolves_and_closes_bets() {
+        let program_id = vrf_betting::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, processor!(vrf_betting::entrypoint::process_instruction));
+        // the state of the program as `getProgramAccounts` would return it
+        let mut program_accounts = vec![];
+
+        let keeper = Keypair::new();
+        program_test.add_account(
+            keeper.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let mut game_state = coin_flip_game(Pubkey::new_unique());
+        game_state.unresolved_vrf_result = 1;
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        program_accounts.push((game_pda, program_account(&game_state, 0)));
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, game_state.host);
+        game_bankroll_state.locked_lamports = 1980;
+        program_accounts.push((game_bankroll_pda, program_account(&game_bankroll_state, 0)));
+
+        // the crank tip of the unresolved bet is escrowed in the stats account
+        program_accounts.push((Stats::find_pda().0, program_account(&Stats::new(), 300)));
+
+        // a fullfilled bet to resolve
+        let bettor = Pubkey::new_unique();
+        let mut bettor_user_account_state = UserAccount::new(bettor, None, None);
+        bettor_user_account_state.active_vrf_results = 2;
+        program_accounts.push((UserAccount::find_pda(&bettor).0, program_account(&bettor_user_account_state, 0)));
+        let mut unresolved_vrf_result_state = coin_flip_bet(bettor, game_pda, 0);
+        unresolved_vrf_result_state.crank_tip_lamports = 300;
+        let (unresolved_vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 0);
+        program_accounts.push((unresolved_vrf_result_pda, program_account(&unresolved_vrf_result_state, 0)));
+        // a settled bet marked for close
+        let mut closable_vrf_result_state = coin_flip_bet(bettor, game_pda, 1);
+        closable_vrf_result_state.is_used = true;
+        closable_vrf_result_state.marked_for_close = true;
+        let (closable_vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 1);
+        let closable_vrf_result_account = program_account(&closable_vrf_result_state, 0);
+        let closable_vrf_result_rent = closable_vrf_result_account.lamports;
+        program_accounts.push((closable_vrf_result_pda, closable_vrf_result_account));
+        // a bet the operator has not fullfilled yet
+        let unfullfilled_vrf_result_state = VrfResult::new(bettor, game_pda, 2, [0; 72], 2000, 1980, unresolved_vrf_result_state.bet_input);
+        program_accounts.push((VrfResult::find_pda(&game_pda, &bettor, 2).0, program_account(&unfullfilled_vrf_result_state, 0)));
+
+        for (pubkey, account) in program_accounts.iter() {
+            program_test.add_account(*pubkey, account.clone());
+        }
+        let vrf_results = parse_accounts::<VrfResult>(&program_accounts);
+        let games: HashMap<Pubkey, Game> = parse_accounts::<Game>(&program_accounts).into_iter().collect();
+        assert_eq!(vrf_results.len(), 3);
+        assert_eq!(games.len(), 1);
+        let (resolves, closes) = duties(&keeper.pubkey(), &vrf_results, &games);
+        assert_eq!(resolves.len(), 1);
+        assert_eq!(closes.len(), 1);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        for resolve in resolves {
+            let mut transaction = Transaction::new_with_payer(&[resolve], Some(&payer.pubkey()));
+            transaction.sign(&[&keeper, &payer], recent_blockhash);
+            banks_client.process_transaction(transaction).await.unwrap();
+        }
+        for batch in batches(closes) {
+            let mut transaction = Transaction::new_with_payer(&batch, Some(&payer.pubkey()));
+            transaction.sign(&[&keeper, &payer], recent_blockhash);
+            banks_client.process_transaction(transaction).await.unwrap();
+        }
+
+        // the fullfilled bet should be resolved, the keeper paid its crank tip and the close tip of the other bet
+        let vrf_result_state: VrfResult = get_state(&mut banks_client, unresolved_vrf_result_pda).await;
+        assert!(vrf_result_state.is_used);
+        let keeper_account = banks_client.get_account(keeper.pubkey()).await.unwrap().unwrap();
+        assert_eq!(keeper_account.lamports, LAMPORTS_PER_SOL + 300 + CLOSE_CRANK_TIP_LAMPORTS);
+        // the bet marked for close should be closed, its rent refunded to the bettor but for the close tip
+        assert!(banks_client.get_account(closable_vrf_result_pda).await.unwrap().is_none());
+        let bettor_account = banks_client.get_account(bettor).await.unwrap().unwrap();
+        assert_eq!(bettor_account.lamports, closable_vrf_result_rent - CLOSE_CRANK_TIP_LAMPORTS);
+        let bettor_user_account_state: UserAccount = get_state(&mut banks_client, UserAccount::find_pda(&bettor).0).await;
+        assert_eq!(bettor_user_account_state.active_vrf_results, 1);
+        // the unfullfilled bet should be left alone
+        let game_state: Game = get_state(&mut banks_client, game_pda).await;
+        assert_eq!(game_state.unresolved_vrf_result, 0);
+    }
+
+    #[tokio::test]
+    async fn test_keeper_resolves_revealed_hash_chain_bets() {
+        let program_id = vrf_betting::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, processor!(vrf_betting::entrypoint::process_instruction));
+        let mut program_accounts = vec![];
+
+        let keeper = Keypair::new();
+        program_test.add_account(
+            keeper.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        // the host revealed the first link of the hash chain, not the second one
+        let server_seed = [9; 32];
+        let mut game_state = coin_flip_game(Pubkey::new_unique());
+        game_state
+            .set_randomness_source(RandomnessSourceConfig::HashChain {
+                head: hashv(&[&hashv(&[&server_seed]).to_bytes()]).to_bytes(),
+            })
+            .unwrap();
+        game_state.next_randomness().unwrap();
+        game_state.next_randomness().unwrap();
+        game_state.reveal_hash_chain_link(0, &hashv(&[&server_seed]).to_bytes()).unwrap();
+        game_state.unresolved_vrf_result = 3;
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        program_accounts.push((game_pda, program_account(&game_state, 0)));
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, game_state.host);
+        game_bankroll_state.locked_lamports = 3 * 1980;
+        program_accounts.push((game_bankroll_pda, program_account(&game_bankroll_state, 0)));
+        program_accounts.push((Stats::find_pda().0, program_account(&Stats::new(), 0)));
+
+        let bettor = Pubkey::new_unique();
+        let mut bettor_user_account_state = UserAccount::new(bettor, None, None);
+        bettor_user_account_state.active_vrf_results = 3;
+        program_accounts.push((UserAccount::find_pda(&bettor).0, program_account(&bettor_user_account_state, 0)));
+        // a bet fullfilled by the revealed link
+        let mut revealed_vrf_result_state = coin_flip_bet(bettor, game_pda, 0);
+        revealed_vrf_result_state.randomness = Randomness::HashChain { index: 0 };
+        revealed_vrf_result_state.beta[..32].copy_from_slice(&hashv(&[&server_seed]).to_bytes());
+        let (revealed_vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 0);
+        program_accounts.push((revealed_vrf_result_pda, program_account(&revealed_vrf_result_state, 0)));
+        // a bet waiting for its host to reveal the next link
+        let mut unrevealed_vrf_result_state = coin_flip_bet(bettor, game_pda, 1);
+        unrevealed_vrf_result_state.randomness = Randomness::HashChain { index: 1 };
+        unrevealed_vrf_result_state.is_fullfilled = false;
+        let (unrevealed_vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 1);
+        program_accounts.push((unrevealed_vrf_result_pda, program_account(&unrevealed_vrf_result_state, 0)));
+        // an oracle bet missing a contribution
+        let mut oracle_vrf_result_state = coin_flip_bet(bettor, game_pda, 2);
+        oracle_vrf_result_state.randomness = Randomness::Oracles {
+            threshold: 2,
+            contributions: 1,
+        };
+        let (oracle_vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 2);
+        program_accounts.push((oracle_vrf_result_pda, program_account(&oracle_vrf_result_state, 0)));
+
+        for (pubkey, account) in program_accounts.iter() {
+            program_test.add_account(*pubkey, account.clone());
+        }
+        let vrf_results = parse_accounts::<VrfResult>(&program_accounts);
+        let games: HashMap<Pubkey, Game> = parse_accounts::<Game>(&program_accounts).into_iter().collect();
+        let (resolves, _) = duties(&keeper.pubkey(), &vrf_results, &games);
+        assert_eq!(resolves.len(), 1);
+        assert_eq!(resolves[0].accounts[1].pubkey, revealed_vrf_result_pda);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        for resolve in resolves {
+            let mut transaction = Transaction::new_with_payer(&[resolve], Some(&payer.pubkey()));
+            transaction.sign(&[&keeper, &payer], recent_blockhash);
+            banks_client.process_transaction(transaction).await.unwrap();
+        }
+
+        // the revealed bet should be resolved, the others left to their host and oracles
+        let vrf_result_state: VrfResult = get_state(&mut banks_client, revealed_vrf_result_pda).await;
+        assert!(vrf_result_state.is_used);
+        for vrf_result_pda in [unrevealed_vrf_result_pda, oracle_vrf_result_pda] {
+            let vrf_result_state: VrfResult = get_state(&mut banks_client, vrf_result_pda).await;
+            assert!(!vrf_result_state.is_used);
+        }
+        let game_state: Game = get_state(&mut banks_client, game_pda).await;
+        assert_eq!(game_state.unresolved_vrf_result, 2);
+    }
+
+    #[test]
+    fn test_keeper_skips_bets_of_missing_games() {
+        let bettor = Pubkey::new_unique();
+        let game = Pubkey::new_unique();
+        let vrf_results = vec![(VrfResult::find_pda(&game, &bettor, 0).0, coin_flip_bet(bettor, game, 0))];
+        let (resolves, closes) = duties(&Pubkey::new_unique(), &vrf_results, &HashMap::new());
+        assert!(resolves.is_empty());
+        assert!(closes.is_empty());
+    }
+
+    #[test]
+    fn test_keeper_batches() {
+        let bettor = Pubkey::new_unique();
+        let game = Pubkey::new_unique();
+        let vrf_results: Vec<_> = (0..9)
+            .map(|bet_id| {
+                let mut vrf_result_state = coin_flip_bet(bettor, game, bet_id);
+                vrf_result_state.is_used = true;
+                vrf_result_state.marked_for_close = true;
+                (VrfResult::find_pda(&game, &bettor, bet_id).0, vrf_result_state)
+            })
+            .collect();
+        let (_, closes) = duties(&Pubkey::new_unique(), &vrf_results, &HashMap::new());
+        // the batches hold at most the instructions that fit a transaction
+        assert_eq!(batches(closes).iter().map(Vec::len).collect::<Vec<_>>(), [4, 4, 1]);
+    }
+}
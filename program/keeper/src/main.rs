@@ -0,0 +1,85 @@
+//! Keeper bot resolving the fullfilled bets of the betting program and closing the ones marked for close.
+//!
+//! Usage: `vrf-betting-keeper <rpc_url> <keypair_path> [interval_seconds]`
+use std::{collections::HashMap, env, process, thread, time::Duration};
+
+use solana_client::{
+    client_error::ClientError,
+    rpc_client::RpcClient,
+    rpc_config::RpcProgramAccountsConfig,
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+    transaction::Transaction,
+};
+use vrf_betting::state::{game::Game, vrf_result::VrfResult, StateAccountType};
+use vrf_betting_keeper::{account_type_bytes, batches, duties, parse_accounts};
+
+const DEFAULT_INTERVAL_SECONDS: u64 = 10;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Usage: {} <rpc_url> <keypair_path> [interval_seconds]", args[0]);
+        process::exit(1);
+    }
+    let rpc_client = RpcClient::new_with_commitment(args[1].clone(), CommitmentConfig::confirmed());
+    let keeper = read_keypair_file(&args[2]).unwrap_or_else(|err| {
+        eprintln!("Can not read keypair {}: {}", args[2], err);
+        process::exit(1);
+    });
+    let interval = match args.get(3).map(|interval| interval.parse()) {
+        Some(Ok(interval)) => interval,
+        Some(Err(err)) => {
+            eprintln!("Invalid interval {}: {}", args[3], err);
+            process::exit(1);
+        }
+        None => DEFAULT_INTERVAL_SECONDS,
+    };
+    println!("Keeper {} running every {} seconds", keeper.pubkey(), interval);
+    loop {
+        if let Err(err) = run_round(&rpc_client, &keeper) {
+            eprintln!("Keeper round failed: {}", err);
+        }
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+/// Program accounts of the given type, filtered by the RPC node on their first byte, the client error is boxed as it is large
+fn program_accounts(rpc_client: &RpcClient, account_type: StateAccountType) -> Result<Vec<(Pubkey, Account)>, Box<ClientError>> {
+    rpc_client
+        .get_program_accounts_with_config(
+            &vrf_betting::id(),
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, account_type_bytes(account_type)))]),
+                ..Default::default()
+            },
+        )
+        .map_err(Box::new)
+}
+
+/// Scan the bets once and send the resolves before the closes
+fn run_round(rpc_client: &RpcClient, keeper: &Keypair) -> Result<(), Box<ClientError>> {
+    let vrf_results = parse_accounts::<VrfResult>(&program_accounts(rpc_client, StateAccountType::Vrf)?);
+    let games: HashMap<Pubkey, Game> = parse_accounts::<Game>(&program_accounts(rpc_client, StateAccountType::Game)?)
+        .into_iter()
+        .collect();
+    let (resolves, closes) = duties(&keeper.pubkey(), &vrf_results, &games);
+    println!("{} bets to resolve, {} bets to close", resolves.len(), closes.len());
+    // every resolve goes in its own transaction, a bet failing to resolve does not hold back the others
+    let resolves = resolves.into_iter().map(|resolve| vec![resolve]);
+    for batch in resolves.chain(batches(closes)) {
+        let transaction = Transaction::new_signed_with_payer(&batch, Some(&keeper.pubkey()), &[keeper], rpc_client.get_latest_blockhash()?);
+        // a transaction failing, usually raced by another keeper, does not hold back the next ones
+        match rpc_client.send_and_confirm_transaction(&transaction) {
+            Ok(signature) => println!("Sent {}", signature),
+            Err(err) => eprintln!("Batch of {} instructions failed: {}", batch.len(), err),
+        }
+    }
+    Ok(())
+}
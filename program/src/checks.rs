@@ -27,6 +27,16 @@ pub fn check_pda_cannonical_bump(account_info: &AccountInfo, seeds: &[&[u8]]) ->
         Err(ProgramError::from(BettingError::WrongPDA))
     }
 }
+/// Verify a PDA with the bump stored in its account, a single hash where finding the canonical bump may take up to 255
+pub fn check_pda_stored_bump(account_info: &AccountInfo, seeds: &[&[u8]], bump: u8) -> ProgramResult {
+    match Pubkey::create_program_address(&[seeds, &[&[bump]]].concat(), &crate::id()) {
+        Ok(pda) if account_info.key == &pda => Ok(()),
+        _ => {
+            msg!("Expect account {} to be the PDA of its seeds and bump {}", account_info.key, bump);
+            Err(ProgramError::from(BettingError::WrongPDA))
+        }
+    }
+}
 pub fn check_pubkey_eq(account_info: &AccountInfo, expected: &Pubkey) -> ProgramResult {
     if account_info.key == expected {
         Ok(())
@@ -6,3 +6,31 @@ pub const OPERATOR_PUBKEY: Pubkey = Pubkey::new_from_array([
 
 pub const PROFIT_SHARE: u64 = 100;
 pub const REFERRAL_SHARE: u64 = 5000;
+
+pub const MIN_USERNAME_LEN: usize = 3;
+pub const MAX_USERNAME_LEN: usize = 32;
+pub const MAX_AVATAR_URI_LEN: usize = 200;
+
+pub const MAX_REFERRAL_TIERS: usize = 8;
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+pub const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+pub const LIMIT_WINDOW_BUCKETS: usize = 24;
+pub const LIMIT_LOOSEN_DELAY: i64 = SECONDS_PER_DAY;
+
+pub const MAX_MANAGEMENT_FEE: u64 = 2000;
+
+pub const MAX_COMMISSION: u64 = 1000;
+pub const COMMISSION_OPERATOR_SHARE: u64 = 2000;
+
+pub const MAX_GAME_TYPE_CONFIG_LEN: usize = 31;
+
+pub const MAX_ORACLES: usize = 8;
+pub const HASH_CHAIN_REVEAL_TIMEOUT: i64 = 5 * 60;
+
+pub const MAX_CRANK_TIP_LAMPORTS: u64 = 10_000_000;
+// paid out of the rent of a VRF result marked for close to whoever closes it, about the fee of a signature
+pub const CLOSE_CRANK_TIP_LAMPORTS: u64 = 5_000;
+
+pub const MAX_CALLBACK_ACCOUNTS: usize = 8;
+pub const MAX_CALLBACK_DATA_LEN: usize = 64;
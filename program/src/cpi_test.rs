@@ -0,0 +1,201 @@
+//! A vault program betting from its PDA through CPI, the way other programs are expected to call the betting program.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program, sysvar,
+};
+use solana_program_test::{processor, tokio, ProgramTest};
+use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+use crate::{
+    constants::OPERATOR_PUBKEY,
+    instructions::{game_place_bet::GamePlaceBetArgs, vrf_result_fullfill::VrfResultFullfillArgs, BettingInstruction},
+    state::{
+        bankroll::GameBankroll,
+        game::{
+            coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+            BetInput, Game, GameTypeConfig,
+        },
+        operator_bond::{OperatorBond, OperatorBondConfig},
+        stats::Stats,
+        user_account::UserAccount,
+        vrf_result::VrfResult,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize)]
+enum VaultInstruction {
+    PlaceBet { bet_input: BetInput },
+    Resolve,
+}
+
+/// Forward the accounts after the betting program to it, the vault PDA signing for itself
+fn process_vault_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (vault, vault_bump) = Pubkey::find_program_address(&[b"Vault".as_ref()], program_id);
+    let account_metas = accounts[1..]
+        .iter()
+        .map(|account_info| AccountMeta {
+            pubkey: *account_info.key,
+            is_signer: account_info.is_signer || account_info.key == &vault,
+            is_writable: account_info.is_writable,
+        })
+        .collect();
+    let betting_instruction = match VaultInstruction::try_from_slice(data)? {
+        VaultInstruction::PlaceBet { bet_input } => BettingInstruction::GamePlaceBet {
+            args: GamePlaceBetArgs {
+                bet_input,
+                close_on_resolve: false,
+                crank_tip_lamports: 0,
+            },
+        },
+        VaultInstruction::Resolve => BettingInstruction::GameResolveVrfResult,
+    };
+    invoke_signed(
+        &Instruction::new_with_borsh(crate::id(), &betting_instruction, account_metas),
+        accounts,
+        &[&[b"Vault".as_ref(), &[vault_bump]]],
+    )
+}
+
+fn add_account<T: BorshSerialize>(program_test: &mut ProgramTest, pubkey: Pubkey, state: &T) {
+    let data = state.try_to_vec().unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: crate::id(),
+            ..Default::default()
+        },
+    );
+}
+
+#[tokio::test]
+async fn test_cpi_place_and_resolve_bet_from_pda() {
+    let program_id = crate::id();
+    let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+    let vault_program_id = Pubkey::new_unique();
+    program_test.add_program("vault", vault_program_id, processor!(process_vault_instruction));
+
+    let key_file_path = home::home_dir().unwrap().join(".config/solana/id.json");
+    let operator = read_keypair_file(key_file_path).unwrap();
+    program_test.add_account(
+        operator.pubkey(),
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            ..Default::default()
+        },
+    );
+
+    let (vault, _) = Pubkey::find_program_address(&[b"Vault".as_ref()], &vault_program_id);
+    let (vault_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), vault.as_ref()], &program_id);
+    let mut vault_user_account_state = UserAccount::new(vault, None, None);
+    vault_user_account_state.current_lamports = LAMPORTS_PER_SOL;
+    add_account(&mut program_test, vault_user_account_pda, &vault_user_account_state);
+
+    let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+    add_account(&mut program_test, stats_pda, &Stats::new());
+
+    let host = Pubkey::new_unique();
+    let game_state = Game::new(
+        host,
+        1000,
+        10000,
+        GameTypeConfig::CoinFlip {
+            config: CoinFlipConfig {
+                host_probability_advantage: 100,
+                payout_rate: 9900,
+            },
+        },
+    );
+    let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+    add_account(&mut program_test, game_pda, &game_state);
+
+    let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+    let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+    game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+    add_account(&mut program_test, game_bankroll_pda, &game_bankroll_state);
+
+    let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &vault, 0);
+    let (vault_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), vault_user_account_pda.as_ref()], &program_id);
+    // the operator bond gates the bets settled with the VRF, its minimum is left at 0
+    let (operator_bond_pda, operator_bond_bump) = Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &program_id);
+    add_account(
+        &mut program_test,
+        operator_bond_pda,
+        &OperatorBond::new(OperatorBondConfig::default(), operator_bond_bump),
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            Instruction::new_with_borsh(
+                vault_program_id,
+                &VaultInstruction::PlaceBet {
+                    bet_input: BetInput::CoinFlip {
+                        input: CoinFlipInput {
+                            wager: 2000,
+                            side: CoinFlipSide::Head,
+                        },
+                    },
+                },
+                vec![
+                    AccountMeta::new_readonly(program_id, false),
+                    AccountMeta::new_readonly(vault, false),
+                    AccountMeta::new(vault_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(vault_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
+                    // the vault holds no lamports, the payer of the transaction pays for the rent
+                    AccountMeta::new(payer.pubkey(), true),
+                ],
+            ),
+            Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::VrfResultFullfill {
+                    args: VrfResultFullfillArgs { beta: [1; 64], pi: [2; 80] },
+                },
+                vec![AccountMeta::new_readonly(operator.pubkey(), true), AccountMeta::new(vrf_result_pda, false)],
+            ),
+            Instruction::new_with_borsh(
+                vault_program_id,
+                &VaultInstruction::Resolve,
+                vec![
+                    AccountMeta::new_readonly(program_id, false),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(vault_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(vault_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                ],
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&operator, &payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // the bet of the vault should be placed and settled
+    let vault_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(vault_user_account_pda).await.unwrap();
+    assert_eq!(vault_user_account_state.total_bets, 1);
+    let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
+    assert_eq!(vrf_result_state.owner, vault);
+    assert!(vrf_result_state.is_used);
+    let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
+    assert_eq!(game_state.unresolved_vrf_result, 0);
+    // the vault never held lamports
+    assert!(banks_client.get_account(vault).await.unwrap().is_none());
+}
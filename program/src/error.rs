@@ -36,6 +36,40 @@ pub enum BettingError {
     VrfResultNotMarkedForClose,
     #[error("GameNotSettled")]
     GameNotSettled,
+    #[error("InvalidUsername")]
+    InvalidUsername,
+    #[error("InvalidAvatarUri")]
+    InvalidAvatarUri,
+    #[error("UsernameTaken")]
+    UsernameTaken,
+    #[error("InvalidReferralTiers")]
+    InvalidReferralTiers,
+    #[error("InvalidReferral")]
+    InvalidReferral,
+    #[error("SelfExcluded")]
+    SelfExcluded,
+    #[error("LimitExceeded")]
+    LimitExceeded,
+    #[error("MathOverflow")]
+    MathOverflow,
+    #[error("WrongRandomnessSource")]
+    WrongRandomnessSource,
+    #[error("InvalidHashChainLink")]
+    InvalidHashChainLink,
+    #[error("InvalidOracleConfig")]
+    InvalidOracleConfig,
+    #[error("RevealNotTimedOut")]
+    RevealNotTimedOut,
+    #[error("InvalidOperatorBondConfig")]
+    InvalidOperatorBondConfig,
+    #[error("OperatorBondTooLow")]
+    OperatorBondTooLow,
+    #[error("FullfillNotLate")]
+    FullfillNotLate,
+    #[error("OperatorBondWithdrawalLocked")]
+    OperatorBondWithdrawalLocked,
+    #[error("InvalidCallback")]
+    InvalidCallback,
 }
 impl PrintProgramError for BettingError {
     fn print<E>(&self)
@@ -0,0 +1,54 @@
+//! Events logged with `sol_log_data`, the first field is the event name and the second its borsh serialization.
+//!
+//! They keep the outcome of bets whose VRF result is closed on resolution, indexers read them from the transaction logs.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::{game::BetInput, randomness::Randomness, vrf_result::VrfResult};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct BetResolved {
+    pub vrf_result: Pubkey,
+    pub owner: Pubkey,
+    pub game: Pubkey,
+    pub bet_id: u32,
+    pub bet_input: BetInput,
+    pub randomness: Randomness,
+    pub beta: [u8; 64],
+    pub locked_bettor_lamports: u64,
+    pub locked_host_lamports: u64,
+    // paid to the bettor, commissions excluded
+    pub bettor_gain: u64,
+    pub host_gain: u64,
+    pub slashed_lamports: u64,
+    pub crank_tip_lamports: u64,
+    // whether the VRF result was closed, in which case this event is the only record of the outcome
+    pub is_closed: bool,
+}
+
+impl BetResolved {
+    pub const NAME: &'static [u8] = b"BetResolved";
+
+    pub fn new(vrf_result: Pubkey, vrf_result_state: &VrfResult, bettor_gain: u64, host_gain: u64) -> Self {
+        Self {
+            vrf_result,
+            owner: vrf_result_state.owner,
+            game: vrf_result_state.game,
+            bet_id: vrf_result_state.bet_id,
+            bet_input: vrf_result_state.bet_input,
+            randomness: vrf_result_state.randomness,
+            beta: vrf_result_state.beta,
+            locked_bettor_lamports: vrf_result_state.locked_bettor_lamports,
+            locked_host_lamports: vrf_result_state.locked_host_lamports,
+            bettor_gain,
+            host_gain,
+            slashed_lamports: vrf_result_state.slashed_lamports,
+            crank_tip_lamports: vrf_result_state.crank_tip_lamports,
+            is_closed: vrf_result_state.close_on_resolve,
+        }
+    }
+    pub fn emit(&self) -> Result<(), ProgramError> {
+        sol_log_data(&[Self::NAME, &self.try_to_vec()?]);
+        Ok(())
+    }
+}
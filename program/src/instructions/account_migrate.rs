@@ -0,0 +1,277 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction,
+    system_program, sysvar::Sysvar,
+};
+use vrf_betting_derive::Accounts;
+
+use crate::state::{
+    bankroll::{BankrollShare, GameBankroll},
+    game::Game,
+    operator_bond::OperatorBond,
+    randomness::OracleConfig,
+    randomness_request::RandomnessRequest,
+    referral::{Referral, ReferralConfig},
+    stats::Stats,
+    user_account::UserAccount,
+    user_limits::UserLimits,
+    username::UsernameRecord,
+    vrf_result::VrfResult,
+    BettingAccount, StateAccountType,
+};
+
+#[derive(Accounts)]
+pub struct AccountMigrateAccounts<'a, 'info> {
+    #[account(signer, writable, name = "payer", desc = "The account paying for the additional rent")]
+    pub payer_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "account", desc = "The state account to migrate to the latest version")]
+    pub account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+}
+
+pub fn account_migrate(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    msg!("Instruction: AccountMigrate");
+    // get accounts
+    let AccountMigrateAccounts {
+        payer_account_info,
+        account_info,
+        system_program_account_info,
+    } = AccountMigrateAccounts::try_from_accounts(accounts)?;
+    // migrate according to the account type
+    let account_type = match account_info.data.borrow().first() {
+        Some(account_type) => StateAccountType::try_from_slice(&[*account_type])?,
+        None => {
+            msg!("Expect account {} to be initialized", account_info.key);
+            return Err(ProgramError::UninitializedAccount);
+        }
+    };
+    match account_type {
+        StateAccountType::Stats => migrate::<Stats>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::UserAccount => migrate::<UserAccount>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::Vrf => migrate::<VrfResult>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::Game => migrate::<Game>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::Username => migrate::<UsernameRecord>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::Referral => migrate::<Referral>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::ReferralConfig => migrate::<ReferralConfig>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::UserLimits => migrate::<UserLimits>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::GameBankroll => migrate::<GameBankroll>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::BankrollShare => migrate::<BankrollShare>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::OracleConfig => migrate::<OracleConfig>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::OperatorBond => migrate::<OperatorBond>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::RandomnessRequest => migrate::<RandomnessRequest>(payer_account_info, account_info, system_program_account_info),
+        StateAccountType::Uninitialized => {
+            msg!("Expect account {} to be initialized", account_info.key);
+            Err(ProgramError::UninitializedAccount)
+        }
+    }
+}
+
+fn migrate<'a, T: BettingAccount + BorshSerialize>(
+    payer_account_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    system_program_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    // deserializing upgrades the state to the latest version
+    let state = T::try_from_account_info(account_info)?;
+    let data = state.try_to_vec()?;
+    if account_info.data.borrow()[..] == data[..] {
+        msg!("Account {} is already at version {}", account_info.key, T::VERSION);
+        return Ok(());
+    }
+    // resize the account and pay for the rent, the rent released by a smaller layout stays in the account,
+    // the payer can be anyone and is not entitled to it
+    let rent = Rent::get()?;
+    account_info.realloc(data.len(), false)?;
+    let min_rent = rent.minimum_balance(data.len());
+    if account_info.lamports() < min_rent {
+        let rent_transfer_ix = system_instruction::transfer(payer_account_info.key, account_info.key, min_rent - account_info.lamports());
+        invoke(
+            &rent_transfer_ix,
+            &[payer_account_info.clone(), account_info.clone(), system_program_account_info.clone()],
+        )?;
+    }
+    // save state
+    account_info.data.borrow_mut().copy_from_slice(&data);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            legacy::{UserAccountV0, UserAccountV3},
+            user_account::UserAccount,
+            BettingAccount, StateAccountType,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_account_migrate_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let payer_wallet = Keypair::new();
+        program_test.add_account(
+            payer_wallet.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let user = Pubkey::new_unique();
+        let (user_account_pda, user_account_bump) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.as_ref()], &program_id);
+        let user_account_state = UserAccountV0 {
+            account_type: StateAccountType::UserAccount,
+            authority: user,
+            total_bets: 2,
+            current_lamports: 10000,
+            lamports_deposited: 20000,
+            lamports_withdrew: 10000,
+            active_vrf_results: 1,
+            games_hosted: 0,
+            referral: None,
+            username: Some("Username".to_string()),
+        };
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        let migrated_data_len = UserAccount::unpack(&user_account_data).unwrap().try_to_vec().unwrap().len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::AccountMigrate,
+                vec![
+                    AccountMeta::new(payer_wallet.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer_wallet, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the user account should be rewritten with the latest layout
+        let user_account = banks_client.get_account(user_account_pda).await.unwrap().unwrap();
+        assert_eq!(user_account.data.len(), migrated_data_len);
+        assert_eq!(user_account.lamports, Rent::default().minimum_balance(migrated_data_len));
+        let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
+        assert_eq!(user_account_state.version, UserAccount::VERSION);
+        assert_eq!(user_account_state.bump, user_account_bump);
+        assert_eq!(user_account_state.authority, user);
+        assert_eq!(user_account_state.total_bets, 2);
+        assert_eq!(user_account_state.current_lamports, 10000);
+        assert_eq!(user_account_state.lamports_deposited, 20000);
+        assert_eq!(user_account_state.lamports_withdrew, 10000);
+        assert_eq!(user_account_state.active_vrf_results, 1);
+        assert_eq!(user_account_state.username(), Some("Username"));
+        // the rent should be paid by the payer
+        let payer_wallet_account = banks_client.get_account(payer_wallet.pubkey()).await.unwrap().unwrap();
+        assert_eq!(
+            payer_wallet_account.lamports,
+            LAMPORTS_PER_SOL - (Rent::default().minimum_balance(migrated_data_len) - Rent::default().minimum_balance(user_account_data_len))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_account_migrate_success_shrink_by_third_party() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        // a wallet other than the authority of the user account
+        let payer_wallet = Keypair::new();
+        program_test.add_account(
+            payer_wallet.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        // a full profile takes more space than the fixed-layout fields
+        let user = Pubkey::new_unique();
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.as_ref()], &program_id);
+        let user_account_state = UserAccountV3 {
+            account_type: StateAccountType::UserAccount,
+            version: 3,
+            authority: user,
+            total_bets: 0,
+            current_lamports: 0,
+            lamports_deposited: 0,
+            lamports_withdrew: 0,
+            active_vrf_results: 0,
+            games_hosted: 0,
+            referral: Some(Pubkey::new_unique()),
+            username: Some("u".repeat(32)),
+            avatar_uri: Some("a".repeat(200)),
+            display_flags: 0,
+            betting_pnl: 0,
+            hosting_pnl: 0,
+            profit_high_water_mark: 0,
+        };
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        let migrated_data_len = UserAccount::unpack(&user_account_data).unwrap().try_to_vec().unwrap().len();
+        assert!(migrated_data_len < user_account_data_len);
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::AccountMigrate,
+                vec![
+                    AccountMeta::new(payer_wallet.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer_wallet, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the rent released by the smaller layout should stay in the user account, the payer gets nothing
+        let user_account = banks_client.get_account(user_account_pda).await.unwrap().unwrap();
+        assert_eq!(user_account.data.len(), migrated_data_len);
+        assert_eq!(user_account.lamports, Rent::default().minimum_balance(user_account_data_len));
+        let payer_wallet_account = banks_client.get_account(payer_wallet.pubkey()).await.unwrap().unwrap();
+        assert_eq!(payer_wallet_account.lamports, LAMPORTS_PER_SOL);
+        let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
+        assert_eq!(user_account_state.username(), Some("u".repeat(32).as_str()));
+        assert_eq!(user_account_state.avatar_uri(), Some("a".repeat(200).as_str()));
+    }
+}
@@ -0,0 +1,153 @@
+use borsh::BorshSerialize;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    math::{to_signed, CheckedMath},
+    state::{bankroll::GameBankroll, user_account::UserAccount},
+};
+
+#[derive(Accounts)]
+pub struct GameClaimBankrollFeeAccounts<'a, 'info> {
+    #[account(
+        signer,
+        address = host_user_account_state.authority,
+        authority = game_bankroll_state.host,
+        name = "host",
+        desc = "The wallet account of the host"
+    )]
+    pub host_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", host_user_account_state.authority],
+        stored_bump = host_user_account_state.bump,
+        name = "host_user_account",
+        desc = "User Betting Account of the host"
+    )]
+    pub host_user_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_bankroll_state.game],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[state(host_user_account_info)]
+    pub host_user_account_state: UserAccount,
+    #[state(game_bankroll_account_info)]
+    pub game_bankroll_state: GameBankroll,
+}
+
+pub fn game_claim_bankroll_fee(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    msg!("Instruction: GameClaimBankrollFee");
+    // get accounts
+    let GameClaimBankrollFeeAccounts {
+        host_user_account_info,
+        game_bankroll_account_info,
+        mut host_user_account_state,
+        mut game_bankroll_state,
+        ..
+    } = GameClaimBankrollFeeAccounts::try_from_accounts(accounts)?;
+    // update host user account
+    host_user_account_state.current_lamports = host_user_account_state.current_lamports.try_add(game_bankroll_state.unclaimed_fee_lamports)?;
+    host_user_account_state.hosting_pnl = host_user_account_state
+        .hosting_pnl
+        .try_add(to_signed(game_bankroll_state.unclaimed_fee_lamports)?)?;
+    host_user_account_state.serialize(&mut &mut host_user_account_info.data.borrow_mut()[..])?;
+    // update game bankroll account
+    game_bankroll_state.unclaimed_fee_lamports = 0;
+    game_bankroll_state.serialize(&mut &mut game_bankroll_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{bankroll::GameBankroll, user_account::UserAccount},
+    };
+
+    async fn run_game_claim_bankroll_fee(is_host: bool) -> (UserAccount, GameBankroll) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let user_account_state = UserAccount::new(user.pubkey(), None, None);
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let game_pda = Pubkey::new_unique();
+        let host = if is_host { user.pubkey() } else { Pubkey::new_unique() };
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 6000;
+        game_bankroll_state.unclaimed_fee_lamports = 200;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameClaimBankrollFee,
+                vec![
+                    AccountMeta::new_readonly(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        (
+            banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap(),
+            banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_game_claim_bankroll_fee_success() {
+        let (host_user_account_state, game_bankroll_state) = run_game_claim_bankroll_fee(true).await;
+        // the fee should be moved to the host balance, the staked lamports are untouched
+        assert_eq!(host_user_account_state.current_lamports, 200);
+        assert_eq!(game_bankroll_state.unclaimed_fee_lamports, 0);
+        assert_eq!(game_bankroll_state.current_lamports, 6000);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(0)")]
+    async fn test_game_claim_bankroll_fee_err_not_host() {
+        run_game_claim_bankroll_fee(false).await;
+    }
+}
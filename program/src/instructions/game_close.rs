@@ -1,78 +1,105 @@
 use borsh::BorshSerialize;
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-    system_program,
-};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, system_program};
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
     error::BettingError,
-    state::{game::Game, stats::Stats, user_account::UserAccount, BettingAccount},
+    math::{to_signed, CheckedMath},
+    state::{bankroll::GameBankroll, game::Game, stats::Stats, user_account::UserAccount, BettingAccount},
 };
 
+#[derive(Accounts)]
+pub struct GameCloseAccounts<'a, 'info> {
+    #[account(
+        signer,
+        writable,
+        address = host_user_account_state.authority,
+        authority = game_account_state.host,
+        name = "host",
+        desc = "The wallet account of the host"
+    )]
+    pub host_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", host_user_account_state.authority],
+        stored_bump = host_user_account_state.bump,
+        name = "host_user_account",
+        desc = "User Betting Account of the host"
+    )]
+    pub host_user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_account_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_account_state.common_config_seed(), game_account_state.game_type_config_seed()],
+        stored_bump = game_account_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_account_info.key],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account, may be uninitialized"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[state(host_user_account_info)]
+    pub host_user_account_state: UserAccount,
+    #[state(stats_account_info)]
+    pub stats_account_state: Stats,
+    #[state(game_account_info)]
+    pub game_account_state: Game,
+}
+
 pub fn game_close(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     msg!("Instruction: GameClose");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let host_wallet_account_info = next_account_info(iter)?;
-    let host_user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let game_account_info = next_account_info(iter)?;
-    let system_program_account_info = next_account_info(iter)?;
-
-    let mut host_user_account_state = UserAccount::try_from_account_info(host_user_account_info)?;
-    let mut stats_account_state = Stats::try_from_account_info(stats_account_info)?;
-    let game_account_state = Game::try_from_account_info(game_account_info)?;
-    // check accounts
-    check_is_signer(host_wallet_account_info)?;
-    check_is_writable(host_wallet_account_info)?;
-    check_pubkey_eq(host_wallet_account_info, &host_user_account_state.authority)?;
-
-    check_is_writable(host_user_account_info)?;
-    check_pda_cannonical_bump(host_user_account_info, &[b"UserAccount".as_ref(), host_user_account_state.authority.as_ref()])?;
-
-    check_is_writable(stats_account_info)?;
-    check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    check_is_writable(game_account_info)?;
-    let game_common_config_vec = game_account_state.common_config.try_to_vec()?;
-    let game_type_config_vec = game_account_state.game_type_config.try_to_vec()?;
-    check_pda_cannonical_bump(
+    let GameCloseAccounts {
+        host_wallet_account_info,
+        host_user_account_info,
+        stats_account_info,
         game_account_info,
-        &[b"Game".as_ref(), game_common_config_vec.as_slice(), game_type_config_vec.as_slice()],
-    )?;
-
-    check_pubkey_eq(system_program_account_info, &system_program::ID)?;
-    // check authority
-    if &game_account_state.host != host_wallet_account_info.key {
-        msg!(
-            "Expect account {} to have authority over game account {}",
-            host_wallet_account_info.key,
-            game_account_info.key
-        );
-        return Err(ProgramError::from(BettingError::NoAuthority));
-    }
+        game_bankroll_account_info,
+        mut host_user_account_state,
+        mut stats_account_state,
+        game_account_state,
+        ..
+    } = GameCloseAccounts::try_from_accounts(accounts)?;
     // check game close conditions
     if game_account_state.unresolved_vrf_result > 0 {
         msg!("Game {} is not settled");
         return Err(ProgramError::from(BettingError::GameNotSettled));
     }
 
+    // close game bankroll account once the stakers have withdrawn, returning the remainder and the fee to the host
+    if !game_bankroll_account_info.data_is_empty() {
+        let game_bankroll_state = GameBankroll::try_from_account_info(game_bankroll_account_info)?;
+        if game_bankroll_state.total_shares > 0 {
+            msg!("Game bankroll {} is kept open for the stakers to withdraw", game_bankroll_account_info.key);
+        } else {
+            let returned_lamports = game_bankroll_state.current_lamports.try_add(game_bankroll_state.unclaimed_fee_lamports)?;
+            host_user_account_state.current_lamports = host_user_account_state.current_lamports.try_add(returned_lamports)?;
+            host_user_account_state.hosting_pnl = host_user_account_state.hosting_pnl.try_add(to_signed(returned_lamports)?)?;
+
+            game_bankroll_account_info.data.borrow_mut().fill(0);
+            game_bankroll_account_info.realloc(0, false)?;
+            **host_wallet_account_info.lamports.borrow_mut() = host_wallet_account_info.lamports().try_add(game_bankroll_account_info.lamports())?;
+            **game_bankroll_account_info.lamports.borrow_mut() = 0;
+        }
+    }
     // update host user account
-    host_user_account_state.games_hosted -= 1;
+    host_user_account_state.games_hosted = host_user_account_state.games_hosted.try_sub(1)?;
     host_user_account_state.serialize(&mut &mut host_user_account_info.data.borrow_mut()[..])?;
     // update stats account
-    stats_account_state.total_games -= 1;
+    stats_account_state.total_games = stats_account_state.total_games.try_sub(1)?;
     stats_account_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
     // close game account
     game_account_info.data.borrow_mut().fill(0);
     game_account_info.realloc(0, false)?;
-    **host_wallet_account_info.lamports.borrow_mut() = host_wallet_account_info.lamports().checked_add(game_account_info.lamports()).unwrap();
+    **host_wallet_account_info.lamports.borrow_mut() = host_wallet_account_info.lamports().try_add(game_account_info.lamports())?;
     **game_account_info.lamports.borrow_mut() = 0;
 
     Ok(())
@@ -94,6 +121,7 @@ mod test {
     use crate::{
         instructions::BettingInstruction,
         state::{
+            bankroll::GameBankroll,
             game::{coinflip::CoinFlipConfig, Game, GameTypeConfig},
             stats::Stats,
             user_account::UserAccount,
@@ -158,7 +186,7 @@ mod test {
             },
         );
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -172,6 +200,22 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, user.pubkey());
+        game_bankroll_state.current_lamports = 3000;
+        game_bankroll_state.unclaimed_fee_lamports = 200;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -183,6 +227,133 @@ mod test {
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the rent should be returned to the user wallet account
+        let uesr_wallet_account = banks_client.get_account(user.pubkey()).await.unwrap().unwrap();
+        assert_eq!(
+            uesr_wallet_account.lamports,
+            LAMPORTS_PER_SOL + Rent::default().minimum_balance(game_data_len) + Rent::default().minimum_balance(game_bankroll_data_len)
+        );
+        // the host user account should be updated
+        let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
+        assert_eq!(host_user_account_state.games_hosted, 0);
+        assert_eq!(host_user_account_state.current_lamports, 3200);
+        // the stats account should be updated
+        let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
+        assert_eq!(stats_state.total_games, 0);
+        // the game account should be closed
+        assert!(banks_client.get_account(game_pda).await.unwrap().is_none());
+        // the game bankroll account should be closed
+        assert!(banks_client.get_account(game_bankroll_pda).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_game_close_success_with_stakers() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let referral = Pubkey::new_unique();
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let mut user_account_state = UserAccount::new(user.pubkey(), Some(referral), Some("Username".to_string()));
+        user_account_state.games_hosted = 1;
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let game_state = Game::new(
+            user.pubkey(),
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, user.pubkey());
+        game_bankroll_state.current_lamports = 3000;
+        game_bankroll_state.total_shares = 3000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameClose,
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(game_bankroll_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -196,11 +367,16 @@ mod test {
         // the host user account should be updated
         let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
         assert_eq!(host_user_account_state.games_hosted, 0);
+        assert_eq!(host_user_account_state.current_lamports, 0);
         // the stats account should be updated
         let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
         assert_eq!(stats_state.total_games, 0);
         // the game account should be closed
         assert!(banks_client.get_account(game_pda).await.unwrap().is_none());
+        // the game bankroll account should be kept open for the stakers to withdraw
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.current_lamports, 3000);
+        assert_eq!(game_bankroll_state.total_shares, 3000);
     }
 
     #[tokio::test]
@@ -262,7 +438,7 @@ mod test {
             },
         );
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -276,6 +452,8 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -287,6 +465,7 @@ mod test {
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(game_bankroll_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -367,7 +546,7 @@ mod test {
         );
         game_state.unresolved_vrf_result = 1;
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -381,6 +560,8 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -392,6 +573,7 @@ mod test {
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(game_bankroll_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -2,23 +2,18 @@ use std::convert::TryInto;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program::invoke_signed,
-    pubkey::Pubkey,
-    rent::Rent,
-    system_instruction, system_program,
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed, pubkey::Pubkey, rent::Rent, system_instruction, system_program,
     sysvar::Sysvar,
 };
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
+    math::CheckedMath,
     state::{
         game::{CommonGameConfig, Game, GameTypeConfig},
+        randomness::RandomnessSourceConfig,
         stats::Stats,
         user_account::UserAccount,
-        BettingAccount,
     },
 };
 
@@ -26,46 +21,69 @@ use crate::{
 pub struct GameCreateArgs {
     pub common_config: CommonGameConfig,
     pub game_type_config: GameTypeConfig,
+    pub randomness_source: RandomnessSourceConfig,
+}
+
+#[derive(Accounts)]
+#[accounts(args = GameCreateArgs)]
+pub struct GameCreateAccounts<'a, 'info> {
+    #[account(
+        signer,
+        writable,
+        address = host_user_account_state.authority,
+        name = "host",
+        desc = "The wallet account of the host"
+    )]
+    pub host_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", host_user_account_state.authority],
+        stored_bump = host_user_account_state.bump,
+        name = "user_account",
+        desc = "User Betting Account of the host"
+    )]
+    pub host_user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_account_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", args.common_config.try_to_vec()?, args.game_type_config.try_to_vec()?],
+        bump = game_pda_bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[state(host_user_account_info)]
+    pub host_user_account_state: UserAccount,
+    #[state(stats_account_info)]
+    pub stats_account_state: Stats,
+    pub game_pda_bump: u8,
 }
 
 pub fn game_create(program_id: &Pubkey, accounts: &[AccountInfo], args: GameCreateArgs) -> ProgramResult {
     msg!("Instruction: GameCreate");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let host_account_info = next_account_info(iter)?;
-    let host_user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let game_account_info = next_account_info(iter)?;
-    let system_program_account_info = next_account_info(iter)?;
-
-    let mut host_user_account_state = UserAccount::try_from_account_info(host_user_account_info)?;
-    let mut stats_account_state = Stats::try_from_account_info(stats_account_info)?;
-    // check accounts
-    check_is_signer(host_account_info)?;
-    check_is_writable(host_account_info)?;
-
-    check_is_writable(host_user_account_info)?;
-    check_pda_cannonical_bump(host_user_account_info, &[b"UserAccount".as_ref(), host_user_account_state.authority.as_ref()])?;
-    check_pubkey_eq(host_account_info, &host_user_account_state.authority)?;
-
-    check_is_writable(stats_account_info)?;
-    check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    check_is_writable(game_account_info)?;
+    let GameCreateAccounts {
+        host_account_info,
+        host_user_account_info,
+        stats_account_info,
+        game_account_info,
+        mut host_user_account_state,
+        mut stats_account_state,
+        game_pda_bump,
+        ..
+    } = GameCreateAccounts::try_from_accounts(accounts, &args)?;
     let common_config_vec = args.common_config.try_to_vec()?;
     let game_type_config_vec = args.game_type_config.try_to_vec()?;
-    let game_pda_bump = check_pda_cannonical_bump(
-        game_account_info,
-        &[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()],
-    )?;
-
-    check_pubkey_eq(system_program_account_info, &system_program::ID)?;
+    // check game type config
+    args.game_type_config.check_config()?;
     // update host user account
-    host_user_account_state.games_hosted += 1;
+    host_user_account_state.games_hosted = host_user_account_state.games_hosted.try_add(1)?;
     host_user_account_state.serialize(&mut &mut host_user_account_info.data.borrow_mut()[..])?;
     // update stats account
-    stats_account_state.total_games += 1;
+    stats_account_state.total_games = stats_account_state.total_games.try_add(1)?;
     stats_account_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
     // create game account
     let game_pda_signer_seeds = &[
@@ -74,12 +92,14 @@ pub fn game_create(program_id: &Pubkey, accounts: &[AccountInfo], args: GameCrea
         game_type_config_vec.as_slice(),
         &[game_pda_bump],
     ];
-    let game_state = Game::new(
+    let mut game_state = Game::new_with_bump(
         *host_account_info.key,
         args.common_config.min_wager,
         args.common_config.max_wager,
         args.game_type_config,
+        game_pda_bump,
     );
+    game_state.set_randomness_source(args.randomness_source)?;
     let game_data = game_state.try_to_vec()?;
     let game_data_len = game_data.len();
     let min_rent = Rent::get()?.minimum_balance(game_data_len);
@@ -117,6 +137,7 @@ mod test {
         instructions::BettingInstruction,
         state::{
             game::{coinflip::CoinFlipConfig, CommonGameConfig, Game, GameTypeConfig},
+            randomness::{RandomnessSourceConfig, RANDOMNESS_SOURCE_VRF},
             stats::Stats,
             user_account::UserAccount,
             StateAccountType,
@@ -125,8 +146,8 @@ mod test {
 
     use super::GameCreateArgs;
 
-    #[tokio::test]
-    async fn test_game_create_success() {
+    /// Create a game with the given game type config and return the states of the host user account, the stats and the game
+    async fn create_game(game_type_config: GameTypeConfig) -> (UserAccount, Pubkey, Stats, Game) {
         let program_id = crate::id();
         let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
 
@@ -173,12 +194,6 @@ mod test {
             min_wager: 1000,
             max_wager: 10000,
         };
-        let game_type_config = GameTypeConfig::CoinFlip {
-            config: CoinFlipConfig {
-                host_probability_advantage: 100,
-                payout_rate: 9900,
-            },
-        };
         let common_config_vec = game_common_config.try_to_vec().unwrap();
         let game_type_config_vec = game_type_config.try_to_vec().unwrap();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -191,6 +206,7 @@ mod test {
                     args: GameCreateArgs {
                         common_config: game_common_config,
                         game_type_config,
+                        randomness_source: RandomnessSourceConfig::Vrf,
                     },
                 },
                 vec![
@@ -205,29 +221,56 @@ mod test {
         );
         transaction.sign(&[&user, &payer], recent_blockhash);
         banks_client.process_transaction(transaction).await.unwrap();
+        (
+            banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap(),
+            user.pubkey(),
+            banks_client.get_account_data_with_borsh(stats_pda).await.unwrap(),
+            banks_client.get_account_data_with_borsh(game_pda).await.unwrap(),
+        )
+    }
 
+    #[tokio::test]
+    async fn test_game_create_success() {
+        let (user_account_state, host, stats_state, game_state) = create_game(GameTypeConfig::CoinFlip {
+            config: CoinFlipConfig {
+                host_probability_advantage: 100,
+                payout_rate: 9900,
+            },
+        })
+        .await;
         // the user account state should be updated
-        let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
         assert_eq!(user_account_state.games_hosted, 1);
         // the stats account state should be updated
-        let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
         assert_eq!(stats_state.total_games, 1);
         // the game pda account should be created
-        let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
-        assert_eq!(game_state.account_type, StateAccountType::Game);
-        assert_eq!(game_state.host, user.pubkey());
-        assert!(game_state.is_active);
+        assert_eq!(game_state.account_type, StateAccountType::Game as u8);
+        assert_eq!(game_state.host, host);
+        assert!(game_state.is_active());
         assert_eq!(game_state.unresolved_vrf_result, 0);
         assert_eq!(game_state.total_lamports_in, 0);
         assert_eq!(game_state.total_lamports_out, 0);
-        assert_eq!(game_state.common_config.max_wager, game_common_config.max_wager);
-        assert_eq!(game_state.common_config.min_wager, game_common_config.min_wager);
-        match game_state.game_type_config {
+        assert_eq!(game_state.common_config.max_wager, 10000);
+        assert_eq!(game_state.common_config.min_wager, 1000);
+        match game_state.game_type_config().unwrap() {
             GameTypeConfig::CoinFlip { config } => {
                 assert_eq!(config.host_probability_advantage, 100);
                 assert_eq!(config.payout_rate, 9900);
             }
             _ => panic!(),
         }
+        assert_eq!(game_state.randomness_source, RANDOMNESS_SOURCE_VRF);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "InvalidArgument")]
+    async fn test_game_create_err_host_probability_advantage_too_high() {
+        // the bets of such a coin flip game could never be resolved
+        create_game(GameTypeConfig::CoinFlip {
+            config: CoinFlipConfig {
+                host_probability_advantage: 5001,
+                payout_rate: 9900,
+            },
+        })
+        .await;
     }
 }
@@ -0,0 +1,369 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    error::BettingError,
+    math::{to_signed, CheckedMath},
+    state::{
+        bankroll::{BankrollShare, GameBankroll},
+        user_account::UserAccount,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct GameDefundBankrollArgs {
+    pub shares: u64,
+}
+
+#[derive(Accounts)]
+pub struct GameDefundBankrollAccounts<'a, 'info> {
+    #[account(signer, address = staker_user_account_state.authority, name = "staker", desc = "The wallet account of the staker")]
+    pub staker_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", staker_user_account_state.authority],
+        stored_bump = staker_user_account_state.bump,
+        name = "staker_user_account",
+        desc = "User Betting Account of the staker"
+    )]
+    pub staker_user_account_info: &'a AccountInfo<'info>,
+    // the game account may already be closed, the bankroll stays open until the stakers withdraw
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_bankroll_state.game],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"BankrollShare", game_bankroll_account_info.key, staker_user_account_info.key],
+        name = "bankroll_share_pda",
+        desc = "Bankroll share PDA account of the staker"
+    )]
+    pub bankroll_share_account_info: &'a AccountInfo<'info>,
+    #[state(staker_user_account_info)]
+    pub staker_user_account_state: UserAccount,
+    #[state(game_bankroll_account_info)]
+    pub game_bankroll_state: GameBankroll,
+    #[state(bankroll_share_account_info)]
+    pub bankroll_share_state: BankrollShare,
+}
+
+pub fn game_defund_bankroll(_program_id: &Pubkey, accounts: &[AccountInfo], args: GameDefundBankrollArgs) -> ProgramResult {
+    msg!("Instruction: GameDefundBankroll");
+    // get accounts
+    let GameDefundBankrollAccounts {
+        staker_user_account_info,
+        game_bankroll_account_info,
+        bankroll_share_account_info,
+        mut staker_user_account_state,
+        mut game_bankroll_state,
+        mut bankroll_share_state,
+        ..
+    } = GameDefundBankrollAccounts::try_from_accounts(accounts)?;
+    // check defund amount
+    if bankroll_share_state.shares < args.shares {
+        msg!("Bankroll share {} does not have enough shares", bankroll_share_account_info.key);
+        return Err(ProgramError::InsufficientFunds);
+    }
+    // the outcome of a fullfilled bet is known before it is resolved, shares are only redeemed once the bets are settled
+    if game_bankroll_state.has_pending_liability() {
+        msg!("Game bankroll {} is not settled", game_bankroll_account_info.key);
+        return Err(ProgramError::from(BettingError::GameNotSettled));
+    }
+    let lamports = game_bankroll_state.lamports_for_shares(args.shares)?;
+    // update bankroll share account
+    bankroll_share_state.shares = bankroll_share_state.shares.try_sub(args.shares)?;
+    bankroll_share_state.lamports_withdrew = bankroll_share_state.lamports_withdrew.try_add(lamports)?;
+    bankroll_share_state.serialize(&mut &mut bankroll_share_account_info.data.borrow_mut()[..])?;
+    // update game bankroll account
+    game_bankroll_state.current_lamports = game_bankroll_state.current_lamports.try_sub(lamports)?;
+    game_bankroll_state.lamports_defunded = game_bankroll_state.lamports_defunded.try_add(lamports)?;
+    game_bankroll_state.total_shares = game_bankroll_state.total_shares.try_sub(args.shares)?;
+    game_bankroll_state.serialize(&mut &mut game_bankroll_account_info.data.borrow_mut()[..])?;
+    // update staker user account
+    staker_user_account_state.current_lamports = staker_user_account_state.current_lamports.try_add(lamports)?;
+    staker_user_account_state.hosting_pnl = staker_user_account_state.hosting_pnl.try_add(to_signed(lamports)?)?;
+    staker_user_account_state.serialize(&mut &mut staker_user_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{
+        account::Account,
+        signature::Keypair,
+        signer::Signer,
+        transaction::{Transaction, TransactionError},
+    };
+
+    use crate::{
+        constants::OPERATOR_PUBKEY,
+        error::BettingError,
+        instructions::BettingInstruction,
+        state::{
+            bankroll::{BankrollShare, GameBankroll},
+            game::{
+                coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+                BetInput, Game, GameTypeConfig,
+            },
+            stats::Stats,
+            user_account::UserAccount,
+            vrf_result::VrfResult,
+        },
+    };
+
+    use super::GameDefundBankrollArgs;
+
+    async fn run_game_defund_bankroll(current_lamports: u64, locked_lamports: u64) -> (u64, u64, u64) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let staker = Keypair::new();
+        let (staker_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), staker.pubkey().as_ref()], &program_id);
+        let staker_user_account_state = UserAccount::new(staker.pubkey(), None, None);
+        let staker_user_account_data = staker_user_account_state.try_to_vec().unwrap();
+        let staker_user_account_data_len = staker_user_account_data.len();
+        program_test.add_account(
+            staker_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(staker_user_account_data_len),
+                data: staker_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        // the game account is not required, the bankroll is checked against the game it was created for
+        let game_pda = Pubkey::new_unique();
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, Pubkey::new_unique());
+        game_bankroll_state.current_lamports = current_lamports;
+        game_bankroll_state.locked_lamports = locked_lamports;
+        game_bankroll_state.total_shares = 6000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (bankroll_share_pda, _) = Pubkey::find_program_address(
+            &[b"BankrollShare".as_ref(), game_bankroll_pda.as_ref(), staker_user_account_pda.as_ref()],
+            &program_id,
+        );
+        let mut bankroll_share_state = BankrollShare::new(game_bankroll_pda, staker_user_account_pda);
+        bankroll_share_state.shares = 6000;
+        let bankroll_share_data = bankroll_share_state.try_to_vec().unwrap();
+        let bankroll_share_data_len = bankroll_share_data.len();
+        program_test.add_account(
+            bankroll_share_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bankroll_share_data_len),
+                data: bankroll_share_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameDefundBankroll {
+                    args: GameDefundBankrollArgs { shares: 5000 },
+                },
+                vec![
+                    AccountMeta::new_readonly(staker.pubkey(), true),
+                    AccountMeta::new(staker_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bankroll_share_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&staker, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let staker_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(staker_user_account_pda).await.unwrap();
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        let bankroll_share_state: BankrollShare = banks_client.get_account_data_with_borsh(bankroll_share_pda).await.unwrap();
+        (
+            staker_user_account_state.current_lamports,
+            game_bankroll_state.current_lamports,
+            bankroll_share_state.shares,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_game_defund_bankroll_success() {
+        let (staker_lamports, bankroll_lamports, shares) = run_game_defund_bankroll(6000, 0).await;
+        // the lamports should be moved from the game bankroll back to the staker balance
+        assert_eq!(staker_lamports, 5000);
+        assert_eq!(bankroll_lamports, 1000);
+        assert_eq!(shares, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_game_defund_bankroll_success_at_nav() {
+        let (staker_lamports, bankroll_lamports, shares) = run_game_defund_bankroll(12000, 0).await;
+        // the bankroll winnings should be withdrawn with the shares
+        assert_eq!(staker_lamports, 10000);
+        assert_eq!(bankroll_lamports, 2000);
+        assert_eq!(shares, 1000);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(13)")]
+    async fn test_game_defund_bankroll_err_pending_liability() {
+        run_game_defund_bankroll(6000, 1980).await;
+    }
+
+    fn add_account<T: BorshSerialize>(program_test: &mut ProgramTest, pubkey: Pubkey, state: &T) {
+        let data = state.try_to_vec().unwrap();
+        program_test.add_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: crate::id(),
+                ..Default::default()
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_game_defund_bankroll_err_between_fullfill_and_resolve() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let staker = Keypair::new();
+        let (staker_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), staker.pubkey().as_ref()], &program_id);
+        add_account(&mut program_test, staker_user_account_pda, &UserAccount::new(staker.pubkey(), None, None));
+
+        let bettor = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, None, None);
+        bettor_user_account_state.active_vrf_results = 1;
+        add_account(&mut program_test, bettor_user_account_pda, &bettor_user_account_state);
+
+        let mut game_state = Game::new(
+            Pubkey::new_unique(),
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = 1;
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        add_account(&mut program_test, game_pda, &game_state);
+
+        // the staker owns the whole bankroll, whose unlocked lamports would cover the withdrawal
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, game_state.host);
+        game_bankroll_state.current_lamports = 8020;
+        game_bankroll_state.locked_lamports = 1980;
+        game_bankroll_state.total_shares = 10000;
+        add_account(&mut program_test, game_bankroll_pda, &game_bankroll_state);
+
+        let (bankroll_share_pda, _) = Pubkey::find_program_address(
+            &[b"BankrollShare".as_ref(), game_bankroll_pda.as_ref(), staker_user_account_pda.as_ref()],
+            &program_id,
+        );
+        let mut bankroll_share_state = BankrollShare::new(game_bankroll_pda, staker_user_account_pda);
+        bankroll_share_state.shares = 10000;
+        add_account(&mut program_test, bankroll_share_pda, &bankroll_share_state);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        add_account(&mut program_test, stats_pda, &Stats::new());
+
+        // the bet is fullfilled, its outcome is public until it is resolved
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            0,
+            [0; 72],
+            2000,
+            1980,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.is_fullfilled = true;
+        let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 0);
+        add_account(&mut program_test, vrf_result_pda, &vrf_result_state);
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let defund_instruction = Instruction::new_with_borsh(
+            program_id,
+            &BettingInstruction::GameDefundBankroll {
+                args: GameDefundBankrollArgs { shares: 5000 },
+            },
+            vec![
+                AccountMeta::new_readonly(staker.pubkey(), true),
+                AccountMeta::new(staker_user_account_pda, false),
+                AccountMeta::new(game_bankroll_pda, false),
+                AccountMeta::new(bankroll_share_pda, false),
+            ],
+        );
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&defund_instruction), Some(&payer.pubkey()));
+        transaction.sign(&[&staker, &payer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(BettingError::GameNotSettled as u32))
+        );
+
+        // once the bet is resolved the staker withdraws at the settled net asset value
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                Instruction::new_with_borsh(
+                    program_id,
+                    &BettingInstruction::GameResolveVrfResult,
+                    vec![
+                        AccountMeta::new(game_pda, false),
+                        AccountMeta::new(vrf_result_pda, false),
+                        AccountMeta::new(game_bankroll_pda, false),
+                        AccountMeta::new(bettor_user_account_pda, false),
+                        AccountMeta::new(stats_pda, false),
+                        AccountMeta::new(bettor_user_limits_pda, false),
+                        AccountMeta::new(OPERATOR_PUBKEY, false),
+                    ],
+                ),
+                defund_instruction,
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&staker, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.locked_lamports, 0);
+        assert_eq!(game_bankroll_state.total_shares, 5000);
+        let staker_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(staker_user_account_pda).await.unwrap();
+        // half of the shares redeem half of the bankroll, whatever the outcome of the bet
+        assert_eq!(staker_user_account_state.current_lamports, game_bankroll_state.current_lamports);
+    }
+}
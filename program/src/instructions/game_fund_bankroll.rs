@@ -0,0 +1,285 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, system_program};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    error::BettingError,
+    math::{to_signed, CheckedMath},
+    state::{
+        bankroll::{BankrollShare, GameBankroll},
+        game::Game,
+        user_account::UserAccount,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct GameFundBankrollArgs {
+    pub lamports: u64,
+}
+
+#[derive(Accounts)]
+pub struct GameFundBankrollAccounts<'a, 'info> {
+    #[account(
+        signer,
+        writable,
+        address = staker_user_account_state.authority,
+        name = "staker",
+        desc = "The wallet account of the staker"
+    )]
+    pub staker_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", staker_user_account_state.authority],
+        stored_bump = staker_user_account_state.bump,
+        name = "staker_user_account",
+        desc = "User Betting Account of the staker"
+    )]
+    pub staker_user_account_info: &'a AccountInfo<'info>,
+    #[account(
+        seeds = [b"Game", game_account_state.common_config_seed(), game_account_state.game_type_config_seed()],
+        stored_bump = game_account_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "game_bankroll_pda", desc = "Game bankroll PDA account")]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "bankroll_share_pda", desc = "Bankroll share PDA account of the staker")]
+    pub bankroll_share_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[state(staker_user_account_info)]
+    pub staker_user_account_state: UserAccount,
+    #[state(game_account_info)]
+    pub game_account_state: Game,
+}
+
+pub fn game_fund_bankroll(_program_id: &Pubkey, accounts: &[AccountInfo], args: GameFundBankrollArgs) -> ProgramResult {
+    msg!("Instruction: GameFundBankroll");
+    // get accounts
+    let GameFundBankrollAccounts {
+        staker_wallet_account_info,
+        staker_user_account_info,
+        game_account_info,
+        game_bankroll_account_info,
+        bankroll_share_account_info,
+        mut staker_user_account_state,
+        game_account_state,
+        ..
+    } = GameFundBankrollAccounts::try_from_accounts(accounts)?;
+    // check fund amount
+    if staker_user_account_state.current_lamports < args.lamports {
+        msg!("Account {} does not have enough lamports", staker_user_account_info.key);
+        return Err(ProgramError::InsufficientFunds);
+    }
+    let mut game_bankroll_state = GameBankroll::load_or_create(
+        game_account_info.key,
+        &game_account_state.host,
+        game_bankroll_account_info,
+        staker_wallet_account_info,
+    )?;
+    // the outcome of a fullfilled bet is known before it is resolved, shares are only priced once the bets are settled
+    if game_account_state.unresolved_vrf_result > 0 || game_bankroll_state.has_pending_liability() {
+        msg!("Game {} is not settled", game_account_info.key);
+        return Err(ProgramError::from(BettingError::GameNotSettled));
+    }
+    let shares = game_bankroll_state.shares_for_lamports(args.lamports)?.ok_or_else(|| {
+        msg!(
+            "Deposit of {} lamports is not worth any share of game bankroll {}",
+            args.lamports,
+            game_bankroll_account_info.key
+        );
+        ProgramError::InvalidArgument
+    })?;
+    // update bankroll share account
+    let mut bankroll_share_state = BankrollShare::load_or_create(
+        game_bankroll_account_info.key,
+        staker_user_account_info.key,
+        bankroll_share_account_info,
+        staker_wallet_account_info,
+    )?;
+    bankroll_share_state.shares = bankroll_share_state.shares.try_add(shares)?;
+    bankroll_share_state.lamports_deposited = bankroll_share_state.lamports_deposited.try_add(args.lamports)?;
+    bankroll_share_state.serialize(&mut &mut bankroll_share_account_info.data.borrow_mut()[..])?;
+    // update game bankroll account
+    game_bankroll_state.current_lamports = game_bankroll_state.current_lamports.try_add(args.lamports)?;
+    game_bankroll_state.lamports_funded = game_bankroll_state.lamports_funded.try_add(args.lamports)?;
+    game_bankroll_state.total_shares = game_bankroll_state.total_shares.try_add(shares)?;
+    game_bankroll_state.serialize(&mut &mut game_bankroll_account_info.data.borrow_mut()[..])?;
+    // update staker user account
+    staker_user_account_state.current_lamports = staker_user_account_state.current_lamports.try_sub(args.lamports)?;
+    staker_user_account_state.hosting_pnl = staker_user_account_state.hosting_pnl.try_sub(to_signed(args.lamports)?)?;
+    staker_user_account_state.serialize(&mut &mut staker_user_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            bankroll::{BankrollShare, GameBankroll},
+            game::{coinflip::CoinFlipConfig, Game, GameTypeConfig},
+            user_account::UserAccount,
+            StateAccountType,
+        },
+    };
+
+    use super::GameFundBankrollArgs;
+
+    async fn run_game_fund_bankroll(staker_is_host: bool, game_bankroll: Option<GameBankroll>) -> (Pubkey, Pubkey, UserAccount, GameBankroll, BankrollShare) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Keypair::new();
+        let staker = if staker_is_host {
+            Keypair::from_bytes(&host.to_bytes()).unwrap()
+        } else {
+            Keypair::new()
+        };
+        program_test.add_account(
+            staker.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (staker_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), staker.pubkey().as_ref()], &program_id);
+        let mut staker_user_account_state = UserAccount::new(staker.pubkey(), None, None);
+        staker_user_account_state.current_lamports = 10000;
+        let staker_user_account_data = staker_user_account_state.try_to_vec().unwrap();
+        let staker_user_account_data_len = staker_user_account_data.len();
+        program_test.add_account(
+            staker_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(staker_user_account_data_len),
+                data: staker_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let game_state = Game::new(
+            host.pubkey(),
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        if let Some(mut game_bankroll_state) = game_bankroll {
+            game_bankroll_state.game = game_pda;
+            game_bankroll_state.host = host.pubkey();
+            let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+            let game_bankroll_data_len = game_bankroll_data.len();
+            program_test.add_account(
+                game_bankroll_pda,
+                Account {
+                    lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                    data: game_bankroll_data,
+                    owner: program_id,
+                    ..Default::default()
+                },
+            );
+        }
+        let (bankroll_share_pda, _) = Pubkey::find_program_address(
+            &[b"BankrollShare".as_ref(), game_bankroll_pda.as_ref(), staker_user_account_pda.as_ref()],
+            &program_id,
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameFundBankroll {
+                    args: GameFundBankrollArgs { lamports: 6000 },
+                },
+                vec![
+                    AccountMeta::new(staker.pubkey(), true),
+                    AccountMeta::new(staker_user_account_pda, false),
+                    AccountMeta::new_readonly(game_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bankroll_share_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&staker, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let staker_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(staker_user_account_pda).await.unwrap();
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        let bankroll_share_state: BankrollShare = banks_client.get_account_data_with_borsh(bankroll_share_pda).await.unwrap();
+        (game_pda, host.pubkey(), staker_user_account_state, game_bankroll_state, bankroll_share_state)
+    }
+
+    #[tokio::test]
+    async fn test_game_fund_bankroll_success() {
+        let (game_pda, host, host_user_account_state, game_bankroll_state, bankroll_share_state) = run_game_fund_bankroll(true, None).await;
+        // the lamports should be moved from the host balance to the game bankroll
+        assert_eq!(host_user_account_state.current_lamports, 4000);
+        assert_eq!(host_user_account_state.hosting_pnl, -6000);
+        assert_eq!(host_user_account_state.betting_pnl, 0);
+        assert_eq!(game_bankroll_state.account_type, StateAccountType::GameBankroll);
+        assert_eq!(game_bankroll_state.game, game_pda);
+        assert_eq!(game_bankroll_state.host, host);
+        assert_eq!(game_bankroll_state.current_lamports, 6000);
+        assert_eq!(game_bankroll_state.lamports_funded, 6000);
+        // the first deposit should be minted one share per lamport
+        assert_eq!(game_bankroll_state.total_shares, 6000);
+        assert_eq!(bankroll_share_state.account_type, StateAccountType::BankrollShare);
+        assert_eq!(bankroll_share_state.shares, 6000);
+        assert_eq!(bankroll_share_state.lamports_deposited, 6000);
+    }
+
+    #[tokio::test]
+    async fn test_game_fund_bankroll_success_third_party_at_nav() {
+        let mut game_bankroll_state = GameBankroll::new(Pubkey::default(), Pubkey::default());
+        game_bankroll_state.current_lamports = 10000;
+        game_bankroll_state.total_shares = 5000;
+        let (_, host, staker_user_account_state, game_bankroll_state, bankroll_share_state) = run_game_fund_bankroll(false, Some(game_bankroll_state)).await;
+        assert_eq!(staker_user_account_state.current_lamports, 4000);
+        assert_eq!(game_bankroll_state.host, host);
+        assert_eq!(game_bankroll_state.current_lamports, 14000);
+        // the shares should be minted at 2 lamports per share
+        assert_eq!(game_bankroll_state.total_shares, 8000);
+        assert_eq!(bankroll_share_state.shares, 3000);
+        assert_eq!(
+            bankroll_share_state.owner,
+            Pubkey::find_program_address(&[b"UserAccount".as_ref(), staker_user_account_state.authority.as_ref()], &crate::id()).0
+        );
+    }
+}
@@ -1,8 +1,8 @@
-use std::convert::TryInto;
+use std::{cell::RefMut, convert::TryInto};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::AccountInfo,
     clock::Clock,
     entrypoint::ProgramResult,
     msg,
@@ -13,118 +13,212 @@ use solana_program::{
     system_instruction, system_program,
     sysvar::{self, Sysvar},
 };
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
+    checks::check_is_writable,
+    constants::MAX_CRANK_TIP_LAMPORTS,
     error::BettingError,
+    math::CheckedMath,
     state::{
+        bankroll::GameBankroll,
         game::{BetInput, Game},
+        operator_bond::OperatorBond,
+        randomness::RANDOMNESS_SOURCE_VRF,
+        referral::Referral,
         stats::Stats,
         user_account::UserAccount,
+        user_limits::UserLimits,
         vrf_result::VrfResult,
-        BettingAccount,
     },
 };
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
 pub struct GamePlaceBetArgs {
     pub bet_input: BetInput,
+    // close the VRF result when the bet is settled, refunding its rent to the bettor's balance
+    pub close_on_resolve: bool,
+    // lamports of the bettor's balance paid to whoever resolves the bet, on top of the game's tip
+    pub crank_tip_lamports: u64,
+}
+
+/// The bettor only signs, so a program can bet from one of its PDAs by invoking this instruction with `invoke_signed`.
+/// The rent of the VRF result is paid by the optional payer passed last, or by the bettor without one.
+#[derive(Accounts)]
+pub struct GamePlaceBetAccounts<'a, 'info> {
+    #[account(signer, address = bettor_user_account_state.authority, name = "bettor", desc = "Bettor wallet account")]
+    pub bettor_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", bettor_user_account_state.authority],
+        stored_bump = bettor_user_account_state.bump,
+        name = "bettor_user_account",
+        desc = "Bettor user account"
+    )]
+    pub bettor_user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_account_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_account_state.common_config_seed(), game_account_state.game_type_config_seed()],
+        stored_bump = game_account_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_account_info.key],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", game_account_info.key, bettor_account_info.key, bettor_user_account_state.total_bets.to_le_bytes()],
+        bump = vrf_result_pda_bump,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[account(address = sysvar::slot_hashes::ID, name = "slot_hashes", desc = "Slot hashes account")]
+    pub slot_hashes_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "bettor_user_limits_pda", desc = "Bettor user limits PDA Account, may be uninitialized")]
+    pub bettor_user_limits_account_info: &'a AccountInfo<'info>,
+    #[account(
+        seeds = [b"OperatorBond"],
+        name = "operator_bond_pda",
+        desc = "Operator bond PDA Account, required to be bonded for the games settled with the VRF"
+    )]
+    pub operator_bond_account_info: &'a AccountInfo<'info>,
+    // the referral account is only passed for referred bettors
+    #[account(
+        writable,
+        optional = let Some(referral) = bettor_user_account_state.referral(),
+        seeds = [b"Referral", referral],
+        name = "referral_pda",
+        desc = "Referral PDA of the bettor's referrer, required when the bettor has a referral"
+    )]
+    pub referral_account_info: Option<&'a AccountInfo<'info>>,
+    #[account(
+        signer,
+        writable,
+        optional = !iter.as_slice().is_empty(),
+        name = "payer",
+        desc = "The account paying for the rent of the VRF result, the bettor when omitted"
+    )]
+    pub payer_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(bettor_user_account_info)]
+    pub bettor_user_account_state: RefMut<'a, UserAccount>,
+    #[state(stats_account_info)]
+    pub stats_account_state: RefMut<'a, Stats>,
+    #[state(game_account_info)]
+    pub game_account_state: RefMut<'a, Game>,
+    #[state(game_bankroll_account_info)]
+    pub game_bankroll_state: GameBankroll,
+    pub vrf_result_pda_bump: u8,
 }
 
 pub fn game_place_bet(program_id: &Pubkey, accounts: &[AccountInfo], args: GamePlaceBetArgs) -> ProgramResult {
     msg!("Instruction: GamePlaceBet");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let bettor_account_info = next_account_info(iter)?;
-    let bettor_user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let game_account_info = next_account_info(iter)?;
-    let host_user_account_info = next_account_info(iter)?;
-    let vrf_result_account_info = next_account_info(iter)?;
-    let slot_hashes_account_info = next_account_info(iter)?;
-    let system_program_account_info = next_account_info(iter)?;
-
-    let mut bettor_user_account_state = UserAccount::try_from_account_info(bettor_user_account_info)?;
-    let mut stats_account_state = Stats::try_from_account_info(stats_account_info)?;
-    let mut game_account_state = Game::try_from_account_info(game_account_info)?;
-    let mut host_user_account_state = UserAccount::try_from_account_info(host_user_account_info)?;
-    // check accounts
-    check_is_signer(bettor_account_info)?;
-
-    check_is_writable(bettor_user_account_info)?;
-    check_pda_cannonical_bump(
+    let GamePlaceBetAccounts {
+        bettor_account_info,
+        payer_account_info,
         bettor_user_account_info,
-        &[b"UserAccount".as_ref(), bettor_user_account_state.authority.as_ref()],
-    )?;
-    check_pubkey_eq(bettor_account_info, &bettor_user_account_state.authority)?;
-
-    check_is_writable(stats_account_info)?;
-    check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    check_is_writable(game_account_info)?;
-    let game_common_config_vec = game_account_state.common_config.try_to_vec()?;
-    let game_type_config_vec = game_account_state.game_type_config.try_to_vec()?;
-    check_pda_cannonical_bump(
         game_account_info,
-        &[b"Game".as_ref(), game_common_config_vec.as_slice(), game_type_config_vec.as_slice()],
-    )?;
-
-    check_is_writable(host_user_account_info)?;
-    check_pda_cannonical_bump(host_user_account_info, &[b"UserAccount".as_ref(), host_user_account_state.authority.as_ref()])?;
-    assert_eq!(&host_user_account_state.authority, &game_account_state.host);
-
-    check_is_writable(vrf_result_account_info)?;
-    let vrf_result_pda_bump = check_pda_cannonical_bump(
+        game_bankroll_account_info,
         vrf_result_account_info,
-        &[
-            b"VrfResult".as_ref(),
-            game_account_info.key.as_ref(),
-            bettor_account_info.key.as_ref(),
-            &bettor_user_account_state.total_bets.to_le_bytes(),
-        ],
-    )?;
-
-    check_pubkey_eq(slot_hashes_account_info, &sysvar::slot_hashes::ID)?;
-
-    check_pubkey_eq(system_program_account_info, &system_program::ID)?;
-
+        slot_hashes_account_info,
+        bettor_user_limits_account_info,
+        operator_bond_account_info,
+        mut bettor_user_account_state,
+        mut stats_account_state,
+        mut game_account_state,
+        mut game_bankroll_state,
+        vrf_result_pda_bump,
+        referral_account_info,
+        ..
+    } = GamePlaceBetAccounts::try_from_accounts(accounts)?;
     // check game is active
-    if !game_account_state.is_active {
+    if !game_account_state.is_active() {
         msg!("Game {} is not active", game_account_info.key);
         return Err(ProgramError::from(BettingError::GameNotActive));
     }
+    // check the operator keeps enough lamports bonded to cover the bets it fullfills, no bond is no bet
+    if game_account_state.randomness_source == RANDOMNESS_SOURCE_VRF
+        && !matches!(OperatorBond::load(operator_bond_account_info)?, Some(operator_bond_state) if operator_bond_state.is_bonded())
+    {
+        msg!("Operator bond {} is missing or below its minimum", operator_bond_account_info.key);
+        return Err(ProgramError::from(BettingError::OperatorBondTooLow));
+    }
     // check bet input
     let dyn_bet_input = args.bet_input.get_dyn_input();
     dyn_bet_input.check_bet_input(&game_account_state)?;
     // check bettor balance
     let bettor_lamports_to_lock = dyn_bet_input.check_bettor_balance(&game_account_state, &bettor_user_account_state)?;
-    // check host balacne
-    let host_lamports_to_lock = dyn_bet_input.check_host_balance(&game_account_state, &host_user_account_state)?;
+    // check game bankroll balance
+    let host_lamports_to_lock = dyn_bet_input.check_host_balance(&game_account_state, &game_bankroll_state)?;
+    // check crank tips, escrowed from the balances of the bettor and the game bankroll
+    if args.crank_tip_lamports > MAX_CRANK_TIP_LAMPORTS {
+        msg!("Crank tip must be at most {} lamports", MAX_CRANK_TIP_LAMPORTS);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let game_crank_tip_lamports = u64::from(game_account_state.crank_tip_lamports);
+    if bettor_user_account_state.current_lamports < bettor_lamports_to_lock.try_add(args.crank_tip_lamports)?
+        || game_bankroll_state.current_lamports < host_lamports_to_lock.try_add(game_crank_tip_lamports)?
+    {
+        msg!("Not enough lamports to escrow the crank tips");
+        return Err(ProgramError::InsufficientFunds);
+    }
+    // check responsible gambling limits
+    if let Some(mut bettor_user_limits_state) = UserLimits::load(bettor_user_account_info.key, bettor_user_limits_account_info)? {
+        bettor_user_limits_state.record_wager(Clock::get()?.unix_timestamp, bettor_lamports_to_lock)?;
+        bettor_user_limits_state.serialize(&mut &mut bettor_user_limits_account_info.data.borrow_mut()[..])?;
+    }
 
-    // update bettor user account
+    // update bettor user account, the zero-copy states are written in place
     let bet_id = bettor_user_account_state.total_bets;
-    bettor_user_account_state.total_bets += 1;
-    bettor_user_account_state.active_vrf_results += 1;
-    bettor_user_account_state.current_lamports -= bettor_lamports_to_lock;
-    bettor_user_account_state.serialize(&mut &mut bettor_user_account_info.data.borrow_mut()[..])?;
+    bettor_user_account_state.total_bets = bettor_user_account_state.total_bets.try_add(1)?;
+    bettor_user_account_state.active_vrf_results = bettor_user_account_state.active_vrf_results.try_add(1)?;
+    bettor_user_account_state.current_lamports = bettor_user_account_state
+        .current_lamports
+        .try_sub(bettor_lamports_to_lock)?
+        .try_sub(args.crank_tip_lamports)?;
     // update stats account
-    stats_account_state.total_bets += 1;
-    stats_account_state.total_wager += bettor_lamports_to_lock;
-    stats_account_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
+    stats_account_state.total_bets = stats_account_state.total_bets.try_add(1)?;
+    stats_account_state.total_wager = stats_account_state.total_wager.try_add(bettor_lamports_to_lock)?;
     // update game account
-    game_account_state.unresolved_vrf_result += 1;
-    game_account_state.total_lamports_in += bettor_lamports_to_lock;
-    game_account_state.serialize(&mut &mut game_account_info.data.borrow_mut()[..])?;
-    // update host user account
-    host_user_account_state.current_lamports -= host_lamports_to_lock;
-    host_user_account_state.serialize(&mut &mut host_user_account_info.data.borrow_mut()[..])?;
-    // create vrf result account
+    game_account_state.unresolved_vrf_result = game_account_state.unresolved_vrf_result.try_add(1)?;
+    game_account_state.total_lamports_in = game_account_state.total_lamports_in.try_add(bettor_lamports_to_lock)?;
+    // update game bankroll account
+    game_bankroll_state.current_lamports = game_bankroll_state
+        .current_lamports
+        .try_sub(host_lamports_to_lock)?
+        .try_sub(game_crank_tip_lamports)?;
+    game_bankroll_state.locked_lamports = game_bankroll_state.locked_lamports.try_add(host_lamports_to_lock)?;
+    game_bankroll_state.serialize(&mut &mut game_bankroll_account_info.data.borrow_mut()[..])?;
+    // update referral account, referrers of bettors referred before the referral PDAs have none to update
+    let referral_state = referral_account_info.map(Referral::load).transpose()?.flatten();
+    if let (Some(referral_account_info), Some(mut referral_state)) = (referral_account_info, referral_state) {
+        referral_state.referred_volume = referral_state.referred_volume.try_add(bettor_lamports_to_lock)?;
+        referral_state.serialize(&mut &mut referral_account_info.data.borrow_mut()[..])?;
+    }
+    // create vrf result account, the bettor pays the rent unless a payer is passed
+    let payer_account_info = match payer_account_info {
+        Some(payer_account_info) => payer_account_info,
+        None => {
+            check_is_writable(bettor_account_info)?;
+            bettor_account_info
+        }
+    };
     let mut alpha = [0u8; 72];
     let now = Clock::get()?.unix_timestamp;
     alpha[0..8].copy_from_slice(&now.to_le_bytes());
     alpha[8..40].copy_from_slice(bettor_account_info.key.as_ref());
     alpha[40..72].copy_from_slice(&slot_hashes_account_info.data.borrow()[16..48]);
-    let vrf_result_state = VrfResult::new(
+    let mut vrf_result_state = VrfResult::new_with_bump(
         *bettor_account_info.key,
         *game_account_info.key,
         bet_id,
@@ -132,7 +226,11 @@ pub fn game_place_bet(program_id: &Pubkey, accounts: &[AccountInfo], args: GameP
         bettor_lamports_to_lock,
         host_lamports_to_lock,
         args.bet_input,
+        vrf_result_pda_bump,
     );
+    vrf_result_state.randomness = game_account_state.next_randomness()?;
+    vrf_result_state.close_on_resolve = args.close_on_resolve;
+    vrf_result_state.crank_tip_lamports = args.crank_tip_lamports.try_add(game_crank_tip_lamports)?;
     let vrf_result_data = vrf_result_state.try_to_vec()?;
     let vrf_result_data_len = vrf_result_data.len();
     let vrf_result_pda_signer_seeds = &[
@@ -144,7 +242,7 @@ pub fn game_place_bet(program_id: &Pubkey, accounts: &[AccountInfo], args: GameP
     ];
     let min_rent = Rent::get()?.minimum_balance(vrf_result_data_len);
     let vrf_result_create_ix = system_instruction::create_account(
-        bettor_account_info.key,
+        payer_account_info.key,
         vrf_result_account_info.key,
         min_rent,
         vrf_result_data_len.try_into().unwrap(),
@@ -152,7 +250,7 @@ pub fn game_place_bet(program_id: &Pubkey, accounts: &[AccountInfo], args: GameP
     );
     invoke_signed(
         &vrf_result_create_ix,
-        &[bettor_account_info.clone(), vrf_result_account_info.clone()],
+        &[payer_account_info.clone(), vrf_result_account_info.clone()],
         &[vrf_result_pda_signer_seeds],
     )?;
     vrf_result_account_info.data.borrow_mut().copy_from_slice(&vrf_result_data);
@@ -176,10 +274,13 @@ mod test {
     use crate::{
         instructions::BettingInstruction,
         state::{
+            bankroll::GameBankroll,
             game::{
                 coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
                 BetInput, Game, GameTypeConfig,
             },
+            operator_bond::{OperatorBond, OperatorBondConfig},
+            referral::Referral,
             stats::Stats,
             user_account::UserAccount,
             vrf_result::VrfResult,
@@ -189,6 +290,34 @@ mod test {
 
     use super::GamePlaceBetArgs;
 
+    /// Add the operator bond PDA, bonding its minimum of 1 SOL with the given pending withdrawal
+    fn add_operator_bond(program_test: &mut ProgramTest, pending_withdrawal_lamports: u64) -> Pubkey {
+        let (operator_bond_pda, operator_bond_bump) = Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &crate::id());
+        let mut operator_bond_state = OperatorBond::new(
+            OperatorBondConfig {
+                min_bond_lamports: LAMPORTS_PER_SOL,
+                withdrawal_delay: 100,
+                fullfill_deadline: 60,
+                slash_penalty: 1000,
+            },
+            operator_bond_bump,
+        );
+        operator_bond_state.bonded_lamports = LAMPORTS_PER_SOL;
+        operator_bond_state.pending_withdrawal_lamports = pending_withdrawal_lamports;
+        let operator_bond_data = operator_bond_state.try_to_vec().unwrap();
+        let operator_bond_data_len = operator_bond_data.len();
+        program_test.add_account(
+            operator_bond_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(operator_bond_data_len) + LAMPORTS_PER_SOL,
+                data: operator_bond_data,
+                owner: crate::id(),
+                ..Default::default()
+            },
+        );
+        operator_bond_pda
+    }
+
     #[tokio::test]
     async fn test_game_place_bet_success() {
         let program_id = crate::id();
@@ -219,6 +348,19 @@ mod test {
             },
         );
 
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let referral_data = Referral::new(referral).try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
+        program_test.add_account(
+            referral_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
         let mut stats_state = Stats::new();
         stats_state.total_users = 1;
@@ -236,22 +378,7 @@ mod test {
         );
 
         let host = Pubkey::new_unique();
-        let (host_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), host.as_ref()], &program_id);
-        let mut host_user_account_state = UserAccount::new(host, None, None);
-        host_user_account_state.current_lamports = LAMPORTS_PER_SOL;
-        let host_user_account_data = host_user_account_state.try_to_vec().unwrap();
-        let host_user_account_data_len = host_user_account_data.len();
-        program_test.add_account(
-            host_user_account_pda,
-            Account {
-                lamports: Rent::default().minimum_balance(host_user_account_data_len),
-                data: host_user_account_data,
-                owner: program_id,
-                ..Default::default()
-            },
-        );
-
-        let game_state = Game::new(
+        let mut game_state = Game::new(
             host,
             1000,
             10000,
@@ -262,8 +389,9 @@ mod test {
                 },
             },
         );
+        game_state.crank_tip_lamports = 50;
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -277,6 +405,21 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (vrf_result_pda, _) = Pubkey::find_program_address(
             &[
                 b"VrfResult".as_ref(),
@@ -287,6 +430,9 @@ mod test {
             &program_id,
         );
 
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let operator_bond_pda = add_operator_bond(&mut program_test, 0);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -299,6 +445,8 @@ mod test {
                                 side: CoinFlipSide::Head,
                             },
                         },
+                        close_on_resolve: true,
+                        crank_tip_lamports: 100,
                     },
                 },
                 vec![
@@ -306,10 +454,13 @@ mod test {
                     AccountMeta::new(bettor_user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
-                    AccountMeta::new(host_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
                     AccountMeta::new(vrf_result_pda, false),
                     AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
+                    AccountMeta::new(referral_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -320,7 +471,7 @@ mod test {
         let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
         assert_eq!(bettor_user_account_state.total_bets, 1);
         assert_eq!(bettor_user_account_state.active_vrf_results, 1);
-        assert_eq!(bettor_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000);
+        assert_eq!(bettor_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000 - 100);
         // the stats account should be updated
         let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
         assert_eq!(stats_state.total_bets, 1);
@@ -329,15 +480,22 @@ mod test {
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
         assert_eq!(game_state.unresolved_vrf_result, 1);
         assert_eq!(game_state.total_lamports_in, 2000);
-        // the host user account should be updated
-        let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(host_user_account_pda).await.unwrap();
-        assert_eq!(host_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        // the host lamports should be locked in the game bankroll
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000 - 50);
+        assert_eq!(game_bankroll_state.locked_lamports, 2000 * 9900 / 10000);
+        // the wager should be added to the referred volume
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.referred_volume, 2000);
         // the vrf result account should be created
         let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
         assert_eq!(vrf_result_state.account_type, StateAccountType::Vrf);
         assert!(!vrf_result_state.is_fullfilled);
         assert!(!vrf_result_state.is_used);
         assert!(!vrf_result_state.marked_for_close);
+        assert!(vrf_result_state.close_on_resolve);
+        // the crank tips of the bettor and the game should be escrowed
+        assert_eq!(vrf_result_state.crank_tip_lamports, 150);
         assert_eq!(vrf_result_state.owner, bettor.pubkey());
         assert_eq!(vrf_result_state.game, game_pda);
         assert_eq!(vrf_result_state.bet_id, 0);
@@ -355,8 +513,159 @@ mod test {
     }
 
     #[tokio::test]
-    #[should_panic(expected = "Custom(9)")]
-    async fn test_game_place_bet_err_game_not_active() {
+    async fn test_game_place_bet_success_legacy_referral() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let bettor = Keypair::new();
+        program_test.add_account(
+            bettor.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let referral = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.pubkey().as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor.pubkey(), Some(referral), Some("Bettor".to_string()));
+        bettor_user_account_state.current_lamports = LAMPORTS_PER_SOL;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        // a bettor referred before the referral PDAs, their referrer has none
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let host = Pubkey::new_unique();
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.crank_tip_lamports = 50;
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (vrf_result_pda, _) = Pubkey::find_program_address(
+            &[
+                b"VrfResult".as_ref(),
+                game_pda.as_ref(),
+                bettor.pubkey().as_ref(),
+                &bettor_user_account_state.total_bets.to_le_bytes(),
+            ],
+            &program_id,
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let operator_bond_pda = add_operator_bond(&mut program_test, 0);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GamePlaceBet {
+                    args: GamePlaceBetArgs {
+                        bet_input: BetInput::CoinFlip {
+                            input: CoinFlipInput {
+                                wager: 2000,
+                                side: CoinFlipSide::Head,
+                            },
+                        },
+                        close_on_resolve: true,
+                        crank_tip_lamports: 100,
+                    },
+                },
+                vec![
+                    AccountMeta::new(bettor.pubkey(), true),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
+                    AccountMeta::new(referral_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&bettor, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        // the bet should be placed without updating a referred volume
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        assert_eq!(bettor_user_account_state.total_bets, 1);
+        assert_eq!(bettor_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000 - 100);
+        let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
+        assert_eq!(vrf_result_state.locked_bettor_lamports, 2000);
+        assert!(banks_client.get_account(referral_pda).await.unwrap().is_none());
+    }
+
+    /// Compute units of a bet on a coin flip game must stay within the budget, a ceiling rather than a comparison with the
+    /// deserialized accounts, only meaningful against the BPF build run by `cargo test-bpf`
+    #[tokio::test]
+    #[cfg_attr(not(feature = "test-bpf"), ignore)]
+    async fn test_game_place_bet_compute_unit_budget() {
+        const COMPUTE_UNITS_BUDGET: u64 = 60_000;
         let program_id = crate::id();
         let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
 
@@ -385,6 +694,19 @@ mod test {
             },
         );
 
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let referral_data = Referral::new(referral).try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
+        program_test.add_account(
+            referral_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
         let mut stats_state = Stats::new();
         stats_state.total_users = 1;
@@ -402,21 +724,152 @@ mod test {
         );
 
         let host = Pubkey::new_unique();
-        let (host_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), host.as_ref()], &program_id);
-        let mut host_user_account_state = UserAccount::new(host, None, None);
-        host_user_account_state.current_lamports = LAMPORTS_PER_SOL;
-        let host_user_account_data = host_user_account_state.try_to_vec().unwrap();
-        let host_user_account_data_len = host_user_account_data.len();
+        let game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
         program_test.add_account(
-            host_user_account_pda,
+            game_bankroll_pda,
             Account {
-                lamports: Rent::default().minimum_balance(host_user_account_data_len),
-                data: host_user_account_data,
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
                 owner: program_id,
                 ..Default::default()
             },
         );
 
+        let (vrf_result_pda, _) = Pubkey::find_program_address(
+            &[
+                b"VrfResult".as_ref(),
+                game_pda.as_ref(),
+                bettor.pubkey().as_ref(),
+                &bettor_user_account_state.total_bets.to_le_bytes(),
+            ],
+            &program_id,
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let operator_bond_pda = add_operator_bond(&mut program_test, 0);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GamePlaceBet {
+                    args: GamePlaceBetArgs {
+                        bet_input: BetInput::CoinFlip {
+                            input: CoinFlipInput {
+                                wager: 2000,
+                                side: CoinFlipSide::Head,
+                            },
+                        },
+                        close_on_resolve: false,
+                        crank_tip_lamports: 0,
+                    },
+                },
+                vec![
+                    AccountMeta::new(bettor.pubkey(), true),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
+                    AccountMeta::new(referral_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&bettor, &payer], recent_blockhash);
+        let simulation = banks_client.simulate_transaction(transaction).await.unwrap();
+        assert!(matches!(simulation.result, Some(Ok(()))));
+        let units_consumed = simulation.simulation_details.unwrap().units_consumed;
+        assert!(
+            units_consumed <= COMPUTE_UNITS_BUDGET,
+            "GamePlaceBet consumed {} compute units, above the budget of {}",
+            units_consumed,
+            COMPUTE_UNITS_BUDGET
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(9)")]
+    async fn test_game_place_bet_err_game_not_active() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let bettor = Keypair::new();
+        program_test.add_account(
+            bettor.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let referral = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.pubkey().as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor.pubkey(), Some(referral), Some("Bettor".to_string()));
+        bettor_user_account_state.current_lamports = LAMPORTS_PER_SOL;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let host = Pubkey::new_unique();
         let mut game_state = Game::new(
             host,
             1000,
@@ -428,9 +881,9 @@ mod test {
                 },
             },
         );
-        game_state.is_active = false;
+        game_state.set_active(false);
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -444,6 +897,21 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (vrf_result_pda, _) = Pubkey::find_program_address(
             &[
                 b"VrfResult".as_ref(),
@@ -454,6 +922,9 @@ mod test {
             &program_id,
         );
 
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let operator_bond_pda = add_operator_bond(&mut program_test, 0);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -466,6 +937,8 @@ mod test {
                                 side: CoinFlipSide::Head,
                             },
                         },
+                        close_on_resolve: false,
+                        crank_tip_lamports: 0,
                     },
                 },
                 vec![
@@ -473,10 +946,12 @@ mod test {
                     AccountMeta::new(bettor_user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
-                    AccountMeta::new(host_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
                     AccountMeta::new(vrf_result_pda, false),
                     AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -496,9 +971,10 @@ mod test {
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
         assert_eq!(game_state.unresolved_vrf_result, 1);
         assert_eq!(game_state.total_lamports_in, 2000);
-        // the host user account should be updated
-        let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(host_user_account_pda).await.unwrap();
-        assert_eq!(host_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        // the host lamports should be locked in the game bankroll
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        assert_eq!(game_bankroll_state.locked_lamports, 2000 * 9900 / 10000);
         // the vrf result account should be created
         let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
         assert_eq!(vrf_result_state.account_type, StateAccountType::Vrf);
@@ -569,21 +1045,6 @@ mod test {
         );
 
         let host = Pubkey::new_unique();
-        let (host_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), host.as_ref()], &program_id);
-        let mut host_user_account_state = UserAccount::new(host, None, None);
-        host_user_account_state.current_lamports = LAMPORTS_PER_SOL;
-        let host_user_account_data = host_user_account_state.try_to_vec().unwrap();
-        let host_user_account_data_len = host_user_account_data.len();
-        program_test.add_account(
-            host_user_account_pda,
-            Account {
-                lamports: Rent::default().minimum_balance(host_user_account_data_len),
-                data: host_user_account_data,
-                owner: program_id,
-                ..Default::default()
-            },
-        );
-
         let game_state = Game::new(
             host,
             1000,
@@ -596,7 +1057,7 @@ mod test {
             },
         );
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -610,6 +1071,21 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (vrf_result_pda, _) = Pubkey::find_program_address(
             &[
                 b"VrfResult".as_ref(),
@@ -620,6 +1096,9 @@ mod test {
             &program_id,
         );
 
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let operator_bond_pda = add_operator_bond(&mut program_test, 0);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -632,6 +1111,8 @@ mod test {
                                 side: CoinFlipSide::Head,
                             },
                         },
+                        close_on_resolve: false,
+                        crank_tip_lamports: 0,
                     },
                 },
                 vec![
@@ -639,10 +1120,12 @@ mod test {
                     AccountMeta::new(bettor_user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
-                    AccountMeta::new(host_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
                     AccountMeta::new(vrf_result_pda, false),
                     AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -662,9 +1145,10 @@ mod test {
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
         assert_eq!(game_state.unresolved_vrf_result, 1);
         assert_eq!(game_state.total_lamports_in, 2000);
-        // the host user account should be updated
-        let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(host_user_account_pda).await.unwrap();
-        assert_eq!(host_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        // the host lamports should be locked in the game bankroll
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        assert_eq!(game_bankroll_state.locked_lamports, 2000 * 9900 / 10000);
         // the vrf result account should be created
         let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
         assert_eq!(vrf_result_state.account_type, StateAccountType::Vrf);
@@ -735,21 +1219,6 @@ mod test {
         );
 
         let host = Pubkey::new_unique();
-        let (host_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), host.as_ref()], &program_id);
-        let mut host_user_account_state = UserAccount::new(host, None, None);
-        host_user_account_state.current_lamports = LAMPORTS_PER_SOL;
-        let host_user_account_data = host_user_account_state.try_to_vec().unwrap();
-        let host_user_account_data_len = host_user_account_data.len();
-        program_test.add_account(
-            host_user_account_pda,
-            Account {
-                lamports: Rent::default().minimum_balance(host_user_account_data_len),
-                data: host_user_account_data,
-                owner: program_id,
-                ..Default::default()
-            },
-        );
-
         let game_state = Game::new(
             host,
             1000,
@@ -762,7 +1231,7 @@ mod test {
             },
         );
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -776,6 +1245,21 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (vrf_result_pda, _) = Pubkey::find_program_address(
             &[
                 b"VrfResult".as_ref(),
@@ -786,6 +1270,9 @@ mod test {
             &program_id,
         );
 
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let operator_bond_pda = add_operator_bond(&mut program_test, 0);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -798,6 +1285,8 @@ mod test {
                                 side: CoinFlipSide::Head,
                             },
                         },
+                        close_on_resolve: false,
+                        crank_tip_lamports: 0,
                     },
                 },
                 vec![
@@ -805,10 +1294,12 @@ mod test {
                     AccountMeta::new(bettor_user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
-                    AccountMeta::new(host_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
                     AccountMeta::new(vrf_result_pda, false),
                     AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -828,9 +1319,10 @@ mod test {
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
         assert_eq!(game_state.unresolved_vrf_result, 1);
         assert_eq!(game_state.total_lamports_in, 2000);
-        // the host user account should be updated
-        let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(host_user_account_pda).await.unwrap();
-        assert_eq!(host_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        // the host lamports should be locked in the game bankroll
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        assert_eq!(game_bankroll_state.locked_lamports, 2000 * 9900 / 10000);
         // the vrf result account should be created
         let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
         assert_eq!(vrf_result_state.account_type, StateAccountType::Vrf);
@@ -901,21 +1393,6 @@ mod test {
         );
 
         let host = Pubkey::new_unique();
-        let (host_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), host.as_ref()], &program_id);
-        let mut host_user_account_state = UserAccount::new(host, None, None);
-        host_user_account_state.current_lamports = 1;
-        let host_user_account_data = host_user_account_state.try_to_vec().unwrap();
-        let host_user_account_data_len = host_user_account_data.len();
-        program_test.add_account(
-            host_user_account_pda,
-            Account {
-                lamports: Rent::default().minimum_balance(host_user_account_data_len),
-                data: host_user_account_data,
-                owner: program_id,
-                ..Default::default()
-            },
-        );
-
         let game_state = Game::new(
             host,
             1000,
@@ -928,7 +1405,7 @@ mod test {
             },
         );
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -942,6 +1419,21 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 1;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (vrf_result_pda, _) = Pubkey::find_program_address(
             &[
                 b"VrfResult".as_ref(),
@@ -952,6 +1444,9 @@ mod test {
             &program_id,
         );
 
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let operator_bond_pda = add_operator_bond(&mut program_test, 0);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -964,6 +1459,8 @@ mod test {
                                 side: CoinFlipSide::Head,
                             },
                         },
+                        close_on_resolve: false,
+                        crank_tip_lamports: 0,
                     },
                 },
                 vec![
@@ -971,10 +1468,12 @@ mod test {
                     AccountMeta::new(bettor_user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(game_pda, false),
-                    AccountMeta::new(host_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
                     AccountMeta::new(vrf_result_pda, false),
                     AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -994,9 +1493,10 @@ mod test {
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
         assert_eq!(game_state.unresolved_vrf_result, 1);
         assert_eq!(game_state.total_lamports_in, 2000);
-        // the host user account should be updated
-        let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(host_user_account_pda).await.unwrap();
-        assert_eq!(host_user_account_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        // the host lamports should be locked in the game bankroll
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.current_lamports, LAMPORTS_PER_SOL - 2000 * 9900 / 10000);
+        assert_eq!(game_bankroll_state.locked_lamports, 2000 * 9900 / 10000);
         // the vrf result account should be created
         let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
         assert_eq!(vrf_result_state.account_type, StateAccountType::Vrf);
@@ -1018,4 +1518,161 @@ mod test {
             panic!()
         }
     }
+
+    /// Place a bet on a coin flip game, the operator bond PDA being added only when given
+    async fn run_game_place_bet_with_operator_bond(operator_bond_pending_withdrawal_lamports: Option<u64>) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let bettor = Keypair::new();
+        program_test.add_account(
+            bettor.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.pubkey().as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor.pubkey(), None, Some("Bettor".to_string()));
+        bettor_user_account_state.current_lamports = LAMPORTS_PER_SOL;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let host = Pubkey::new_unique();
+        let game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = LAMPORTS_PER_SOL;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (vrf_result_pda, _) = Pubkey::find_program_address(
+            &[
+                b"VrfResult".as_ref(),
+                game_pda.as_ref(),
+                bettor.pubkey().as_ref(),
+                &bettor_user_account_state.total_bets.to_le_bytes(),
+            ],
+            &program_id,
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let operator_bond_pda = match operator_bond_pending_withdrawal_lamports {
+            Some(pending_withdrawal_lamports) => add_operator_bond(&mut program_test, pending_withdrawal_lamports),
+            None => Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &program_id).0,
+        };
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GamePlaceBet {
+                    args: GamePlaceBetArgs {
+                        bet_input: BetInput::CoinFlip {
+                            input: CoinFlipInput {
+                                wager: 2000,
+                                side: CoinFlipSide::Head,
+                            },
+                        },
+                        close_on_resolve: false,
+                        crank_tip_lamports: 0,
+                    },
+                },
+                vec![
+                    AccountMeta::new(bettor.pubkey(), true),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new_readonly(operator_bond_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&bettor, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_game_place_bet_success_bonded_operator() {
+        run_game_place_bet_with_operator_bond(Some(0)).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(27)")]
+    async fn test_game_place_bet_err_operator_bond_too_low() {
+        // the operator requested to withdraw the whole bond
+        run_game_place_bet_with_operator_bond(Some(LAMPORTS_PER_SOL)).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(27)")]
+    async fn test_game_place_bet_err_operator_bond_missing() {
+        run_game_place_bet_with_operator_bond(None).await;
+    }
 }
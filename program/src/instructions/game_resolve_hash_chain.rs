@@ -0,0 +1,292 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    constants::OPERATOR_PUBKEY,
+    error::BettingError,
+    state::{bankroll::GameBankroll, game::Game, stats::Stats, user_account::UserAccount, vrf_result::VrfResult},
+};
+
+use super::{game_resolve_vrf_result::GameResolveVrfResultAccounts, vrf_result_reveal_hash_chain::reveal_hash_chain_link};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct GameResolveHashChainArgs {
+    pub server_seed: [u8; 32],
+}
+
+#[derive(Accounts)]
+pub struct GameResolveHashChainAccounts<'a, 'info> {
+    #[account(signer, address = game_state.host, name = "host", desc = "The wallet account of the host")]
+    pub host_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        constraint = game_account_info.key == &vrf_result_state.game @ BettingError::WrongPubkey,
+        constraint = bettor_user_account_state.authority == vrf_result_state.owner @ BettingError::WrongPubkey,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_account_info.key],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", bettor_user_account_state.authority],
+        stored_bump = bettor_user_account_state.bump,
+        name = "bettor_user_account",
+        desc = "Bettor user account"
+    )]
+    pub bettor_user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "bettor_user_limits_pda", desc = "Bettor user limits PDA Account, may be uninitialized")]
+    pub bettor_user_limits_account_info: &'a AccountInfo<'info>,
+    #[account(writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        signer,
+        writable,
+        optional = !iter.as_slice().is_empty(),
+        name = "cranker",
+        desc = "Signer resolving the bet, paid its crank tip instead of the operator"
+    )]
+    pub cranker_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(game_account_info)]
+    pub game_state: RefMut<'a, Game>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+    #[state(game_bankroll_account_info)]
+    pub game_bankroll_state: GameBankroll,
+    #[state(bettor_user_account_info)]
+    pub bettor_user_account_state: RefMut<'a, UserAccount>,
+    #[state(stats_account_info)]
+    pub stats_state: RefMut<'a, Stats>,
+}
+
+pub fn game_resolve_hash_chain(_program_id: &Pubkey, accounts: &[AccountInfo], args: GameResolveHashChainArgs) -> ProgramResult {
+    msg!("Instruction: GameResolveHashChain");
+    // get accounts
+    let GameResolveHashChainAccounts {
+        game_account_info,
+        vrf_result_account_info,
+        game_bankroll_account_info,
+        bettor_user_account_info,
+        stats_account_info,
+        bettor_user_limits_account_info,
+        op_account_info,
+        cranker_account_info,
+        mut game_state,
+        mut vrf_result_state,
+        game_bankroll_state,
+        bettor_user_account_state,
+        stats_state,
+        ..
+    } = GameResolveHashChainAccounts::try_from_accounts(accounts)?;
+    // reveal the server seed of the bet and settle it in the same instruction
+    reveal_hash_chain_link(vrf_result_account_info, &mut vrf_result_state, &mut game_state, &args.server_seed)?;
+    GameResolveVrfResultAccounts {
+        game_account_info,
+        vrf_result_account_info,
+        game_bankroll_account_info,
+        bettor_user_account_info,
+        stats_account_info,
+        bettor_user_limits_account_info,
+        op_account_info,
+        cranker_account_info,
+        game_state,
+        vrf_result_state,
+        game_bankroll_state,
+        bettor_user_account_state,
+        stats_state,
+    }
+    .resolve()
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        hash::hashv,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, BanksClient, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        constants::OPERATOR_PUBKEY,
+        instructions::BettingInstruction,
+        state::{
+            bankroll::GameBankroll,
+            game::{
+                coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+                BetInput, Game, GameTypeConfig, ProcessVrfResult,
+            },
+            randomness::{Randomness, RandomnessSourceConfig},
+            stats::Stats,
+            user_account::UserAccount,
+            vrf_result::VrfResult,
+        },
+    };
+
+    use super::GameResolveHashChainArgs;
+
+    const CONFIG: CoinFlipConfig = CoinFlipConfig {
+        host_probability_advantage: 100,
+        payout_rate: 9900,
+    };
+
+    fn add_account<T: BorshSerialize>(program_test: &mut ProgramTest, pubkey: Pubkey, state: &T) {
+        let data = state.try_to_vec().unwrap();
+        program_test.add_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: crate::id(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Resolve the first bet of a hash chain game, signed by the host or by another wallet
+    async fn resolve_hash_chain(is_signed_by_host: bool) -> (BanksClient, VrfResult, Pubkey, Pubkey, Pubkey) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Keypair::new();
+        let signer = if is_signed_by_host {
+            Keypair::from_bytes(&host.to_bytes()).unwrap()
+        } else {
+            Keypair::new()
+        };
+        program_test.add_account(
+            signer.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let bettor = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, None, None);
+        bettor_user_account_state.active_vrf_results = 1;
+        add_account(&mut program_test, bettor_user_account_pda, &bettor_user_account_state);
+
+        let server_seed = [9; 32];
+        let mut game_state = Game::new(host.pubkey(), 1000, 10000, GameTypeConfig::CoinFlip { config: CONFIG });
+        game_state
+            .set_randomness_source(RandomnessSourceConfig::HashChain {
+                head: hashv(&[&server_seed]).to_bytes(),
+            })
+            .unwrap();
+        game_state.next_randomness().unwrap();
+        game_state.unresolved_vrf_result = 1;
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        add_account(&mut program_test, game_pda, &game_state);
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host.pubkey());
+        game_bankroll_state.locked_lamports = 1980;
+        add_account(&mut program_test, game_bankroll_pda, &game_bankroll_state);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        add_account(&mut program_test, stats_pda, &Stats::new());
+
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            0,
+            [1; 72],
+            2000,
+            1980,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.randomness = Randomness::HashChain { index: 0 };
+        let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 0);
+        add_account(&mut program_test, vrf_result_pda, &vrf_result_state);
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveHashChain {
+                    args: GameResolveHashChainArgs { server_seed },
+                },
+                vec![
+                    AccountMeta::new_readonly(signer.pubkey(), true),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&signer, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the bet as it is fullfilled by the server seed
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state.beta[..32].copy_from_slice(&server_seed);
+        (banks_client, vrf_result_state, game_pda, vrf_result_pda, bettor_user_account_pda)
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_hash_chain_success() {
+        let (mut banks_client, fullfilled_vrf_result_state, game_pda, vrf_result_pda, bettor_user_account_pda) = resolve_hash_chain(true).await;
+        let (host_gain, bettor_gain) = CONFIG
+            .process_vrf_result(&fullfilled_vrf_result_state, &fullfilled_vrf_result_state.randomness)
+            .unwrap();
+        assert_eq!(host_gain + bettor_gain, 2000 + 1980);
+
+        // the server seed should be the new head of the hash chain
+        let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
+        assert_eq!(game_state.hash_chain_head, [9; 32]);
+        assert_eq!(game_state.hash_chain_revealed, 1);
+        assert_eq!(game_state.unresolved_vrf_result, 0);
+        assert_eq!(game_state.total_lamports_out, bettor_gain);
+        // the bet should be fullfilled and settled in the same instruction
+        let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
+        assert!(vrf_result_state.is_fullfilled);
+        assert!(vrf_result_state.is_used);
+        assert_eq!(vrf_result_state.beta, fullfilled_vrf_result_state.beta);
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        assert_eq!(bettor_user_account_state.current_lamports, bettor_gain);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(4)")]
+    async fn test_game_resolve_hash_chain_err_not_host() {
+        resolve_hash_chain(false).await;
+    }
+}
@@ -0,0 +1,313 @@
+use std::cell::RefMut;
+
+use borsh::BorshSerialize;
+use solana_program::{account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    constants::OPERATOR_PUBKEY,
+    error::BettingError,
+    math::CheckedMath,
+    state::{
+        bankroll::GameBankroll, game::Game, operator_bond::OperatorBond, randomness::Randomness, stats::Stats, user_account::UserAccount, vrf_result::VrfResult,
+    },
+};
+
+use super::game_resolve_vrf_result::GameResolveVrfResultAccounts;
+
+#[derive(Accounts)]
+pub struct GameResolveLateFullfillAccounts<'a, 'info> {
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        constraint = game_account_info.key == &vrf_result_state.game @ BettingError::WrongPubkey,
+        constraint = bettor_user_account_state.authority == vrf_result_state.owner @ BettingError::WrongPubkey,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_account_info.key],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", bettor_user_account_state.authority],
+        stored_bump = bettor_user_account_state.bump,
+        name = "bettor_user_account",
+        desc = "Bettor user account"
+    )]
+    pub bettor_user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "bettor_user_limits_pda", desc = "Bettor user limits PDA Account, may be uninitialized")]
+    pub bettor_user_limits_account_info: &'a AccountInfo<'info>,
+    #[account(writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"OperatorBond"],
+        stored_bump = operator_bond_state.bump,
+        name = "operator_bond_pda",
+        desc = "Operator bond PDA Account"
+    )]
+    pub operator_bond_account_info: &'a AccountInfo<'info>,
+    #[account(
+        signer,
+        writable,
+        optional = !iter.as_slice().is_empty(),
+        name = "cranker",
+        desc = "Signer resolving the bet, paid its crank tip instead of the operator"
+    )]
+    pub cranker_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(game_account_info)]
+    pub game_state: RefMut<'a, Game>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+    #[state(game_bankroll_account_info)]
+    pub game_bankroll_state: GameBankroll,
+    #[state(bettor_user_account_info)]
+    pub bettor_user_account_state: RefMut<'a, UserAccount>,
+    #[state(stats_account_info)]
+    pub stats_state: RefMut<'a, Stats>,
+    #[state(operator_bond_account_info)]
+    pub operator_bond_state: OperatorBond,
+}
+
+pub fn game_resolve_late_fullfill(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    msg!("Instruction: GameResolveLateFullfill");
+    // get accounts
+    let GameResolveLateFullfillAccounts {
+        game_account_info,
+        vrf_result_account_info,
+        game_bankroll_account_info,
+        bettor_user_account_info,
+        stats_account_info,
+        bettor_user_limits_account_info,
+        op_account_info,
+        operator_bond_account_info,
+        cranker_account_info,
+        game_state,
+        mut vrf_result_state,
+        game_bankroll_state,
+        mut bettor_user_account_state,
+        stats_state,
+        mut operator_bond_state,
+    } = GameResolveLateFullfillAccounts::try_from_accounts(accounts)?;
+    // check conditions
+    if vrf_result_state.is_fullfilled {
+        msg!("VRF result account {} is already fullfilled", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyFullfilled));
+    }
+    if vrf_result_state.is_used {
+        msg!("VRF result account {} is already used", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyUsed));
+    }
+    if vrf_result_state.randomness != Randomness::Vrf {
+        msg!("VRF result account {} is not settled by the operator", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::WrongRandomnessSource));
+    }
+    let fullfill_deadline = operator_bond_state.config.fullfill_deadline;
+    if Clock::get()?.unix_timestamp < vrf_result_state.placed_at().try_add(fullfill_deadline)? {
+        msg!("The operator has {} seconds to fullfill the bet", fullfill_deadline);
+        return Err(ProgramError::from(BettingError::FullfillNotLate));
+    }
+    // update operator bond account
+    let penalty = operator_bond_state.slash(vrf_result_state.locked_bettor_lamports)?;
+    operator_bond_state.serialize(&mut &mut operator_bond_account_info.data.borrow_mut()[..])?;
+    // the penalty joins the lamports of the user accounts held by the stats account
+    **operator_bond_account_info.lamports.borrow_mut() = operator_bond_account_info.lamports().try_sub(penalty)?;
+    **stats_account_info.lamports.borrow_mut() = stats_account_info.lamports().try_add(penalty)?;
+    bettor_user_account_state.current_lamports = bettor_user_account_state.current_lamports.try_add(penalty)?;
+    vrf_result_state.slashed_lamports = penalty;
+    // the bet is called off, both sides take back the lamports they locked
+    let host_gain = vrf_result_state.locked_host_lamports;
+    let bettor_gain = vrf_result_state.locked_bettor_lamports;
+    GameResolveVrfResultAccounts {
+        game_account_info,
+        vrf_result_account_info,
+        game_bankroll_account_info,
+        bettor_user_account_info,
+        stats_account_info,
+        bettor_user_limits_account_info,
+        op_account_info,
+        cranker_account_info,
+        game_state,
+        vrf_result_state,
+        game_bankroll_state,
+        bettor_user_account_state,
+        stats_state,
+    }
+    .settle(host_gain, bettor_gain, false)
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, BanksClient, ProgramTest};
+    use solana_sdk::{account::Account, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        constants::OPERATOR_PUBKEY,
+        instructions::BettingInstruction,
+        state::{
+            bankroll::GameBankroll,
+            game::{
+                coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+                BetInput, Game, GameTypeConfig,
+            },
+            operator_bond::{OperatorBond, OperatorBondConfig},
+            stats::Stats,
+            user_account::UserAccount,
+            vrf_result::VrfResult,
+        },
+    };
+
+    fn add_account<T: BorshSerialize>(program_test: &mut ProgramTest, pubkey: Pubkey, state: &T, extra_lamports: u64) {
+        let data = state.try_to_vec().unwrap();
+        program_test.add_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()) + extra_lamports,
+                data,
+                owner: crate::id(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Slash the operator for an unfullfilled bet placed at the given unix timestamp
+    async fn resolve_late_fullfill(placed_at: i64) -> (BanksClient, Pubkey, Pubkey, Pubkey, Pubkey) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, None, None);
+        bettor_user_account_state.active_vrf_results = 1;
+        add_account(&mut program_test, bettor_user_account_pda, &bettor_user_account_state, 0);
+
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = 1;
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        add_account(&mut program_test, game_pda, &game_state, 0);
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.locked_lamports = 1980;
+        add_account(&mut program_test, game_bankroll_pda, &game_bankroll_state, 0);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        add_account(&mut program_test, stats_pda, &Stats::new(), 0);
+
+        let (operator_bond_pda, operator_bond_bump) = Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &program_id);
+        let mut operator_bond_state = OperatorBond::new(
+            OperatorBondConfig {
+                min_bond_lamports: 10000,
+                withdrawal_delay: 0,
+                fullfill_deadline: 60,
+                slash_penalty: 5000,
+            },
+            operator_bond_bump,
+        );
+        operator_bond_state.bonded_lamports = 10000;
+        add_account(&mut program_test, operator_bond_pda, &operator_bond_state, 10000);
+
+        let mut alpha = [0; 72];
+        alpha[..8].copy_from_slice(&placed_at.to_le_bytes());
+        let vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            0,
+            alpha,
+            2000,
+            1980,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 0);
+        add_account(&mut program_test, vrf_result_pda, &vrf_result_state, 0);
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveLateFullfill,
+                vec![
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                    AccountMeta::new(operator_bond_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        (banks_client, vrf_result_pda, game_bankroll_pda, bettor_user_account_pda, operator_bond_pda)
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_late_fullfill_success() {
+        let (mut banks_client, vrf_result_pda, game_bankroll_pda, bettor_user_account_pda, operator_bond_pda) = resolve_late_fullfill(0).await;
+
+        // the bettor should get the wager back and half of it out of the bond
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        assert_eq!(bettor_user_account_state.current_lamports, 2000 + 1000);
+        // the host should get the locked lamports back
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.locked_lamports, 0);
+        assert_eq!(game_bankroll_state.current_lamports, 1980);
+        let operator_bond_state: OperatorBond = banks_client.get_account_data_with_borsh(operator_bond_pda).await.unwrap();
+        assert_eq!(operator_bond_state.bonded_lamports, 9000);
+        assert_eq!(operator_bond_state.total_slashed_lamports, 1000);
+        // the penalty should be recorded on the settled bet
+        let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
+        assert!(vrf_result_state.is_used);
+        assert_eq!(vrf_result_state.slashed_lamports, 1000);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(28)")]
+    async fn test_game_resolve_late_fullfill_err_not_late() {
+        resolve_late_fullfill(i64::MAX / 2).await;
+    }
+}
@@ -0,0 +1,178 @@
+use solana_program::{account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar};
+
+use crate::{constants::HASH_CHAIN_REVEAL_TIMEOUT, error::BettingError, math::CheckedMath, state::randomness::Randomness};
+
+use super::game_resolve_vrf_result::GameResolveVrfResultAccounts;
+
+pub fn game_resolve_timed_out(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    msg!("Instruction: GameResolveTimedOut");
+    // get accounts, an unrevealed bet is settled with the accounts of a resolve, only the conditions differ
+    let accounts = GameResolveVrfResultAccounts::try_from_accounts(accounts)?;
+    let vrf_result_state = &accounts.vrf_result_state;
+    // check conditions
+    if vrf_result_state.is_fullfilled {
+        msg!("VRF result account {} is already fullfilled", accounts.vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyFullfilled));
+    }
+    if vrf_result_state.is_used {
+        msg!("VRF result account {} is already used", accounts.vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyUsed));
+    }
+    if !matches!(vrf_result_state.randomness, Randomness::HashChain { .. }) {
+        msg!("VRF result account {} is not settled by a hash chain", accounts.vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::WrongRandomnessSource));
+    }
+    if Clock::get()?.unix_timestamp < vrf_result_state.placed_at().try_add(HASH_CHAIN_REVEAL_TIMEOUT)? {
+        msg!("The host has {} seconds to reveal the bet", HASH_CHAIN_REVEAL_TIMEOUT);
+        return Err(ProgramError::from(BettingError::RevealNotTimedOut));
+    }
+    // the host forfeits the bet, the bettor takes the lamports locked on both sides
+    let bettor_gain = vrf_result_state.locked_bettor_lamports.try_add(vrf_result_state.locked_host_lamports)?;
+    accounts.settle(0, bettor_gain, false)
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, BanksClient, ProgramTest};
+    use solana_sdk::{account::Account, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        constants::OPERATOR_PUBKEY,
+        instructions::BettingInstruction,
+        state::{
+            bankroll::GameBankroll,
+            game::{
+                coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+                BetInput, Game, GameTypeConfig,
+            },
+            randomness::{Randomness, RandomnessSourceConfig},
+            stats::Stats,
+            user_account::UserAccount,
+            vrf_result::VrfResult,
+        },
+    };
+
+    fn add_account<T: BorshSerialize>(program_test: &mut ProgramTest, pubkey: Pubkey, state: &T) {
+        let data = state.try_to_vec().unwrap();
+        program_test.add_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: crate::id(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Resolve an unrevealed hash chain bet placed at the given unix timestamp
+    async fn resolve_timed_out(placed_at: i64) -> (BanksClient, Pubkey, Pubkey, Pubkey, Pubkey) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, None, None);
+        bettor_user_account_state.active_vrf_results = 1;
+        add_account(&mut program_test, bettor_user_account_pda, &bettor_user_account_state);
+
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.set_randomness_source(RandomnessSourceConfig::HashChain { head: [7; 32] }).unwrap();
+        game_state.next_randomness().unwrap();
+        game_state.unresolved_vrf_result = 1;
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        add_account(&mut program_test, game_pda, &game_state);
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.locked_lamports = 1980;
+        add_account(&mut program_test, game_bankroll_pda, &game_bankroll_state);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        add_account(&mut program_test, stats_pda, &Stats::new());
+
+        let mut alpha = [0; 72];
+        alpha[..8].copy_from_slice(&placed_at.to_le_bytes());
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            0,
+            alpha,
+            2000,
+            1980,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.randomness = Randomness::HashChain { index: 0 };
+        let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 0);
+        add_account(&mut program_test, vrf_result_pda, &vrf_result_state);
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveTimedOut,
+                vec![
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        (banks_client, game_pda, vrf_result_pda, game_bankroll_pda, bettor_user_account_pda)
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_timed_out_success() {
+        let (mut banks_client, game_pda, vrf_result_pda, game_bankroll_pda, bettor_user_account_pda) = resolve_timed_out(0).await;
+
+        // the bettor should take the lamports locked on both sides, without commission
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        assert_eq!(bettor_user_account_state.current_lamports, 2000 + 1980);
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.locked_lamports, 0);
+        assert_eq!(game_bankroll_state.current_lamports, 0);
+        let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
+        assert_eq!(game_state.unresolved_vrf_result, 0);
+        // the bet should be settled without being fullfilled
+        let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
+        assert!(vrf_result_state.is_used);
+        assert!(!vrf_result_state.is_fullfilled);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(25)")]
+    async fn test_game_resolve_timed_out_err_not_timed_out() {
+        resolve_timed_out(i64::MAX / 2).await;
+    }
+}
@@ -1,88 +1,165 @@
 use borsh::BorshSerialize;
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    pubkey::Pubkey,
-};
+use std::cell::RefMut;
+
+use solana_program::{account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, pubkey::Pubkey, sysvar::Sysvar};
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
-    state::{game::Game, stats::Stats, user_account::UserAccount, vrf_result::VrfResult, BettingAccount},
+    constants::OPERATOR_PUBKEY,
+    error::BettingError,
+    events::BetResolved,
+    math::{signed_diff, CheckedMath},
+    state::{bankroll::GameBankroll, game::Game, stats::Stats, user_account::UserAccount, user_limits::UserLimits, vrf_result::VrfResult},
 };
 
+/// Anyone can resolve a bet, the crank tip of the bet is paid to the optional cranker, the operator otherwise.
+#[derive(Accounts)]
+pub struct GameResolveVrfResultAccounts<'a, 'info> {
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        constraint = game_account_info.key == &vrf_result_state.game @ BettingError::WrongPubkey,
+        constraint = bettor_user_account_state.authority == vrf_result_state.owner @ BettingError::WrongPubkey,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_account_info.key],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", bettor_user_account_state.authority],
+        stored_bump = bettor_user_account_state.bump,
+        name = "bettor_user_account",
+        desc = "Bettor user account"
+    )]
+    pub bettor_user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "bettor_user_limits_pda", desc = "Bettor user limits PDA Account, may be uninitialized")]
+    pub bettor_user_limits_account_info: &'a AccountInfo<'info>,
+    #[account(writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        signer,
+        writable,
+        optional = !iter.as_slice().is_empty(),
+        name = "cranker",
+        desc = "Signer resolving the bet, paid its crank tip instead of the operator"
+    )]
+    pub cranker_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(game_account_info)]
+    pub game_state: RefMut<'a, Game>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+    #[state(game_bankroll_account_info)]
+    pub game_bankroll_state: GameBankroll,
+    #[state(bettor_user_account_info)]
+    pub bettor_user_account_state: RefMut<'a, UserAccount>,
+    #[state(stats_account_info)]
+    pub stats_state: RefMut<'a, Stats>,
+}
+
 pub fn game_resolve_vrf_result(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     msg!("Instruction: GameResolveVrfResult");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let game_account_info = next_account_info(iter)?;
-    let vrf_result_account_info = next_account_info(iter)?;
-    let host_user_account_info = next_account_info(iter)?;
-    let bettor_user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-
-    let mut game_state = Game::try_from_account_info(game_account_info)?;
-    let mut vrf_result_state = VrfResult::try_from_account_info(vrf_result_account_info)?;
-    let mut host_user_account_state = UserAccount::try_from_account_info(host_user_account_info)?;
-    let mut bettor_user_account_state = UserAccount::try_from_account_info(bettor_user_account_info)?;
-    let mut stats_state = Stats::try_from_account_info(stats_account_info)?;
-
-    // check accounts
-    check_is_writable(game_account_info)?;
-    let game_common_config_vec = game_state.common_config.try_to_vec()?;
-    let game_type_config_vec = game_state.game_type_config.try_to_vec()?;
-    check_pda_cannonical_bump(
-        game_account_info,
-        &[b"Game".as_ref(), game_common_config_vec.as_slice(), game_type_config_vec.as_slice()],
-    )?;
-
-    check_is_writable(vrf_result_account_info)?;
-    check_pda_cannonical_bump(
-        vrf_result_account_info,
-        &[
-            b"VrfResult".as_ref(),
-            vrf_result_state.game.as_ref(),
-            vrf_result_state.owner.as_ref(),
-            &vrf_result_state.bet_id.to_le_bytes(),
-        ],
-    )?;
-    check_pubkey_eq(game_account_info, &vrf_result_state.game)?;
-    assert_eq!(&bettor_user_account_state.authority, &vrf_result_state.owner);
-
-    check_is_writable(host_user_account_info)?;
-    check_pda_cannonical_bump(host_user_account_info, &[b"UserAccount".as_ref(), host_user_account_state.authority.as_ref()])?;
-
-    check_is_writable(bettor_user_account_info)?;
-    check_pda_cannonical_bump(
-        bettor_user_account_info,
-        &[b"UserAccount".as_ref(), bettor_user_account_state.authority.as_ref()],
-    )?;
-
-    check_is_writable(stats_account_info)?;
-    check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    // bet result
-    let game_type_dyn = game_state.game_type_config.get_dyn_config();
-    let (host_gain, bettor_gain) = game_type_dyn.process_vrf_result(&vrf_result_state)?;
-    // update game account
-    game_state.unresolved_vrf_result -= 1;
-    game_state.total_lamports_out += bettor_gain;
-    game_state.serialize(&mut &mut game_account_info.data.borrow_mut()[..])?;
-    // update vrf result account
-    vrf_result_state.is_used = true;
-    vrf_result_state.serialize(&mut &mut vrf_result_account_info.data.borrow_mut()[..])?;
-    // update host user account
-    host_user_account_state.current_lamports += host_gain;
-    host_user_account_state.serialize(&mut &mut host_user_account_info.data.borrow_mut()[..])?;
-    // update bettor user account
-    bettor_user_account_state.current_lamports += bettor_gain;
-    bettor_user_account_state.serialize(&mut &mut bettor_user_account_info.data.borrow_mut()[..])?;
-    // update stats account
-    stats_state.total_lamports_won_by_bettors += bettor_gain - vrf_result_state.locked_bettor_lamports;
-    stats_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
-
-    Ok(())
+    GameResolveVrfResultAccounts::try_from_accounts(accounts)?.resolve()
+}
+
+impl<'a, 'info> GameResolveVrfResultAccounts<'a, 'info> {
+    /// Settle a fullfilled bet with the draws of its randomness source
+    pub(crate) fn resolve(self) -> ProgramResult {
+        let game_type_dyn = self.game_state.game_type_config()?.get_dyn_config();
+        let (host_gain, bettor_gain) = game_type_dyn.process_vrf_result(&self.vrf_result_state, &self.vrf_result_state.randomness)?;
+        self.settle(host_gain, bettor_gain, true)
+    }
+    /// Pay out a bet, the commission is only charged on bets settled by their randomness
+    pub(crate) fn settle(self, host_gain: u64, bettor_gain: u64, is_commission_charged: bool) -> ProgramResult {
+        let GameResolveVrfResultAccounts {
+            vrf_result_account_info,
+            game_bankroll_account_info,
+            bettor_user_account_info,
+            stats_account_info,
+            bettor_user_limits_account_info,
+            op_account_info,
+            cranker_account_info,
+            mut game_state,
+            mut vrf_result_state,
+            mut game_bankroll_state,
+            mut bettor_user_account_state,
+            mut stats_state,
+            ..
+        } = self;
+        // the commission is charged on the net win of the bettor
+        let (host_commission, operator_commission) = if is_commission_charged {
+            game_state.get_commission(bettor_gain.saturating_sub(vrf_result_state.locked_bettor_lamports))?
+        } else {
+            (0, 0)
+        };
+        let bettor_gain = bettor_gain.try_sub(host_commission)?.try_sub(operator_commission)?;
+        // update game account, the zero-copy states are written in place
+        game_state.unresolved_vrf_result = game_state.unresolved_vrf_result.try_sub(1)?;
+        game_state.total_lamports_out = game_state.total_lamports_out.try_add(bettor_gain)?;
+        // update vrf result account, or close it when the bettor opted in and keep its outcome in the event
+        vrf_result_state.is_used = true;
+        BetResolved::new(*vrf_result_account_info.key, &vrf_result_state, bettor_gain, host_gain).emit()?;
+        if vrf_result_state.close_on_resolve {
+            let rent_lamports = vrf_result_account_info.lamports();
+            vrf_result_account_info.data.borrow_mut().fill(0);
+            vrf_result_account_info.realloc(0, false)?;
+            **vrf_result_account_info.lamports.borrow_mut() = 0;
+            // the rent is refunded to the balance of the bettor, held by the stats account
+            **stats_account_info.lamports.borrow_mut() = stats_account_info.lamports().try_add(rent_lamports)?;
+            bettor_user_account_state.current_lamports = bettor_user_account_state.current_lamports.try_add(rent_lamports)?;
+            bettor_user_account_state.active_vrf_results = bettor_user_account_state.active_vrf_results.try_sub(1)?;
+        } else {
+            vrf_result_state.serialize(&mut &mut vrf_result_account_info.data.borrow_mut()[..])?;
+        }
+        // update game bankroll account
+        game_bankroll_state.settle(vrf_result_state.locked_host_lamports, host_gain)?;
+        game_bankroll_state.unclaimed_fee_lamports = game_bankroll_state.unclaimed_fee_lamports.try_add(host_commission)?;
+        game_bankroll_state.serialize(&mut &mut game_bankroll_account_info.data.borrow_mut()[..])?;
+        // update bettor user account
+        bettor_user_account_state.current_lamports = bettor_user_account_state.current_lamports.try_add(bettor_gain)?;
+        bettor_user_account_state.record_bet_result(vrf_result_state.locked_bettor_lamports, bettor_gain)?;
+        // update stats account
+        stats_state.total_bettor_pnl = stats_state
+            .total_bettor_pnl
+            .try_add(signed_diff(bettor_gain, vrf_result_state.locked_bettor_lamports)?)?;
+        stats_state.total_host_pnl = stats_state
+            .total_host_pnl
+            .try_add(signed_diff(host_gain, vrf_result_state.locked_host_lamports)?)?;
+        stats_state.total_host_commission_lamports = stats_state.total_host_commission_lamports.try_add(host_commission)?;
+        stats_state.total_operator_commission_lamports = stats_state.total_operator_commission_lamports.try_add(operator_commission)?;
+        **stats_account_info.lamports.borrow_mut() = stats_account_info.lamports().try_sub(operator_commission)?;
+        // transfer the operator commission to op
+        **op_account_info.lamports.borrow_mut() = op_account_info.lamports().try_add(operator_commission)?;
+        // the crank tip escrowed in the stats account goes to the signer resolving the bet, or to the operator running the default resolver
+        let cranker_account_info = cranker_account_info.unwrap_or(op_account_info);
+        **stats_account_info.lamports.borrow_mut() = stats_account_info.lamports().try_sub(vrf_result_state.crank_tip_lamports)?;
+        **cranker_account_info.lamports.borrow_mut() = cranker_account_info.lamports().try_add(vrf_result_state.crank_tip_lamports)?;
+        // payouts count against the net loss of the bettor
+        if let Some(mut bettor_user_limits_state) = UserLimits::load(bettor_user_account_info.key, bettor_user_limits_account_info)? {
+            bettor_user_limits_state.record_payout(Clock::get()?.unix_timestamp, bettor_gain);
+            bettor_user_limits_state.serialize(&mut &mut bettor_user_limits_account_info.data.borrow_mut()[..])?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -90,21 +167,25 @@ mod test {
     use borsh::BorshSerialize;
     use solana_program::{
         instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
         pubkey::Pubkey,
         rent::Rent,
     };
     use solana_program_test::{tokio, ProgramTest};
-    use solana_sdk::{account::Account, signer::Signer, transaction::Transaction};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
 
     use crate::{
+        constants::OPERATOR_PUBKEY,
         instructions::BettingInstruction,
         state::{
+            bankroll::GameBankroll,
             game::{
                 coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
                 BetInput, Game, GameTypeConfig,
             },
             stats::Stats,
             user_account::UserAccount,
+            user_limits::UserLimits,
             vrf_result::VrfResult,
         },
     };
@@ -115,21 +196,6 @@ mod test {
         let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
 
         let host = Pubkey::new_unique();
-        let (host_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), host.as_ref()], &program_id);
-        let mut host_user_account_state = UserAccount::new(host, None, None);
-        host_user_account_state.current_lamports = 0;
-        let host_user_account_data = host_user_account_state.try_to_vec().unwrap();
-        let host_user_account_data_len = host_user_account_data.len();
-        program_test.add_account(
-            host_user_account_pda,
-            Account {
-                lamports: Rent::default().minimum_balance(host_user_account_data_len),
-                data: host_user_account_data,
-                owner: program_id,
-                ..Default::default()
-            },
-        );
-
         let bettor = Pubkey::new_unique();
         let referral = Pubkey::new_unique();
         let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
@@ -161,7 +227,7 @@ mod test {
         );
         game_state.unresolved_vrf_result = 1;
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -175,6 +241,22 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 0;
+        game_bankroll_state.locked_lamports = 2000 * 9900 / 10000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
         let mut stats_state = Stats::new();
         stats_state.total_users = 1;
@@ -225,6 +307,19 @@ mod test {
             },
         );
 
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let bettor_user_limits_data = UserLimits::new(bettor_user_account_pda).try_to_vec().unwrap();
+        let bettor_user_limits_data_len = bettor_user_limits_data.len();
+        program_test.add_account(
+            bettor_user_limits_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_limits_data_len),
+                data: bettor_user_limits_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -233,9 +328,11 @@ mod test {
                 vec![
                     AccountMeta::new(game_pda, false),
                     AccountMeta::new(vrf_result_pda, false),
-                    AccountMeta::new(host_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
                     AccountMeta::new(bettor_user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -252,32 +349,185 @@ mod test {
         // the bettor user account should be updated
         let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
         assert_eq!(bettor_user_account_state.current_lamports, 2000 + 2000 * 9900 / 10000);
+        assert_eq!(bettor_user_account_state.betting_pnl, 2000 * 9900 / 10000);
         // the stats account should be updated
         let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
-        assert_eq!(stats_state.total_lamports_won_by_bettors, 2000 * 9900 / 10000);
+        assert_eq!(stats_state.total_bettor_pnl, 2000 * 9900 / 10000);
+        assert_eq!(stats_state.total_host_pnl, -(2000 * 9900 / 10000));
+        // the payout should be recorded against the bettor's net loss
+        let bettor_user_limits_state: UserLimits = banks_client.get_account_data_with_borsh(bettor_user_limits_pda).await.unwrap();
+        assert_eq!(bettor_user_limits_state.daily_payouts.buckets.iter().sum::<u64>(), 2000 + 2000 * 9900 / 10000);
     }
 
+    /// Compute units of resolving a won coin flip bet must stay within the budget, a ceiling rather than a comparison with the
+    /// deserialized accounts, only meaningful against the BPF build run by `cargo test-bpf`
     #[tokio::test]
-    async fn test_game_resolve_vrf_result_success_bettor_lost() {
+    #[cfg_attr(not(feature = "test-bpf"), ignore)]
+    async fn test_game_resolve_vrf_result_compute_unit_budget() {
+        const COMPUTE_UNITS_BUDGET: u64 = 40_000;
         let program_id = crate::id();
         let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
 
         let host = Pubkey::new_unique();
-        let (host_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), host.as_ref()], &program_id);
-        let mut host_user_account_state = UserAccount::new(host, None, None);
-        host_user_account_state.current_lamports = 0;
-        let host_user_account_data = host_user_account_state.try_to_vec().unwrap();
-        let host_user_account_data_len = host_user_account_data.len();
+        let bettor = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, Some(referral), Some("Bettor".to_string()));
+        bettor_user_account_state.current_lamports = 0;
+        bettor_user_account_state.active_vrf_results = 1;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = 1;
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 0;
+        game_bankroll_state.locked_lamports = 2000 * 9900 / 10000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        stats_state.total_bets = 1;
+        stats_state.total_wager = 2000;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let bet_id = 0;
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_id,
+            [0; 72],
+            2000,
+            2000 * 9900 / 10000,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.marked_for_close = true;
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state.is_used = false;
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) =
+            Pubkey::find_program_address(&[b"VrfResult".as_ref(), game_pda.as_ref(), bettor.as_ref(), &bet_id.to_le_bytes()], &program_id);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let bettor_user_limits_data = UserLimits::new(bettor_user_account_pda).try_to_vec().unwrap();
+        let bettor_user_limits_data_len = bettor_user_limits_data.len();
         program_test.add_account(
-            host_user_account_pda,
+            bettor_user_limits_pda,
             Account {
-                lamports: Rent::default().minimum_balance(host_user_account_data_len),
-                data: host_user_account_data,
+                lamports: Rent::default().minimum_balance(bettor_user_limits_data_len),
+                data: bettor_user_limits_data,
                 owner: program_id,
                 ..Default::default()
             },
         );
 
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveVrfResult,
+                vec![
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        let simulation = banks_client.simulate_transaction(transaction).await.unwrap();
+        assert!(matches!(simulation.result, Some(Ok(()))));
+        let units_consumed = simulation.simulation_details.unwrap().units_consumed;
+        assert!(
+            units_consumed <= COMPUTE_UNITS_BUDGET,
+            "GameResolveVrfResult consumed {} compute units, above the budget of {}",
+            units_consumed,
+            COMPUTE_UNITS_BUDGET
+        );
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_vrf_result_success_bettor_won_with_commission() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Pubkey::new_unique();
         let bettor = Pubkey::new_unique();
         let referral = Pubkey::new_unique();
         let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
@@ -308,8 +558,9 @@ mod test {
             },
         );
         game_state.unresolved_vrf_result = 1;
+        game_state.commission = 500;
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -323,6 +574,22 @@ mod test {
             },
         );
 
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 0;
+        game_bankroll_state.locked_lamports = 2000 * 9900 / 10000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
         let mut stats_state = Stats::new();
         stats_state.total_users = 1;
@@ -334,13 +601,21 @@ mod test {
         program_test.add_account(
             stats_pda,
             Account {
-                lamports: Rent::default().minimum_balance(stats_data_len),
+                lamports: Rent::default().minimum_balance(stats_data_len) + 10000,
                 data: stats_data,
                 owner: program_id,
                 ..Default::default()
             },
         );
 
+        program_test.add_account(
+            OPERATOR_PUBKEY,
+            Account {
+                lamports: Rent::default().minimum_balance(0),
+                ..Default::default()
+            },
+        );
+
         let bet_id = 0;
         let mut vrf_result_state = VrfResult::new(
             bettor,
@@ -359,7 +634,6 @@ mod test {
         vrf_result_state.marked_for_close = true;
         vrf_result_state.is_fullfilled = true;
         vrf_result_state.is_used = false;
-        vrf_result_state.beta[0..16].copy_from_slice(&8000_u128.to_le_bytes());
         let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
         let vrf_result_data_len = vrf_result_data.len();
         let (vrf_result_pda, _) =
@@ -374,6 +648,19 @@ mod test {
             },
         );
 
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+        let bettor_user_limits_data = UserLimits::new(bettor_user_account_pda).try_to_vec().unwrap();
+        let bettor_user_limits_data_len = bettor_user_limits_data.len();
+        program_test.add_account(
+            bettor_user_limits_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_limits_data_len),
+                data: bettor_user_limits_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -382,23 +669,684 @@ mod test {
                 vec![
                     AccountMeta::new(game_pda, false),
                     AccountMeta::new(vrf_result_pda, false),
-                    AccountMeta::new(host_user_account_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
                     AccountMeta::new(bettor_user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
                 ],
             )],
             Some(&payer.pubkey()),
         );
         transaction.sign(&[&payer], recent_blockhash);
         banks_client.process_transaction(transaction).await.unwrap();
-        // the game pda should be updated
+        // commission of 5% of the net win 1980, 20% of it to the operator
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
         assert_eq!(game_state.unresolved_vrf_result, 0);
-        // the vrf result should be update
-        let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
-        assert!(vrf_result_state.is_used);
-        // the host user account should be updated
-        let host_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(host_user_account_pda).await.unwrap();
-        assert_eq!(host_user_account_state.current_lamports, 2000 + 2000 * 9900 / 10000);
+        assert_eq!(game_state.total_lamports_out, 2000 + 1980 - 99);
+        // the bettor user account should be paid the win minus the commission
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        assert_eq!(bettor_user_account_state.current_lamports, 2000 + 1980 - 99);
+        assert_eq!(bettor_user_account_state.betting_pnl, 1980 - 99);
+        // the host commission should be claimable from the game bankroll
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.unclaimed_fee_lamports, 80);
+        assert_eq!(game_bankroll_state.locked_lamports, 0);
+        // the stats account should be updated
+        let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
+        assert_eq!(stats_state.total_bettor_pnl, 1980 - 99);
+        assert_eq!(stats_state.total_host_commission_lamports, 80);
+        assert_eq!(stats_state.total_operator_commission_lamports, 19);
+        let stats_account = banks_client.get_account(stats_pda).await.unwrap().unwrap();
+        assert_eq!(stats_account.lamports, Rent::default().minimum_balance(stats_data_len) + 10000 - 19);
+        // the operator commission should be transferred to the operator account
+        let op_account = banks_client.get_account(OPERATOR_PUBKEY).await.unwrap().unwrap();
+        assert_eq!(op_account.lamports, Rent::default().minimum_balance(0) + 19);
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_vrf_result_success_bettor_lost() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, Some(referral), Some("Bettor".to_string()));
+        bettor_user_account_state.current_lamports = 0;
+        bettor_user_account_state.active_vrf_results = 1;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = 1;
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 0;
+        game_bankroll_state.locked_lamports = 2000 * 9900 / 10000;
+        game_bankroll_state.management_fee = 1000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        stats_state.total_bets = 1;
+        stats_state.total_wager = 2000;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let bet_id = 0;
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_id,
+            [0; 72],
+            2000,
+            2000 * 9900 / 10000,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.marked_for_close = true;
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state.is_used = false;
+        vrf_result_state.beta[0..16].copy_from_slice(&2000_u128.to_le_bytes());
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) =
+            Pubkey::find_program_address(&[b"VrfResult".as_ref(), game_pda.as_ref(), bettor.as_ref(), &bet_id.to_le_bytes()], &program_id);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveVrfResult,
+                vec![
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        // the game pda should be updated
+        let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
+        assert_eq!(game_state.unresolved_vrf_result, 0);
+        // the vrf result should be update
+        let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
+        assert!(vrf_result_state.is_used);
+        // the host winnings minus the management fee should be returned to the game bankroll
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.current_lamports, 2000 + 2000 * 9900 / 10000 - 200);
+        assert_eq!(game_bankroll_state.locked_lamports, 0);
+        assert_eq!(game_bankroll_state.unclaimed_fee_lamports, 200);
+    }
+
+    /// Resolve a bet tipped by the bettor and the game, returns the lamports of the stats account above its rent, the
+    /// lamports the bettor and the bankroll are owed and the lamports of the cranker, or of the operator without one
+    async fn run_game_resolve_vrf_result_crank_tip(with_cranker: bool) -> (u64, u64, u64) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Pubkey::new_unique();
+        let cranker = Keypair::new();
+        for tip_receiver in [cranker.pubkey(), OPERATOR_PUBKEY] {
+            program_test.add_account(
+                tip_receiver,
+                Account {
+                    lamports: LAMPORTS_PER_SOL,
+                    ..Default::default()
+                },
+            );
+        }
+        let bettor = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, Some(referral), Some("Bettor".to_string()));
+        bettor_user_account_state.current_lamports = 1000;
+        bettor_user_account_state.active_vrf_results = 1;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = 1;
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 5000;
+        game_bankroll_state.locked_lamports = 2000 * 9900 / 10000;
+        game_bankroll_state.management_fee = 1000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        stats_state.total_bets = 1;
+        stats_state.total_wager = 2000;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                // the balances, the locked lamports and the crank tip of both sides are all held by the stats account
+                lamports: Rent::default().minimum_balance(stats_data_len) + 1000 + 5000 + 2000 + 2000 * 9900 / 10000 + 300,
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let bet_id = 0;
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_id,
+            [0; 72],
+            2000,
+            2000 * 9900 / 10000,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.crank_tip_lamports = 300;
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state.is_used = false;
+        vrf_result_state.beta[0..16].copy_from_slice(&2000_u128.to_le_bytes());
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) =
+            Pubkey::find_program_address(&[b"VrfResult".as_ref(), game_pda.as_ref(), bettor.as_ref(), &bet_id.to_le_bytes()], &program_id);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut account_metas = vec![
+            AccountMeta::new(game_pda, false),
+            AccountMeta::new(vrf_result_pda, false),
+            AccountMeta::new(game_bankroll_pda, false),
+            AccountMeta::new(bettor_user_account_pda, false),
+            AccountMeta::new(stats_pda, false),
+            AccountMeta::new(bettor_user_limits_pda, false),
+            AccountMeta::new(OPERATOR_PUBKEY, false),
+        ];
+        let mut signers = vec![&payer];
+        if with_cranker {
+            account_metas.push(AccountMeta::new(cranker.pubkey(), true));
+            signers.push(&cranker);
+        }
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveVrfResult,
+                account_metas,
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&signers, recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
+        assert_eq!(game_state.unresolved_vrf_result, 0);
+        let stats_account = banks_client.get_account(stats_pda).await.unwrap().unwrap();
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        let tip_receiver = if with_cranker { cranker.pubkey() } else { OPERATOR_PUBKEY };
+        let tip_receiver_account = banks_client.get_account(tip_receiver).await.unwrap().unwrap();
+        (
+            stats_account.lamports - Rent::default().minimum_balance(stats_data_len),
+            bettor_user_account_state.current_lamports
+                + game_bankroll_state.current_lamports
+                + game_bankroll_state.locked_lamports
+                + game_bankroll_state.unclaimed_fee_lamports,
+            tip_receiver_account.lamports,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_vrf_result_success_crank_tip() {
+        let (stats_lamports, owed_lamports, cranker_lamports) = run_game_resolve_vrf_result_crank_tip(true).await;
+        // the crank tip should be paid to the signer resolving the bet
+        assert_eq!(cranker_lamports, LAMPORTS_PER_SOL + 300);
+        // the stats account should still hold every lamport the bettor and the bankroll are owed
+        assert_eq!(stats_lamports, owed_lamports);
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_vrf_result_success_crank_tip_without_cranker() {
+        let (stats_lamports, owed_lamports, operator_lamports) = run_game_resolve_vrf_result_crank_tip(false).await;
+        // the crank tip should be paid to the operator, along with its commission when the bettor won
+        assert!(operator_lamports >= LAMPORTS_PER_SOL + 300);
+        assert_eq!(stats_lamports, owed_lamports);
+    }
+
+    #[tokio::test]
+    async fn test_game_resolve_vrf_result_success_close_on_resolve() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, Some(referral), Some("Bettor".to_string()));
+        bettor_user_account_state.current_lamports = 0;
+        bettor_user_account_state.active_vrf_results = 1;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = 1;
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 0;
+        game_bankroll_state.locked_lamports = 2000 * 9900 / 10000;
+        game_bankroll_state.management_fee = 1000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        stats_state.total_bets = 1;
+        stats_state.total_wager = 2000;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let bet_id = 0;
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_id,
+            [0; 72],
+            2000,
+            2000 * 9900 / 10000,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.close_on_resolve = true;
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state.is_used = false;
+        vrf_result_state.beta[0..16].copy_from_slice(&2000_u128.to_le_bytes());
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) =
+            Pubkey::find_program_address(&[b"VrfResult".as_ref(), game_pda.as_ref(), bettor.as_ref(), &bet_id.to_le_bytes()], &program_id);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveVrfResult,
+                vec![
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        // the vrf result should be closed
+        assert!(banks_client.get_account(vrf_result_pda).await.unwrap().is_none());
+        // the rent should be refunded to the balance of the bettor, held by the stats account
+        let vrf_result_rent = Rent::default().minimum_balance(vrf_result_data_len);
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        assert_eq!(bettor_user_account_state.current_lamports, vrf_result_rent);
+        assert_eq!(bettor_user_account_state.active_vrf_results, 0);
+        let stats_account = banks_client.get_account(stats_pda).await.unwrap().unwrap();
+        assert_eq!(stats_account.lamports, Rent::default().minimum_balance(stats_data_len) + vrf_result_rent);
+        // the bet should still be settled
+        let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
+        assert_eq!(game_state.unresolved_vrf_result, 0);
+        let game_bankroll_state: GameBankroll = banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap();
+        assert_eq!(game_bankroll_state.locked_lamports, 0);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(4)")]
+    async fn test_game_resolve_vrf_result_err_wrong_bettor_user_account() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        // the user account of another user is passed as the bettor user account
+        let other = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), other.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(other, Some(referral), Some("Other".to_string()));
+        bettor_user_account_state.current_lamports = 0;
+        bettor_user_account_state.active_vrf_results = 1;
+        let bettor_user_account_data = bettor_user_account_state.try_to_vec().unwrap();
+        let bettor_user_account_data_len = bettor_user_account_data.len();
+        program_test.add_account(
+            bettor_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(bettor_user_account_data_len),
+                data: bettor_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let mut game_state = Game::new(
+            host,
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = 1;
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, host);
+        game_bankroll_state.current_lamports = 0;
+        game_bankroll_state.locked_lamports = 2000 * 9900 / 10000;
+        let game_bankroll_data = game_bankroll_state.try_to_vec().unwrap();
+        let game_bankroll_data_len = game_bankroll_data.len();
+        program_test.add_account(
+            game_bankroll_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_bankroll_data_len),
+                data: game_bankroll_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        stats_state.total_games = 1;
+        stats_state.total_bets = 1;
+        stats_state.total_wager = 2000;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let bet_id = 0;
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_id,
+            [0; 72],
+            2000,
+            2000 * 9900 / 10000,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.marked_for_close = true;
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state.is_used = false;
+        vrf_result_state.beta[0..16].copy_from_slice(&2000_u128.to_le_bytes());
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) =
+            Pubkey::find_program_address(&[b"VrfResult".as_ref(), game_pda.as_ref(), bettor.as_ref(), &bet_id.to_le_bytes()], &program_id);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameResolveVrfResult,
+                vec![
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
     }
 }
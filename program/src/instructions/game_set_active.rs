@@ -1,48 +1,40 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-};
-
-use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump},
-    error::BettingError,
-    state::{game::Game, BettingAccount},
-};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::state::game::Game;
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
 pub struct GameSetActiveArgs {
     pub is_active: bool,
 }
+
+#[derive(Accounts)]
+pub struct GameSetActiveAccounts<'a, 'info> {
+    #[account(signer, authority = game_state.host, name = "host", desc = "The wallet account of the host")]
+    pub host_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[state(game_account_info)]
+    pub game_state: Game,
+}
+
 pub fn game_set_active(_program_id: &Pubkey, accounts: &[AccountInfo], args: GameSetActiveArgs) -> ProgramResult {
     msg!("Instruction: GameSetActive");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let host_account_info = next_account_info(iter)?;
-    let game_account_info = next_account_info(iter)?;
-
-    let mut game_state = Game::try_from_account_info(game_account_info)?;
-    // check accounts
-    check_is_signer(host_account_info)?;
-
-    check_is_writable(game_account_info)?;
-    let common_config_vec = game_state.common_config.try_to_vec()?;
-    let game_type_config_vec = game_state.game_type_config.try_to_vec()?;
-    check_pda_cannonical_bump(
+    let GameSetActiveAccounts {
         game_account_info,
-        &[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()],
-    )?;
-    // check authority
-    if &game_state.host != host_account_info.key {
-        msg!("Expect account {} to be the host of the game {}", host_account_info.key, game_account_info.key);
-        return Err(ProgramError::from(BettingError::NoAuthority));
-    }
+        mut game_state,
+        ..
+    } = GameSetActiveAccounts::try_from_accounts(accounts)?;
     // update game state
-    game_state.is_active = args.is_active;
+    game_state.set_active(args.is_active);
     game_state.serialize(&mut &mut game_account_info.data.borrow_mut()[..])?;
 
     Ok(())
@@ -92,7 +84,7 @@ mod test {
             },
         );
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -122,7 +114,7 @@ mod test {
 
         // the game state should be updated
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
-        assert!(!game_state.is_active);
+        assert!(!game_state.is_active());
     }
 
     #[tokio::test]
@@ -160,7 +152,7 @@ mod test {
             },
         );
         let common_config_vec = game_state.common_config.try_to_vec().unwrap();
-        let game_type_config_vec = game_state.game_type_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
         let game_data = game_state.try_to_vec().unwrap();
         let game_data_len = game_data.len();
         let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
@@ -190,6 +182,6 @@ mod test {
 
         // the game state should be updated
         let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
-        assert!(!game_state.is_active);
+        assert!(!game_state.is_active());
     }
 }
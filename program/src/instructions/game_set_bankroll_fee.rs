@@ -0,0 +1,155 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, system_program};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    constants::MAX_MANAGEMENT_FEE,
+    state::{bankroll::GameBankroll, game::Game},
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
+pub struct GameSetBankrollFeeArgs {
+    pub management_fee: u64,
+}
+
+#[derive(Accounts)]
+pub struct GameSetBankrollFeeAccounts<'a, 'info> {
+    #[account(signer, writable, authority = game_state.host, name = "host", desc = "The wallet account of the host")]
+    pub host_account_info: &'a AccountInfo<'info>,
+    #[account(
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "game_bankroll_pda", desc = "Game bankroll PDA account")]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[state(game_account_info)]
+    pub game_state: Game,
+}
+
+pub fn game_set_bankroll_fee(_program_id: &Pubkey, accounts: &[AccountInfo], args: GameSetBankrollFeeArgs) -> ProgramResult {
+    msg!("Instruction: GameSetBankrollFee");
+    // get accounts
+    let GameSetBankrollFeeAccounts {
+        host_account_info,
+        game_account_info,
+        game_bankroll_account_info,
+        game_state,
+        ..
+    } = GameSetBankrollFeeAccounts::try_from_accounts(accounts)?;
+    // check fee
+    if args.management_fee > MAX_MANAGEMENT_FEE {
+        msg!("Management fee must be at most {} basis points", MAX_MANAGEMENT_FEE);
+        return Err(ProgramError::InvalidArgument);
+    }
+    // update game bankroll account
+    let mut game_bankroll_state = GameBankroll::load_or_create(game_account_info.key, &game_state.host, game_bankroll_account_info, host_account_info)?;
+    game_bankroll_state.management_fee = args.management_fee;
+    game_bankroll_state.serialize(&mut &mut game_bankroll_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            bankroll::GameBankroll,
+            game::{coinflip::CoinFlipConfig, Game, GameTypeConfig},
+        },
+    };
+
+    use super::GameSetBankrollFeeArgs;
+
+    async fn run_game_set_bankroll_fee(management_fee: u64) -> GameBankroll {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Keypair::new();
+        program_test.add_account(
+            host.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let game_state = Game::new(
+            host.pubkey(),
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameSetBankrollFee {
+                    args: GameSetBankrollFeeArgs { management_fee },
+                },
+                vec![
+                    AccountMeta::new(host.pubkey(), true),
+                    AccountMeta::new_readonly(game_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&host, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        banks_client.get_account_data_with_borsh(game_bankroll_pda).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_game_set_bankroll_fee_success() {
+        let game_bankroll_state = run_game_set_bankroll_fee(1000).await;
+        assert_eq!(game_bankroll_state.management_fee, 1000);
+        assert_eq!(game_bankroll_state.total_shares, 0);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "InvalidArgument")]
+    async fn test_game_set_bankroll_fee_err_above_max() {
+        run_game_set_bankroll_fee(2001).await;
+    }
+}
@@ -0,0 +1,135 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{constants::MAX_COMMISSION, error::BettingError, state::game::Game};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
+pub struct GameSetCommissionArgs {
+    pub commission: u64,
+}
+
+#[derive(Accounts)]
+pub struct GameSetCommissionAccounts<'a, 'info> {
+    #[account(signer, authority = game_state.host, name = "host", desc = "The wallet account of the host")]
+    pub host_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[state(game_account_info)]
+    pub game_state: Game,
+}
+
+pub fn game_set_commission(_program_id: &Pubkey, accounts: &[AccountInfo], args: GameSetCommissionArgs) -> ProgramResult {
+    msg!("Instruction: GameSetCommission");
+    // get accounts
+    let GameSetCommissionAccounts {
+        game_account_info,
+        mut game_state,
+        ..
+    } = GameSetCommissionAccounts::try_from_accounts(accounts)?;
+    // check commission, the bets already placed are resolved with the commission they were placed with
+    if args.commission > MAX_COMMISSION {
+        msg!("Commission must be at most {} basis points", MAX_COMMISSION);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if game_state.unresolved_vrf_result > 0 {
+        msg!("Game {} is not settled", game_account_info.key);
+        return Err(ProgramError::from(BettingError::GameNotSettled));
+    }
+    // update game state
+    game_state.commission = args.commission;
+    game_state.serialize(&mut &mut game_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::game::{crash::CrashConfig, Game, GameTypeConfig},
+    };
+
+    use super::GameSetCommissionArgs;
+
+    async fn run_game_set_commission(commission: u64, unresolved_vrf_result: u32) -> Game {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Keypair::new();
+        let mut game_state = Game::new(
+            host.pubkey(),
+            1000,
+            10000,
+            GameTypeConfig::Crash {
+                config: CrashConfig {
+                    multiplier_straight_one_possibility: 100,
+                },
+            },
+        );
+        game_state.unresolved_vrf_result = unresolved_vrf_result;
+        let common_config_vec = game_state.common_config.try_to_vec().unwrap();
+        let game_type_config_vec = game_state.game_type_config_seed().to_vec();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&[b"Game".as_ref(), common_config_vec.as_slice(), game_type_config_vec.as_slice()], &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameSetCommission {
+                    args: GameSetCommissionArgs { commission },
+                },
+                vec![AccountMeta::new_readonly(host.pubkey(), true), AccountMeta::new(game_pda, false)],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&host, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        banks_client.get_account_data_with_borsh(game_pda).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_game_set_commission_success() {
+        let game_state = run_game_set_commission(500, 0).await;
+        assert_eq!(game_state.commission, 500);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "InvalidArgument")]
+    async fn test_game_set_commission_err_above_max() {
+        run_game_set_commission(1001, 0).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(13)")]
+    async fn test_game_set_commission_err_not_settled() {
+        run_game_set_commission(500, 1).await;
+    }
+}
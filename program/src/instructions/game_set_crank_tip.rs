@@ -0,0 +1,123 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{constants::MAX_CRANK_TIP_LAMPORTS, state::game::Game};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
+pub struct GameSetCrankTipArgs {
+    pub crank_tip_lamports: u32,
+}
+
+#[derive(Accounts)]
+pub struct GameSetCrankTipAccounts<'a, 'info> {
+    #[account(signer, authority = game_state.host, name = "host", desc = "The wallet account of the host")]
+    pub host_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[state(game_account_info)]
+    pub game_state: Game,
+}
+
+pub fn game_set_crank_tip(_program_id: &Pubkey, accounts: &[AccountInfo], args: GameSetCrankTipArgs) -> ProgramResult {
+    msg!("Instruction: GameSetCrankTip");
+    // get accounts
+    let GameSetCrankTipAccounts {
+        game_account_info,
+        mut game_state,
+        ..
+    } = GameSetCrankTipAccounts::try_from_accounts(accounts)?;
+    // check tip, the bets already placed keep the tip escrowed with them
+    if u64::from(args.crank_tip_lamports) > MAX_CRANK_TIP_LAMPORTS {
+        msg!("Crank tip must be at most {} lamports", MAX_CRANK_TIP_LAMPORTS);
+        return Err(ProgramError::InvalidArgument);
+    }
+    // update game state
+    game_state.crank_tip_lamports = args.crank_tip_lamports;
+    game_state.serialize(&mut &mut game_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::game::{crash::CrashConfig, Game, GameTypeConfig},
+    };
+
+    use super::GameSetCrankTipArgs;
+
+    async fn run_game_set_crank_tip(crank_tip_lamports: u32) -> Game {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Keypair::new();
+        let mut game_state = Game::new(
+            host.pubkey(),
+            1000,
+            10000,
+            GameTypeConfig::Crash {
+                config: CrashConfig {
+                    multiplier_straight_one_possibility: 100,
+                },
+            },
+        );
+        // the tip can change while bets are unresolved
+        game_state.unresolved_vrf_result = 1;
+        let game_data = game_state.try_to_vec().unwrap();
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data.len()),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::GameSetCrankTip {
+                    args: GameSetCrankTipArgs { crank_tip_lamports },
+                },
+                vec![AccountMeta::new_readonly(host.pubkey(), true), AccountMeta::new(game_pda, false)],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&host, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        banks_client.get_account_data_with_borsh(game_pda).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_game_set_crank_tip_success() {
+        let game_state = run_game_set_crank_tip(5000).await;
+        assert_eq!(game_state.crank_tip_lamports, 5000);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "InvalidArgument")]
+    async fn test_game_set_crank_tip_err_above_max() {
+        run_game_set_crank_tip(10_000_001).await;
+    }
+}
@@ -1,91 +1,207 @@
+pub mod account_migrate;
+pub mod game_claim_bankroll_fee;
 pub mod game_close;
 pub mod game_create;
+pub mod game_defund_bankroll;
+pub mod game_fund_bankroll;
 pub mod game_place_bet;
+pub mod game_resolve_hash_chain;
+pub mod game_resolve_late_fullfill;
+pub mod game_resolve_timed_out;
 pub mod game_resolve_vrf_result;
 pub mod game_set_active;
+pub mod game_set_bankroll_fee;
+pub mod game_set_commission;
+pub mod game_set_crank_tip;
+pub mod operator_bond_request_withdrawal;
+pub mod operator_bond_set_config;
+pub mod operator_bond_top_up;
+pub mod operator_bond_withdraw;
+pub mod oracle_config_set;
+pub mod randomness_request;
+pub mod randomness_request_fullfill;
+pub mod referral_account_create;
+pub mod referral_claim;
+pub mod referral_config_set;
 pub mod stats_account_create;
 pub mod user_account_close;
 pub mod user_account_create;
 pub mod user_account_deposit;
+pub mod user_account_update_profile;
 pub mod user_account_withdraw;
+pub mod user_limits_set;
+pub mod user_self_exclude;
 pub mod vrf_result_close;
 pub mod vrf_result_fullfill;
+pub mod vrf_result_fullfill_and_resolve;
 pub mod vrf_result_mark_close;
+pub mod vrf_result_oracle_fullfill;
+pub mod vrf_result_reveal_hash_chain;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use shank::ShankInstruction;
 
 use self::{
-    game_create::GameCreateArgs, game_place_bet::GamePlaceBetArgs, game_set_active::GameSetActiveArgs, user_account_create::UserAccountCreateArgs,
-    user_account_deposit::UserAccountDepositArgs, user_account_withdraw::UserAccountWithdrawArgs, vrf_result_fullfill::VrfResultFullfillArgs,
+    game_create::GameCreateArgs, game_defund_bankroll::GameDefundBankrollArgs, game_fund_bankroll::GameFundBankrollArgs, game_place_bet::GamePlaceBetArgs,
+    game_resolve_hash_chain::GameResolveHashChainArgs, game_set_active::GameSetActiveArgs, game_set_bankroll_fee::GameSetBankrollFeeArgs,
+    game_set_commission::GameSetCommissionArgs, game_set_crank_tip::GameSetCrankTipArgs, operator_bond_request_withdrawal::OperatorBondRequestWithdrawalArgs,
+    operator_bond_set_config::OperatorBondSetConfigArgs, operator_bond_top_up::OperatorBondTopUpArgs, oracle_config_set::OracleConfigSetArgs,
+    randomness_request::RandomnessRequestArgs, randomness_request_fullfill::RandomnessRequestFullfillArgs, referral_account_create::ReferralAccountCreateArgs,
+    referral_config_set::ReferralConfigSetArgs, user_account_create::UserAccountCreateArgs, user_account_deposit::UserAccountDepositArgs,
+    user_account_update_profile::UserAccountUpdateProfileArgs, user_account_withdraw::UserAccountWithdrawArgs, user_limits_set::UserLimitsSetArgs,
+    user_self_exclude::UserSelfExcludeArgs, vrf_result_fullfill::VrfResultFullfillArgs, vrf_result_oracle_fullfill::VrfResultOracleFullfillArgs,
+    vrf_result_reveal_hash_chain::VrfResultRevealHashChainArgs,
 };
 
 #[derive(BorshSerialize, BorshDeserialize, ShankInstruction, Clone)]
 pub enum BettingInstruction {
-    #[account(0, writable, signer, name = "operator", desc = "Operator Account")]
-    #[account(1, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(2, name = "system_program", desc = "System Program Account")]
     StatsAccountCreate,
-    #[account(0, writable, signer, name = "user_wallet_account", desc = "User Wallet Account")]
-    #[account(1, writable, name = "user_account", desc = "User Betting Account")]
-    #[account(2, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(3, name = "system_program", desc = "System Program Account")]
     UserAccountCreate { args: UserAccountCreateArgs },
-    #[account(0, writable, signer, name = "depositor", desc = "The account to transfer lamports from")]
-    #[account(1, writable, name = "user_account", desc = "User Betting Account")]
-    #[account(2, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(3, name = "system_program", desc = "System Program Account")]
     UserAccountDeposit { args: UserAccountDepositArgs },
-    #[account(0, writable, signer, name = "user_wallet_account", desc = "User Wallet Account")]
-    #[account(1, writable, name = "user_account", desc = "User Betting Account")]
-    #[account(2, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(3, writable, name = "operator", desc = "Operator Account")]
-    #[account(4, writable, optional, name = "referall_account", desc = "Referral Wallet Account")]
     UserAccountWithdraw { args: UserAccountWithdrawArgs },
-    #[account(0, writable, signer, name = "user_wallet_account", desc = "User Wallet Account")]
-    #[account(1, writable, name = "user_account", desc = "User Betting Account")]
-    #[account(2, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(3, name = "system_program", desc = "System Program Account")]
     UserAccountClose,
-    #[account(0, writable, signer, name = "host", desc = "The wallet account of the host")]
-    #[account(1, writable, name = "user_account", desc = "User Betting Account of the host")]
-    #[account(2, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(3, writable, name = "game_pda", desc = "Game PDA Account")]
-    #[account(4, name = "system_program", desc = "System Program Account")]
     GameCreate { args: GameCreateArgs },
-    #[account(0, signer, name = "host", desc = "The wallet account of the host")]
-    #[account(1, writable, name = "game_pda", desc = "Game PDA Account")]
     GameSetActive { args: GameSetActiveArgs },
-    #[account(0, writable, signer, name = "bettor", desc = "Bettor wallet account")]
-    #[account(1, writable, name = "bettor_user_account", desc = "Bettor user account")]
-    #[account(2, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(3, writable, name = "game_pda", desc = "Game PDA Account")]
-    #[account(4, writable, name = "host_user_account", desc = "Host user account")]
-    #[account(5, writable, name = "vrf_result_pda", desc = "VRF result PDA account")]
-    #[account(6, name = "slot_hashes", desc = "Slot hashes account")]
-    #[account(7, name = "system_program", desc = "System Program Account")]
     GamePlaceBet { args: GamePlaceBetArgs },
-    #[account(0, writable, name = "game_pda", desc = "Game PDA Account")]
-    #[account(1, writable, name = "vrf_result_pda", desc = "VRF result PDA account")]
-    #[account(2, writable, name = "host_user_account", desc = "Host user account")]
-    #[account(3, writable, name = "bettor_user_account", desc = "Bettor user account")]
-    #[account(4, writable, name = "stats_pda", desc = "Stats PDA Account")]
     GameResolveVrfResult,
-    #[account(0, writable, signer, name = "host", desc = "The wallet account of the host")]
-    #[account(1, writable, name = "host_user_account", desc = "User Betting Account of the host")]
-    #[account(2, writable, name = "stats_pda", desc = "Stats PDA Account")]
-    #[account(3, writable, name = "game_pda", desc = "Game PDA Account")]
-    #[account(4, name = "system_program", desc = "System Program Account")]
     GameClose,
-    #[account(0, signer, name = "operator", desc = "Operator Account")]
-    #[account(1, writable, name = "vrf_result_pda", desc = "VRF result PDA account")]
     VrfResultFullfill { args: VrfResultFullfillArgs },
-    #[account(0, signer, name = "bettor", desc = "Bettor wallet account")]
-    #[account(1, writable, name = "vrf_result_pda", desc = "VRF result PDA account")]
     VrfResultMarkClose,
-    #[account(0, writable, name = "vrf_result_pda", desc = "VRF result PDA account")]
-    #[account(1, writable, signer, name = "bettor", desc = "Bettor wallet account")]
-    #[account(2, writable, name = "bettor_user_account", desc = "Bettor user account")]
-    #[account(3, name = "system_program", desc = "System Program Account")]
     VrfResultClose,
+    AccountMigrate,
+    UserAccountUpdateProfile { args: UserAccountUpdateProfileArgs },
+    ReferralConfigSet { args: ReferralConfigSetArgs },
+    ReferralAccountCreate { args: ReferralAccountCreateArgs },
+    ReferralClaim,
+    UserLimitsSet { args: UserLimitsSetArgs },
+    UserSelfExclude { args: UserSelfExcludeArgs },
+    GameFundBankroll { args: GameFundBankrollArgs },
+    GameDefundBankroll { args: GameDefundBankrollArgs },
+    GameSetBankrollFee { args: GameSetBankrollFeeArgs },
+    GameClaimBankrollFee,
+    GameSetCommission { args: GameSetCommissionArgs },
+    OracleConfigSet { args: OracleConfigSetArgs },
+    VrfResultRevealHashChain { args: VrfResultRevealHashChainArgs },
+    VrfResultOracleFullfill { args: VrfResultOracleFullfillArgs },
+    GameResolveHashChain { args: GameResolveHashChainArgs },
+    GameResolveTimedOut,
+    OperatorBondSetConfig { args: OperatorBondSetConfigArgs },
+    OperatorBondTopUp { args: OperatorBondTopUpArgs },
+    OperatorBondRequestWithdrawal { args: OperatorBondRequestWithdrawalArgs },
+    OperatorBondWithdraw,
+    GameResolveLateFullfill,
+    RandomnessRequest { args: RandomnessRequestArgs },
+    RandomnessRequestFullfill { args: RandomnessRequestFullfillArgs },
+    VrfResultFullfillAndResolve { args: VrfResultFullfillArgs },
+    GameSetCrankTip { args: GameSetCrankTipArgs },
+}
+
+/// An account of an instruction in the IDL
+#[derive(Debug, PartialEq, Eq)]
+pub struct IdlAccount {
+    pub name: &'static str,
+    pub is_mut: bool,
+    pub is_signer: bool,
+    pub is_optional: bool,
+    pub desc: &'static str,
+}
+
+/// The accounts of every instruction, generated from its account context. Shank only declares the instructions, the IDL
+/// build fills their accounts in from this table, see `examples/idl_accounts.rs`
+pub const INSTRUCTION_ACCOUNTS: &[(&str, &[IdlAccount])] = &[
+    ("StatsAccountCreate", stats_account_create::StatsAccountCreateAccounts::IDL_ACCOUNTS),
+    ("UserAccountCreate", user_account_create::UserAccountCreateAccounts::IDL_ACCOUNTS),
+    ("UserAccountDeposit", user_account_deposit::UserAccountDepositAccounts::IDL_ACCOUNTS),
+    ("UserAccountWithdraw", user_account_withdraw::UserAccountWithdrawAccounts::IDL_ACCOUNTS),
+    ("UserAccountClose", user_account_close::UserAccountCloseAccounts::IDL_ACCOUNTS),
+    ("GameCreate", game_create::GameCreateAccounts::IDL_ACCOUNTS),
+    ("GameSetActive", game_set_active::GameSetActiveAccounts::IDL_ACCOUNTS),
+    ("GamePlaceBet", game_place_bet::GamePlaceBetAccounts::IDL_ACCOUNTS),
+    ("GameResolveVrfResult", game_resolve_vrf_result::GameResolveVrfResultAccounts::IDL_ACCOUNTS),
+    ("GameClose", game_close::GameCloseAccounts::IDL_ACCOUNTS),
+    ("VrfResultFullfill", vrf_result_fullfill::VrfResultFullfillAccounts::IDL_ACCOUNTS),
+    ("VrfResultMarkClose", vrf_result_mark_close::VrfResultMarkCloseAccounts::IDL_ACCOUNTS),
+    ("VrfResultClose", vrf_result_close::VrfResultCloseAccounts::IDL_ACCOUNTS),
+    ("AccountMigrate", account_migrate::AccountMigrateAccounts::IDL_ACCOUNTS),
+    (
+        "UserAccountUpdateProfile",
+        user_account_update_profile::UserAccountUpdateProfileAccounts::IDL_ACCOUNTS,
+    ),
+    ("ReferralConfigSet", referral_config_set::ReferralConfigSetAccounts::IDL_ACCOUNTS),
+    ("ReferralAccountCreate", referral_account_create::ReferralAccountCreateAccounts::IDL_ACCOUNTS),
+    ("ReferralClaim", referral_claim::ReferralClaimAccounts::IDL_ACCOUNTS),
+    ("UserLimitsSet", user_limits_set::UserLimitsSetAccounts::IDL_ACCOUNTS),
+    ("UserSelfExclude", user_self_exclude::UserSelfExcludeAccounts::IDL_ACCOUNTS),
+    ("GameFundBankroll", game_fund_bankroll::GameFundBankrollAccounts::IDL_ACCOUNTS),
+    ("GameDefundBankroll", game_defund_bankroll::GameDefundBankrollAccounts::IDL_ACCOUNTS),
+    ("GameSetBankrollFee", game_set_bankroll_fee::GameSetBankrollFeeAccounts::IDL_ACCOUNTS),
+    ("GameClaimBankrollFee", game_claim_bankroll_fee::GameClaimBankrollFeeAccounts::IDL_ACCOUNTS),
+    ("GameSetCommission", game_set_commission::GameSetCommissionAccounts::IDL_ACCOUNTS),
+    ("OracleConfigSet", oracle_config_set::OracleConfigSetAccounts::IDL_ACCOUNTS),
+    (
+        "VrfResultRevealHashChain",
+        vrf_result_reveal_hash_chain::VrfResultRevealHashChainAccounts::IDL_ACCOUNTS,
+    ),
+    (
+        "VrfResultOracleFullfill",
+        vrf_result_oracle_fullfill::VrfResultOracleFullfillAccounts::IDL_ACCOUNTS,
+    ),
+    ("GameResolveHashChain", game_resolve_hash_chain::GameResolveHashChainAccounts::IDL_ACCOUNTS),
+    // an unrevealed bet times out with the accounts of a resolve, only the conditions of the settlement differ
+    ("GameResolveTimedOut", game_resolve_vrf_result::GameResolveVrfResultAccounts::IDL_ACCOUNTS),
+    ("OperatorBondSetConfig", operator_bond_set_config::OperatorBondSetConfigAccounts::IDL_ACCOUNTS),
+    ("OperatorBondTopUp", operator_bond_top_up::OperatorBondTopUpAccounts::IDL_ACCOUNTS),
+    (
+        "OperatorBondRequestWithdrawal",
+        operator_bond_request_withdrawal::OperatorBondRequestWithdrawalAccounts::IDL_ACCOUNTS,
+    ),
+    ("OperatorBondWithdraw", operator_bond_withdraw::OperatorBondWithdrawAccounts::IDL_ACCOUNTS),
+    (
+        "GameResolveLateFullfill",
+        game_resolve_late_fullfill::GameResolveLateFullfillAccounts::IDL_ACCOUNTS,
+    ),
+    ("RandomnessRequest", randomness_request::RandomnessRequestAccounts::IDL_ACCOUNTS),
+    (
+        "RandomnessRequestFullfill",
+        randomness_request_fullfill::RandomnessRequestFullfillAccounts::IDL_ACCOUNTS,
+    ),
+    (
+        "VrfResultFullfillAndResolve",
+        vrf_result_fullfill_and_resolve::VrfResultFullfillAndResolveAccounts::IDL_ACCOUNTS,
+    ),
+    ("GameSetCrankTip", game_set_crank_tip::GameSetCrankTipAccounts::IDL_ACCOUNTS),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_instruction_accounts() {
+        let mut names: Vec<&str> = INSTRUCTION_ACCOUNTS.iter().map(|(name, _)| *name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), INSTRUCTION_ACCOUNTS.len());
+        let (name, accounts) = INSTRUCTION_ACCOUNTS.last().unwrap();
+        assert_eq!(*name, "GameSetCrankTip");
+        assert_eq!(
+            *accounts,
+            &[
+                IdlAccount {
+                    name: "host",
+                    is_mut: false,
+                    is_signer: true,
+                    is_optional: false,
+                    desc: "The wallet account of the host",
+                },
+                IdlAccount {
+                    name: "game_pda",
+                    is_mut: true,
+                    is_signer: false,
+                    is_optional: false,
+                    desc: "Game PDA Account",
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,122 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, pubkey::Pubkey, sysvar::Sysvar};
+use vrf_betting_derive::Accounts;
+
+use crate::{constants::OPERATOR_PUBKEY, state::operator_bond::OperatorBond};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct OperatorBondRequestWithdrawalArgs {
+    pub lamports: u64,
+}
+
+#[derive(Accounts)]
+pub struct OperatorBondRequestWithdrawalAccounts<'a, 'info> {
+    #[account(signer, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"OperatorBond"],
+        stored_bump = operator_bond_state.bump,
+        name = "operator_bond_pda",
+        desc = "Operator bond PDA Account"
+    )]
+    pub operator_bond_account_info: &'a AccountInfo<'info>,
+    #[state(operator_bond_account_info)]
+    pub operator_bond_state: OperatorBond,
+}
+
+pub fn operator_bond_request_withdrawal(_program_id: &Pubkey, accounts: &[AccountInfo], args: OperatorBondRequestWithdrawalArgs) -> ProgramResult {
+    msg!("Instruction: OperatorBondRequestWithdrawal");
+    // get accounts
+    let OperatorBondRequestWithdrawalAccounts {
+        operator_bond_account_info,
+        mut operator_bond_state,
+        ..
+    } = OperatorBondRequestWithdrawalAccounts::try_from_accounts(accounts)?;
+    // update operator bond account, the lamports can be slashed until they are withdrawn
+    operator_bond_state.request_withdrawal(Clock::get()?.unix_timestamp, args.lamports)?;
+    operator_bond_state.serialize(&mut &mut operator_bond_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use home::home_dir;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::operator_bond::{OperatorBond, OperatorBondConfig},
+    };
+
+    use super::OperatorBondRequestWithdrawalArgs;
+
+    #[tokio::test]
+    async fn test_operator_bond_request_withdrawal_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        // accounts
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (operator_bond_pda, operator_bond_bump) = Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &program_id);
+        let mut operator_bond_state = OperatorBond::new(
+            OperatorBondConfig {
+                min_bond_lamports: 1000,
+                withdrawal_delay: 100,
+                fullfill_deadline: 60,
+                slash_penalty: 1000,
+            },
+            operator_bond_bump,
+        );
+        operator_bond_state.bonded_lamports = 3000;
+        let operator_bond_data = operator_bond_state.try_to_vec().unwrap();
+        program_test.add_account(
+            operator_bond_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(operator_bond_data.len()) + 3000,
+                data: operator_bond_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::OperatorBondRequestWithdrawal {
+                    args: OperatorBondRequestWithdrawalArgs { lamports: 2500 },
+                },
+                vec![AccountMeta::new_readonly(operator.pubkey(), true), AccountMeta::new(operator_bond_pda, false)],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the withdrawal should be pending and no longer count towards the minimum bond
+        let operator_bond_state: OperatorBond = banks_client.get_account_data_with_borsh(operator_bond_pda).await.unwrap();
+        assert_eq!(operator_bond_state.bonded_lamports, 3000);
+        assert_eq!(operator_bond_state.pending_withdrawal_lamports, 2500);
+        assert!(operator_bond_state.withdrawal_unlocks_at > 100);
+        assert!(!operator_bond_state.is_bonded());
+    }
+}
@@ -0,0 +1,168 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed, pubkey::Pubkey, rent::Rent, system_instruction, system_program,
+    sysvar::Sysvar,
+};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    constants::OPERATOR_PUBKEY,
+    state::{
+        operator_bond::{OperatorBond, OperatorBondConfig},
+        BettingAccount,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct OperatorBondSetConfigArgs {
+    pub config: OperatorBondConfig,
+}
+
+#[derive(Accounts)]
+pub struct OperatorBondSetConfigAccounts<'a, 'info> {
+    #[account(signer, writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"OperatorBond"],
+        bump = operator_bond_bump,
+        name = "operator_bond_pda",
+        desc = "Operator bond PDA Account"
+    )]
+    pub operator_bond_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    pub operator_bond_bump: u8,
+}
+
+pub fn operator_bond_set_config(program_id: &Pubkey, accounts: &[AccountInfo], args: OperatorBondSetConfigArgs) -> ProgramResult {
+    msg!("Instruction: OperatorBondSetConfig");
+    // get accounts
+    let OperatorBondSetConfigAccounts {
+        op_account_info,
+        operator_bond_account_info,
+        operator_bond_bump,
+        ..
+    } = OperatorBondSetConfigAccounts::try_from_accounts(accounts)?;
+    // check config
+    OperatorBond::check_config(&args.config)?;
+
+    if !operator_bond_account_info.data_is_empty() {
+        // update operator bond account, a new config applies to the bets that are not settled yet
+        let mut operator_bond_state = OperatorBond::try_from_account_info(operator_bond_account_info)?;
+        operator_bond_state.config = args.config;
+        operator_bond_state.serialize(&mut &mut operator_bond_account_info.data.borrow_mut()[..])?;
+        return Ok(());
+    }
+    // create operator bond account
+    let operator_bond_data = OperatorBond::new(args.config, operator_bond_bump).try_to_vec()?;
+    let operator_bond_data_len = operator_bond_data.len();
+    let min_rent = Rent::get()?.minimum_balance(operator_bond_data_len);
+    let operator_bond_signer_seeds = &[b"OperatorBond".as_ref(), &[operator_bond_bump]];
+    let operator_bond_create_ix = system_instruction::create_account(
+        op_account_info.key,
+        operator_bond_account_info.key,
+        min_rent,
+        operator_bond_data_len.try_into().unwrap(),
+        program_id,
+    );
+    invoke_signed(
+        &operator_bond_create_ix,
+        &[op_account_info.clone(), operator_bond_account_info.clone()],
+        &[operator_bond_signer_seeds],
+    )?;
+    operator_bond_account_info.data.borrow_mut().copy_from_slice(&operator_bond_data);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use home::home_dir;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            operator_bond::{OperatorBond, OperatorBondConfig},
+            StateAccountType,
+        },
+    };
+
+    use super::OperatorBondSetConfigArgs;
+
+    async fn process_set_config(config: OperatorBondConfig) -> OperatorBond {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        // accounts
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (operator_bond_pda, _) = Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::OperatorBondSetConfig {
+                    args: OperatorBondSetConfigArgs { config },
+                },
+                vec![
+                    AccountMeta::new(operator.pubkey(), true),
+                    AccountMeta::new(operator_bond_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        banks_client.get_account_data_with_borsh(operator_bond_pda).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_operator_bond_set_config_success() {
+        let config = OperatorBondConfig {
+            min_bond_lamports: LAMPORTS_PER_SOL,
+            withdrawal_delay: 7 * 24 * 60 * 60,
+            fullfill_deadline: 60,
+            slash_penalty: 1000,
+        };
+        let operator_bond_state = process_set_config(config).await;
+
+        // the operator bond account should be created empty
+        assert_eq!(operator_bond_state.account_type, StateAccountType::OperatorBond);
+        assert_eq!(operator_bond_state.config, config);
+        assert_eq!(operator_bond_state.bonded_lamports, 0);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(26)")]
+    async fn test_operator_bond_set_config_err_invalid_config() {
+        process_set_config(OperatorBondConfig {
+            min_bond_lamports: LAMPORTS_PER_SOL,
+            withdrawal_delay: 0,
+            fullfill_deadline: 60,
+            slash_penalty: 20000,
+        })
+        .await;
+    }
+}
@@ -0,0 +1,122 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke, pubkey::Pubkey, system_instruction, system_program};
+use vrf_betting_derive::Accounts;
+
+use crate::{math::CheckedMath, state::operator_bond::OperatorBond};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct OperatorBondTopUpArgs {
+    pub lamports: u64,
+}
+
+#[derive(Accounts)]
+pub struct OperatorBondTopUpAccounts<'a, 'info> {
+    #[account(signer, writable, name = "funder", desc = "The account to transfer lamports from")]
+    pub funder_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"OperatorBond"],
+        stored_bump = operator_bond_state.bump,
+        name = "operator_bond_pda",
+        desc = "Operator bond PDA Account"
+    )]
+    pub operator_bond_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[state(operator_bond_account_info)]
+    pub operator_bond_state: OperatorBond,
+}
+
+pub fn operator_bond_top_up(_program_id: &Pubkey, accounts: &[AccountInfo], args: OperatorBondTopUpArgs) -> ProgramResult {
+    msg!("Instruction: OperatorBondTopUp");
+    // get accounts
+    let OperatorBondTopUpAccounts {
+        funder_account_info,
+        operator_bond_account_info,
+        mut operator_bond_state,
+        ..
+    } = OperatorBondTopUpAccounts::try_from_accounts(accounts)?;
+    // transfer lamports
+    let lamports_transfer_ix = system_instruction::transfer(funder_account_info.key, operator_bond_account_info.key, args.lamports);
+    invoke(&lamports_transfer_ix, &[funder_account_info.clone(), operator_bond_account_info.clone()])?;
+    // update operator bond account
+    operator_bond_state.bonded_lamports = operator_bond_state.bonded_lamports.try_add(args.lamports)?;
+    operator_bond_state.serialize(&mut &mut operator_bond_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::operator_bond::{OperatorBond, OperatorBondConfig},
+    };
+
+    use super::OperatorBondTopUpArgs;
+
+    #[tokio::test]
+    async fn test_operator_bond_top_up_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let funder = Keypair::new();
+        program_test.add_account(
+            funder.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (operator_bond_pda, operator_bond_bump) = Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &program_id);
+        let mut operator_bond_state = OperatorBond::new(OperatorBondConfig::default(), operator_bond_bump);
+        operator_bond_state.bonded_lamports = 1000;
+        let operator_bond_data = operator_bond_state.try_to_vec().unwrap();
+        let operator_bond_rent = Rent::default().minimum_balance(operator_bond_data.len());
+        program_test.add_account(
+            operator_bond_pda,
+            Account {
+                lamports: operator_bond_rent + 1000,
+                data: operator_bond_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::OperatorBondTopUp {
+                    args: OperatorBondTopUpArgs { lamports: 5000 },
+                },
+                vec![
+                    AccountMeta::new(funder.pubkey(), true),
+                    AccountMeta::new(operator_bond_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&funder, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the lamports should be bonded
+        let operator_bond_state: OperatorBond = banks_client.get_account_data_with_borsh(operator_bond_pda).await.unwrap();
+        assert_eq!(operator_bond_state.bonded_lamports, 6000);
+        let operator_bond_account = banks_client.get_account(operator_bond_pda).await.unwrap().unwrap();
+        assert_eq!(operator_bond_account.lamports, operator_bond_rent + 6000);
+    }
+}
@@ -0,0 +1,126 @@
+use borsh::BorshSerialize;
+use solana_program::{account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, pubkey::Pubkey, sysvar::Sysvar};
+use vrf_betting_derive::Accounts;
+
+use crate::{constants::OPERATOR_PUBKEY, math::CheckedMath, state::operator_bond::OperatorBond};
+
+#[derive(Accounts)]
+pub struct OperatorBondWithdrawAccounts<'a, 'info> {
+    #[account(signer, writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"OperatorBond"],
+        stored_bump = operator_bond_state.bump,
+        name = "operator_bond_pda",
+        desc = "Operator bond PDA Account"
+    )]
+    pub operator_bond_account_info: &'a AccountInfo<'info>,
+    #[state(operator_bond_account_info)]
+    pub operator_bond_state: OperatorBond,
+}
+
+pub fn operator_bond_withdraw(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    msg!("Instruction: OperatorBondWithdraw");
+    // get accounts
+    let OperatorBondWithdrawAccounts {
+        op_account_info,
+        operator_bond_account_info,
+        mut operator_bond_state,
+    } = OperatorBondWithdrawAccounts::try_from_accounts(accounts)?;
+    // update operator bond account
+    let lamports = operator_bond_state.withdraw(Clock::get()?.unix_timestamp)?;
+    operator_bond_state.serialize(&mut &mut operator_bond_account_info.data.borrow_mut()[..])?;
+    // transfer lamports to op
+    **operator_bond_account_info.lamports.borrow_mut() = operator_bond_account_info.lamports().try_sub(lamports)?;
+    **op_account_info.lamports.borrow_mut() = op_account_info.lamports().try_add(lamports)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use home::home_dir;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, BanksClient, ProgramTest};
+    use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::operator_bond::{OperatorBond, OperatorBondConfig},
+    };
+
+    /// Withdraw 2500 of the 3000 bonded lamports, the withdrawal unlocking at the given unix timestamp
+    async fn process_withdraw(withdrawal_unlocks_at: i64) -> (BanksClient, Pubkey, Pubkey, u64) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        // accounts
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (operator_bond_pda, operator_bond_bump) = Pubkey::find_program_address(&[b"OperatorBond".as_ref()], &program_id);
+        let mut operator_bond_state = OperatorBond::new(OperatorBondConfig::default(), operator_bond_bump);
+        operator_bond_state.bonded_lamports = 3000;
+        operator_bond_state.pending_withdrawal_lamports = 2500;
+        operator_bond_state.withdrawal_unlocks_at = withdrawal_unlocks_at;
+        let operator_bond_data = operator_bond_state.try_to_vec().unwrap();
+        let operator_bond_rent = Rent::default().minimum_balance(operator_bond_data.len());
+        program_test.add_account(
+            operator_bond_pda,
+            Account {
+                lamports: operator_bond_rent + 3000,
+                data: operator_bond_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::OperatorBondWithdraw,
+                vec![AccountMeta::new(operator.pubkey(), true), AccountMeta::new(operator_bond_pda, false)],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        (banks_client, operator.pubkey(), operator_bond_pda, operator_bond_rent)
+    }
+
+    #[tokio::test]
+    async fn test_operator_bond_withdraw_success() {
+        let (mut banks_client, operator, operator_bond_pda, operator_bond_rent) = process_withdraw(0).await;
+
+        // the pending withdrawal should be paid to the operator
+        let operator_bond_state: OperatorBond = banks_client.get_account_data_with_borsh(operator_bond_pda).await.unwrap();
+        assert_eq!(operator_bond_state.bonded_lamports, 500);
+        assert_eq!(operator_bond_state.pending_withdrawal_lamports, 0);
+        let operator_bond_account = banks_client.get_account(operator_bond_pda).await.unwrap().unwrap();
+        assert_eq!(operator_bond_account.lamports, operator_bond_rent + 500);
+        let operator_account = banks_client.get_account(operator).await.unwrap().unwrap();
+        assert_eq!(operator_account.lamports, LAMPORTS_PER_SOL + 2500);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(29)")]
+    async fn test_operator_bond_withdraw_err_locked() {
+        process_withdraw(i64::MAX).await;
+    }
+}
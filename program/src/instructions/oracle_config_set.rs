@@ -0,0 +1,197 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    constants::OPERATOR_PUBKEY,
+    math::CheckedMath,
+    state::{randomness::OracleConfig, BettingAccount},
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct OracleConfigSetArgs {
+    pub oracles: Vec<Pubkey>,
+}
+
+#[derive(Accounts)]
+pub struct OracleConfigSetAccounts<'a, 'info> {
+    #[account(signer, writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"OracleConfig"],
+        bump = oracle_config_bump,
+        name = "oracle_config_pda",
+        desc = "Oracle config PDA Account"
+    )]
+    pub oracle_config_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    pub oracle_config_bump: u8,
+}
+
+pub fn oracle_config_set(program_id: &Pubkey, accounts: &[AccountInfo], args: OracleConfigSetArgs) -> ProgramResult {
+    msg!("Instruction: OracleConfigSet");
+    // get accounts
+    let OracleConfigSetAccounts {
+        op_account_info,
+        oracle_config_account_info,
+        system_program_account_info,
+        oracle_config_bump,
+    } = OracleConfigSetAccounts::try_from_accounts(accounts)?;
+    // check oracles
+    OracleConfig::check_oracles(&args.oracles)?;
+
+    let oracle_config_state = OracleConfig::new(args.oracles);
+    let oracle_config_data = oracle_config_state.try_to_vec()?;
+    let oracle_config_data_len = oracle_config_data.len();
+    let min_rent = Rent::get()?.minimum_balance(oracle_config_data_len);
+    if oracle_config_account_info.data_is_empty() {
+        // create oracle config account
+        let oracle_config_signer_seeds = &[b"OracleConfig".as_ref(), &[oracle_config_bump]];
+        let oracle_config_create_ix = system_instruction::create_account(
+            op_account_info.key,
+            oracle_config_account_info.key,
+            min_rent,
+            oracle_config_data_len.try_into().unwrap(),
+            program_id,
+        );
+        invoke_signed(
+            &oracle_config_create_ix,
+            &[op_account_info.clone(), oracle_config_account_info.clone()],
+            &[oracle_config_signer_seeds],
+        )?;
+    } else {
+        // resize oracle config account
+        OracleConfig::try_from_account_info(oracle_config_account_info)?;
+        oracle_config_account_info.realloc(oracle_config_data_len, false)?;
+        let current_rent = oracle_config_account_info.lamports();
+        if current_rent < min_rent {
+            let rent_transfer_ix = system_instruction::transfer(op_account_info.key, oracle_config_account_info.key, min_rent - current_rent);
+            invoke(
+                &rent_transfer_ix,
+                &[op_account_info.clone(), oracle_config_account_info.clone(), system_program_account_info.clone()],
+            )?;
+        } else if current_rent > min_rent {
+            **op_account_info.lamports.borrow_mut() = op_account_info.lamports().try_add(current_rent - min_rent)?;
+            **oracle_config_account_info.lamports.borrow_mut() = min_rent;
+        }
+    }
+    // save state
+    oracle_config_account_info.data.borrow_mut().copy_from_slice(&oracle_config_data);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use home::home_dir;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{randomness::OracleConfig, StateAccountType},
+    };
+
+    use super::OracleConfigSetArgs;
+
+    #[tokio::test]
+    async fn test_oracle_config_set_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        // accounts
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (oracle_config_pda, _) = Pubkey::find_program_address(&[b"OracleConfig".as_ref()], &program_id);
+        let oracles = vec![Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::OracleConfigSet {
+                    args: OracleConfigSetArgs { oracles: oracles.clone() },
+                },
+                vec![
+                    AccountMeta::new(operator.pubkey(), true),
+                    AccountMeta::new(oracle_config_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the oracle config account should be created
+        let oracle_config_state: OracleConfig = banks_client.get_account_data_with_borsh(oracle_config_pda).await.unwrap();
+        assert_eq!(oracle_config_state.account_type, StateAccountType::OracleConfig);
+        assert_eq!(oracle_config_state.oracles, oracles);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(24)")]
+    async fn test_oracle_config_set_err_duplicate_oracles() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        // accounts
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (oracle_config_pda, _) = Pubkey::find_program_address(&[b"OracleConfig".as_ref()], &program_id);
+        let oracle = Pubkey::new_unique();
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::OracleConfigSet {
+                    args: OracleConfigSetArgs { oracles: vec![oracle, oracle] },
+                },
+                vec![
+                    AccountMeta::new(operator.pubkey(), true),
+                    AccountMeta::new(oracle_config_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::hashv,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::{self, Sysvar},
+};
+use vrf_betting_derive::Accounts;
+
+use crate::state::randomness_request::{RandomnessCallback, RandomnessRequest};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct RandomnessRequestArgs {
+    pub seed: [u8; 32],
+    pub callback: Option<RandomnessCallback>,
+}
+
+#[derive(Accounts)]
+#[accounts(args = RandomnessRequestArgs)]
+pub struct RandomnessRequestAccounts<'a, 'info> {
+    #[account(signer, name = "requester", desc = "The account requesting randomness, usually a PDA of the calling program")]
+    pub requester_account_info: &'a AccountInfo<'info>,
+    #[account(signer, writable, name = "payer", desc = "The account paying for the rent of the request")]
+    pub payer_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"RandomnessRequest", requester_account_info.key, args.seed],
+        bump = randomness_request_pda_bump,
+        name = "randomness_request_pda",
+        desc = "Randomness request PDA Account"
+    )]
+    pub randomness_request_account_info: &'a AccountInfo<'info>,
+    #[account(address = sysvar::slot_hashes::ID, name = "slot_hashes", desc = "Slot hashes account")]
+    pub slot_hashes_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    pub randomness_request_pda_bump: u8,
+}
+
+pub fn randomness_request(program_id: &Pubkey, accounts: &[AccountInfo], args: RandomnessRequestArgs) -> ProgramResult {
+    msg!("Instruction: RandomnessRequest");
+    // get accounts
+    let RandomnessRequestAccounts {
+        requester_account_info,
+        payer_account_info,
+        randomness_request_account_info,
+        slot_hashes_account_info,
+        randomness_request_pda_bump,
+        ..
+    } = RandomnessRequestAccounts::try_from_accounts(accounts, &args)?;
+    // check callback
+    if let Some(callback) = &args.callback {
+        callback.check_callback()?;
+    }
+    // the alpha mixes the seed of the requester with a recent slot hash, so that neither side can pick it
+    let mut alpha = [0u8; 72];
+    alpha[0..8].copy_from_slice(&Clock::get()?.unix_timestamp.to_le_bytes());
+    alpha[8..40].copy_from_slice(&hashv(&[requester_account_info.key.as_ref(), &args.seed]).to_bytes());
+    alpha[40..72].copy_from_slice(&slot_hashes_account_info.data.borrow()[16..48]);
+    // create randomness request account
+    let randomness_request_state = RandomnessRequest::new(*requester_account_info.key, args.seed, alpha, args.callback, randomness_request_pda_bump);
+    let randomness_request_data = randomness_request_state.try_to_vec()?;
+    let randomness_request_data_len = randomness_request_data.len();
+    let randomness_request_pda_signer_seeds = &[
+        b"RandomnessRequest".as_ref(),
+        requester_account_info.key.as_ref(),
+        &args.seed,
+        &[randomness_request_pda_bump],
+    ];
+    let min_rent = Rent::get()?.minimum_balance(randomness_request_data_len);
+    let randomness_request_create_ix = system_instruction::create_account(
+        payer_account_info.key,
+        randomness_request_account_info.key,
+        min_rent,
+        randomness_request_data_len.try_into().unwrap(),
+        program_id,
+    );
+    invoke_signed(
+        &randomness_request_create_ix,
+        &[payer_account_info.clone(), randomness_request_account_info.clone()],
+        &[randomness_request_pda_signer_seeds],
+    )?;
+    randomness_request_account_info.data.borrow_mut().copy_from_slice(&randomness_request_data);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        system_program, sysvar,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            randomness_request::{CallbackAccount, RandomnessCallback, RandomnessRequest},
+            StateAccountType,
+        },
+    };
+
+    use super::RandomnessRequestArgs;
+
+    async fn process_request(callback: Option<RandomnessCallback>) -> (Pubkey, RandomnessRequest) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let payer_account = Keypair::new();
+        program_test.add_account(
+            payer_account.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+        // a wallet stands in for the PDA a calling program would sign with
+        let requester = Keypair::new();
+        let seed = [4; 32];
+        let (randomness_request_pda, _) = RandomnessRequest::find_pda(&requester.pubkey(), &seed);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::RandomnessRequest {
+                    args: RandomnessRequestArgs { seed, callback },
+                },
+                vec![
+                    AccountMeta::new_readonly(requester.pubkey(), true),
+                    AccountMeta::new(payer_account.pubkey(), true),
+                    AccountMeta::new(randomness_request_pda, false),
+                    AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&requester, &payer_account, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        (
+            requester.pubkey(),
+            banks_client.get_account_data_with_borsh(randomness_request_pda).await.unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_randomness_request_success() {
+        let callback = RandomnessCallback {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![CallbackAccount {
+                pubkey: Pubkey::new_unique(),
+                is_writable: true,
+            }],
+            instruction_data: vec![1],
+        };
+        let (requester, randomness_request_state) = process_request(Some(callback.clone())).await;
+
+        // the randomness request account should be created unfullfilled
+        assert_eq!(randomness_request_state.account_type, StateAccountType::RandomnessRequest);
+        assert_eq!(randomness_request_state.requester, requester);
+        assert_eq!(randomness_request_state.seed, [4; 32]);
+        assert!(!randomness_request_state.is_fullfilled);
+        assert_eq!(randomness_request_state.callback, Some(callback));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(30)")]
+    async fn test_randomness_request_err_invalid_callback() {
+        process_request(Some(RandomnessCallback {
+            program_id: crate::id(),
+            accounts: vec![],
+            instruction_data: vec![],
+        }))
+        .await;
+    }
+}
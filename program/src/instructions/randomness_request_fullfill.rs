@@ -0,0 +1,202 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{checks::check_pubkey_eq, constants::OPERATOR_PUBKEY, error::BettingError, state::randomness_request::RandomnessRequest};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct RandomnessRequestFullfillArgs {
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+}
+
+#[derive(Accounts)]
+pub struct RandomnessRequestFullfillAccounts<'a, 'info> {
+    #[account(signer, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"RandomnessRequest", randomness_request_state.requester, randomness_request_state.seed],
+        stored_bump = randomness_request_state.bump,
+        name = "randomness_request_pda",
+        desc = "Randomness request PDA Account"
+    )]
+    pub randomness_request_account_info: &'a AccountInfo<'info>,
+    #[state(randomness_request_account_info)]
+    pub randomness_request_state: RandomnessRequest,
+}
+
+pub fn randomness_request_fullfill(_program_id: &Pubkey, accounts: &[AccountInfo], args: RandomnessRequestFullfillArgs) -> ProgramResult {
+    msg!("Instruction: RandomnessRequestFullfill");
+    // get accounts, the callback program and its accounts follow the declared ones
+    let RandomnessRequestFullfillAccounts {
+        randomness_request_account_info,
+        mut randomness_request_state,
+        ..
+    } = RandomnessRequestFullfillAccounts::try_from_accounts(accounts)?;
+    let callback_account_infos = &accounts[2..];
+    // check conditions
+    if randomness_request_state.is_fullfilled {
+        msg!("Randomness request account {} is already fullfilled", randomness_request_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyFullfilled));
+    }
+    // update randomness request account, it is written before the callback reads it
+    randomness_request_state.is_fullfilled = true;
+    randomness_request_state.beta = args.beta;
+    randomness_request_state.pi = args.pi;
+    randomness_request_state.serialize(&mut &mut randomness_request_account_info.data.borrow_mut()[..])?;
+    // call back the requesting program, the request account signs to prove the randomness comes from here
+    if let Some(callback_ix) = randomness_request_state.callback_instruction(randomness_request_account_info.key) {
+        let callback_program_account_info = callback_account_infos.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        check_pubkey_eq(callback_program_account_info, &callback_ix.program_id)?;
+        if callback_account_infos.len() < callback_ix.accounts.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        for (account_info, account_meta) in callback_account_infos[1..].iter().zip(&callback_ix.accounts[1..]) {
+            check_pubkey_eq(account_info, &account_meta.pubkey)?;
+        }
+        let mut callback_account_infos = callback_account_infos[..callback_ix.accounts.len()].to_vec();
+        callback_account_infos.push(randomness_request_account_info.clone());
+        let randomness_request_pda_signer_seeds = &[
+            b"RandomnessRequest".as_ref(),
+            randomness_request_state.requester.as_ref(),
+            &randomness_request_state.seed,
+            &[randomness_request_state.bump],
+        ];
+        invoke_signed(&callback_ix, &callback_account_infos, &[randomness_request_pda_signer_seeds])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use home::home_dir;
+    use solana_program::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{processor, tokio, BanksClient, ProgramTest};
+    use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::randomness_request::{CallbackAccount, RandomnessCallback, RandomnessRequest},
+    };
+
+    use super::RandomnessRequestFullfillArgs;
+
+    /// Callback of a raffle, storing the randomness once it is signed by a request of the betting program
+    fn process_raffle_callback(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let (request_account_info, raffle_account_info) = (&accounts[0], &accounts[1]);
+        if !request_account_info.is_signer || request_account_info.owner != &crate::id() {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        raffle_account_info.data.borrow_mut().copy_from_slice(&data[1..]);
+        Ok(())
+    }
+
+    /// Fullfill a request, calling back a raffle program with the given tag when there is one
+    async fn process_fullfill(callback_tag: Option<u8>) -> (BanksClient, Pubkey, Pubkey) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+        let raffle_program_id = Pubkey::new_unique();
+        program_test.add_program("raffle", raffle_program_id, processor!(process_raffle_callback));
+
+        // accounts
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let raffle = Pubkey::new_unique();
+        program_test.add_account(
+            raffle,
+            Account {
+                lamports: Rent::default().minimum_balance(64),
+                data: vec![0; 64],
+                owner: raffle_program_id,
+                ..Default::default()
+            },
+        );
+
+        let requester = Pubkey::new_unique();
+        let seed = [4; 32];
+        let (randomness_request_pda, randomness_request_bump) = RandomnessRequest::find_pda(&requester, &seed);
+        let callback = callback_tag.map(|tag| RandomnessCallback {
+            program_id: raffle_program_id,
+            accounts: vec![CallbackAccount {
+                pubkey: raffle,
+                is_writable: true,
+            }],
+            instruction_data: vec![tag],
+        });
+        let randomness_request_data = RandomnessRequest::new(requester, seed, [5; 72], callback, randomness_request_bump)
+            .try_to_vec()
+            .unwrap();
+        program_test.add_account(
+            randomness_request_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(randomness_request_data.len()),
+                data: randomness_request_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::RandomnessRequestFullfill {
+                    args: RandomnessRequestFullfillArgs { beta: [6; 64], pi: [7; 80] },
+                },
+                vec![
+                    AccountMeta::new_readonly(operator.pubkey(), true),
+                    AccountMeta::new(randomness_request_pda, false),
+                    AccountMeta::new_readonly(raffle_program_id, false),
+                    AccountMeta::new(raffle, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        (banks_client, randomness_request_pda, raffle)
+    }
+
+    #[tokio::test]
+    async fn test_randomness_request_fullfill_success() {
+        let (mut banks_client, randomness_request_pda, raffle) = process_fullfill(None).await;
+
+        // the randomness request account should be fullfilled
+        let randomness_request_state: RandomnessRequest = banks_client.get_account_data_with_borsh(randomness_request_pda).await.unwrap();
+        assert!(randomness_request_state.is_fullfilled);
+        assert_eq!(randomness_request_state.beta, [6; 64]);
+        assert_eq!(randomness_request_state.pi, [7; 80]);
+        // without a callback the raffle is left alone
+        let raffle_account = banks_client.get_account(raffle).await.unwrap().unwrap();
+        assert_eq!(raffle_account.data, [0; 64]);
+    }
+
+    #[tokio::test]
+    async fn test_randomness_request_fullfill_success_callback() {
+        let (mut banks_client, _, raffle) = process_fullfill(Some(1)).await;
+
+        // the raffle program should be called back with the beta
+        let raffle_account = banks_client.get_account(raffle).await.unwrap().unwrap();
+        assert_eq!(raffle_account.data, [6; 64]);
+    }
+}
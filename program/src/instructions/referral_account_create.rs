@@ -0,0 +1,98 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey, system_program};
+use vrf_betting_derive::Accounts;
+
+use crate::state::referral::Referral;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct ReferralAccountCreateArgs {
+    pub referrer: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct ReferralAccountCreateAccounts<'a, 'info> {
+    #[account(signer, writable, name = "payer", desc = "The account paying for the rent")]
+    pub payer_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "referral_pda", desc = "Referral PDA Account of the referrer")]
+    pub referral_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+}
+
+pub fn referral_account_create(_program_id: &Pubkey, accounts: &[AccountInfo], args: ReferralAccountCreateArgs) -> ProgramResult {
+    msg!("Instruction: ReferralAccountCreate");
+    // get accounts
+    let ReferralAccountCreateAccounts {
+        payer_account_info,
+        referral_account_info,
+        ..
+    } = ReferralAccountCreateAccounts::try_from_accounts(accounts)?;
+    // create referral account
+    Referral::load_or_create(&args.referrer, referral_account_info, payer_account_info)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{referral::Referral, StateAccountType},
+    };
+
+    use super::ReferralAccountCreateArgs;
+
+    #[tokio::test]
+    async fn test_referral_account_create_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let payer_wallet = Keypair::new();
+        program_test.add_account(
+            payer_wallet.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let referrer = Pubkey::new_unique();
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referrer.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::ReferralAccountCreate {
+                    args: ReferralAccountCreateArgs { referrer },
+                },
+                vec![
+                    AccountMeta::new(payer_wallet.pubkey(), true),
+                    AccountMeta::new(referral_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer_wallet, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the referral account should be created
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.account_type, StateAccountType::Referral);
+        assert_eq!(referral_state.referrer, referrer);
+        assert_eq!(referral_state.referred_users, 0);
+        assert_eq!(referral_state.referred_volume, 0);
+        assert_eq!(referral_state.total_lamports_earned, 0);
+        assert_eq!(referral_state.unclaimed_lamports, 0);
+    }
+}
@@ -0,0 +1,189 @@
+use borsh::BorshSerialize;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    error::BettingError,
+    math::CheckedMath,
+    state::{referral::Referral, user_account::UserAccount},
+};
+
+#[derive(Accounts)]
+pub struct ReferralClaimAccounts<'a, 'info> {
+    #[account(signer, address = referrer_user_account_state.authority, name = "referrer", desc = "Referrer wallet account")]
+    pub referrer_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", referrer_user_account_state.authority],
+        stored_bump = referrer_user_account_state.bump,
+        name = "referrer_user_account",
+        desc = "User Betting Account of the referrer"
+    )]
+    pub referrer_user_account_info: &'a AccountInfo<'info>,
+    // referrals set before they were validated point to the referrer's wallet
+    #[account(
+        writable,
+        seeds = [b"Referral", referral_state.referrer],
+        constraint = &referral_state.referrer == referrer_user_account_info.key
+            || &referral_state.referrer == referrer_wallet_account_info.key @ BettingError::NoAuthority,
+        name = "referral_pda",
+        desc = "Referral PDA Account of the referrer"
+    )]
+    pub referral_account_info: &'a AccountInfo<'info>,
+    #[state(referrer_user_account_info)]
+    pub referrer_user_account_state: UserAccount,
+    #[state(referral_account_info)]
+    pub referral_state: Referral,
+}
+
+pub fn referral_claim(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    msg!("Instruction: ReferralClaim");
+    // get accounts
+    let ReferralClaimAccounts {
+        referrer_user_account_info,
+        referral_account_info,
+        mut referrer_user_account_state,
+        mut referral_state,
+        ..
+    } = ReferralClaimAccounts::try_from_accounts(accounts)?;
+    // move the rewards to the referrer's balance
+    referrer_user_account_state.current_lamports = referrer_user_account_state.current_lamports.try_add(referral_state.unclaimed_lamports)?;
+    referrer_user_account_state.serialize(&mut &mut referrer_user_account_info.data.borrow_mut()[..])?;
+    referral_state.unclaimed_lamports = 0;
+    referral_state.serialize(&mut &mut referral_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{referral::Referral, user_account::UserAccount},
+    };
+
+    #[tokio::test]
+    async fn test_referral_claim_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let referrer = Keypair::new();
+        let (referrer_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), referrer.pubkey().as_ref()], &program_id);
+        let mut referrer_user_account_state = UserAccount::new(referrer.pubkey(), None, None);
+        referrer_user_account_state.current_lamports = 1000;
+        let referrer_user_account_data = referrer_user_account_state.try_to_vec().unwrap();
+        let referrer_user_account_data_len = referrer_user_account_data.len();
+        program_test.add_account(
+            referrer_user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referrer_user_account_data_len),
+                data: referrer_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referrer_user_account_pda.as_ref()], &program_id);
+        let mut referral_state = Referral::new(referrer_user_account_pda);
+        referral_state.total_lamports_earned = 500;
+        referral_state.unclaimed_lamports = 300;
+        let referral_data = referral_state.try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
+        program_test.add_account(
+            referral_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::ReferralClaim,
+                vec![
+                    AccountMeta::new_readonly(referrer.pubkey(), true),
+                    AccountMeta::new(referrer_user_account_pda, false),
+                    AccountMeta::new(referral_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&referrer, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the rewards should be moved to the referrer's balance
+        let referrer_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(referrer_user_account_pda).await.unwrap();
+        assert_eq!(referrer_user_account_state.current_lamports, 1300);
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.unclaimed_lamports, 0);
+        assert_eq!(referral_state.total_lamports_earned, 500);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(0)")]
+    async fn test_referral_claim_err_no_authority() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let user_account_state = UserAccount::new(user.pubkey(), None, None);
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        // the referral account belongs to someone else
+        let referrer = Pubkey::new_unique();
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referrer.as_ref()], &program_id);
+        let mut referral_state = Referral::new(referrer);
+        referral_state.unclaimed_lamports = 300;
+        let referral_data = referral_state.try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
+        program_test.add_account(
+            referral_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::ReferralClaim,
+                vec![
+                    AccountMeta::new_readonly(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(referral_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    constants::OPERATOR_PUBKEY,
+    math::CheckedMath,
+    state::{
+        referral::{ReferralConfig, ReferralTier},
+        BettingAccount,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct ReferralConfigSetArgs {
+    pub tiers: Vec<ReferralTier>,
+}
+
+#[derive(Accounts)]
+pub struct ReferralConfigSetAccounts<'a, 'info> {
+    #[account(signer, writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"ReferralConfig"],
+        bump = referral_config_bump,
+        name = "referral_config_pda",
+        desc = "Referral config PDA Account"
+    )]
+    pub referral_config_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    pub referral_config_bump: u8,
+}
+
+pub fn referral_config_set(program_id: &Pubkey, accounts: &[AccountInfo], args: ReferralConfigSetArgs) -> ProgramResult {
+    msg!("Instruction: ReferralConfigSet");
+    // get accounts
+    let ReferralConfigSetAccounts {
+        op_account_info,
+        referral_config_account_info,
+        system_program_account_info,
+        referral_config_bump,
+    } = ReferralConfigSetAccounts::try_from_accounts(accounts)?;
+    // check tiers
+    ReferralConfig::check_tiers(&args.tiers)?;
+
+    let referral_config_state = ReferralConfig::new(args.tiers);
+    let referral_config_data = referral_config_state.try_to_vec()?;
+    let referral_config_data_len = referral_config_data.len();
+    let min_rent = Rent::get()?.minimum_balance(referral_config_data_len);
+    if referral_config_account_info.data_is_empty() {
+        // create referral config account
+        let referral_config_signer_seeds = &[b"ReferralConfig".as_ref(), &[referral_config_bump]];
+        let referral_config_create_ix = system_instruction::create_account(
+            op_account_info.key,
+            referral_config_account_info.key,
+            min_rent,
+            referral_config_data_len.try_into().unwrap(),
+            program_id,
+        );
+        invoke_signed(
+            &referral_config_create_ix,
+            &[op_account_info.clone(), referral_config_account_info.clone()],
+            &[referral_config_signer_seeds],
+        )?;
+    } else {
+        // resize referral config account
+        ReferralConfig::try_from_account_info(referral_config_account_info)?;
+        referral_config_account_info.realloc(referral_config_data_len, false)?;
+        let current_rent = referral_config_account_info.lamports();
+        if current_rent < min_rent {
+            let rent_transfer_ix = system_instruction::transfer(op_account_info.key, referral_config_account_info.key, min_rent - current_rent);
+            invoke(
+                &rent_transfer_ix,
+                &[
+                    op_account_info.clone(),
+                    referral_config_account_info.clone(),
+                    system_program_account_info.clone(),
+                ],
+            )?;
+        } else if current_rent > min_rent {
+            **op_account_info.lamports.borrow_mut() = op_account_info.lamports().try_add(current_rent - min_rent)?;
+            **referral_config_account_info.lamports.borrow_mut() = min_rent;
+        }
+    }
+    // save state
+    referral_config_account_info.data.borrow_mut().copy_from_slice(&referral_config_data);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use home::home_dir;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            referral::{ReferralConfig, ReferralTier},
+            StateAccountType,
+        },
+    };
+
+    use super::ReferralConfigSetArgs;
+
+    #[tokio::test]
+    async fn test_referral_config_set_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        // accounts
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (referral_config_pda, _) = Pubkey::find_program_address(&[b"ReferralConfig".as_ref()], &program_id);
+        let tiers = vec![
+            ReferralTier {
+                min_referred_volume: 0,
+                share: 3000,
+            },
+            ReferralTier {
+                min_referred_volume: 100 * LAMPORTS_PER_SOL,
+                share: 5000,
+            },
+        ];
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::ReferralConfigSet {
+                    args: ReferralConfigSetArgs { tiers: tiers.clone() },
+                },
+                vec![
+                    AccountMeta::new(operator.pubkey(), true),
+                    AccountMeta::new(referral_config_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the referral config account should be created
+        let referral_config_state: ReferralConfig = banks_client.get_account_data_with_borsh(referral_config_pda).await.unwrap();
+        assert_eq!(referral_config_state.account_type, StateAccountType::ReferralConfig);
+        assert_eq!(referral_config_state.tiers, tiers);
+    }
+}
@@ -2,41 +2,35 @@ use std::convert::TryInto;
 
 use borsh::BorshSerialize;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program::invoke_signed,
-    pubkey::Pubkey,
-    rent::Rent,
-    system_instruction, system_program,
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed, pubkey::Pubkey, rent::Rent, system_instruction, system_program,
     sysvar::Sysvar,
 };
+use vrf_betting_derive::Accounts;
 
-use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
-    constants::OPERATOR_PUBKEY,
-    state::stats::Stats,
-};
+use crate::{constants::OPERATOR_PUBKEY, state::stats::Stats};
+
+#[derive(Accounts)]
+pub struct StatsAccountCreateAccounts<'a, 'info> {
+    #[account(signer, writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], bump = stats_account_bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_accournt_info: &'a AccountInfo<'info>,
+    pub stats_account_bump: u8,
+}
 
 pub fn stats_account_create(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     msg!("Instruction: StatsAccountCreate");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let op_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let system_program_accournt_info = next_account_info(iter)?;
-    // check accounts
-    check_is_signer(op_account_info)?;
-    check_is_writable(op_account_info)?;
-    check_pubkey_eq(op_account_info, &OPERATOR_PUBKEY)?;
-
-    check_is_writable(stats_account_info)?;
-    let stats_account_bump = check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    check_pubkey_eq(system_program_accournt_info, &system_program::ID)?;
+    let StatsAccountCreateAccounts {
+        op_account_info,
+        stats_account_info,
+        stats_account_bump,
+        ..
+    } = StatsAccountCreateAccounts::try_from_accounts(accounts)?;
     // create account
-    let stats_account_state = Stats::new();
+    let stats_account_state = Stats::new_with_bump(stats_account_bump);
     let stats_account_data = stats_account_state.try_to_vec()?;
     let stats_account_signer_seeds = &[b"Stats".as_ref(), &[stats_account_bump]];
     let min_rent = Rent::get()?.minimum_balance(stats_account_data.len());
@@ -111,12 +105,13 @@ mod test {
 
         // the stats account should be created
         let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
-        assert_eq!(stats_state.account_type, StateAccountType::Stats);
+        assert_eq!(stats_state.account_type, StateAccountType::Stats as u8);
         assert_eq!(stats_state.total_games, 0);
         assert_eq!(stats_state.total_users, 0);
         assert_eq!(stats_state.total_bets, 0);
         assert_eq!(stats_state.total_wager, 0);
-        assert_eq!(stats_state.total_lamports_won_by_bettors, 0);
+        assert_eq!(stats_state.total_bettor_pnl, 0);
+        assert_eq!(stats_state.total_host_pnl, 0);
         assert_eq!(stats_state.total_lamports_deposited, 0);
         assert_eq!(stats_state.total_lamports_withdrew, 0);
     }
@@ -1,51 +1,61 @@
 use borsh::BorshSerialize;
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-    system_program,
-};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, system_program};
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
     error::BettingError,
-    state::{stats::Stats, user_account::UserAccount, BettingAccount},
+    math::CheckedMath,
+    state::{stats::Stats, user_account::UserAccount, username::UsernameRecord},
 };
 
+#[derive(Accounts)]
+pub struct UserAccountCloseAccounts<'a, 'info> {
+    #[account(
+        signer,
+        writable,
+        authority = user_account_state.authority,
+        name = "user_wallet_account",
+        desc = "User Wallet Account"
+    )]
+    pub user_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", user_account_state.authority],
+        stored_bump = user_account_state.bump,
+        name = "user_account",
+        desc = "User Betting Account"
+    )]
+    pub user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_account_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    // the username account is only passed when the user holds a username
+    #[account(
+        writable,
+        optional = user_account_state.username().filter(|username| UserAccount::check_username(username).is_ok()).is_some(),
+        name = "username_pda",
+        desc = "Username registry PDA, required when the user has a username"
+    )]
+    pub username_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(user_account_info)]
+    pub user_account_state: UserAccount,
+    #[state(stats_account_info)]
+    pub stats_account_state: Stats,
+}
+
 pub fn user_account_close(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     msg!("Instruction: UserAccountClose");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let user_wallet_account_info = next_account_info(iter)?;
-    let user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let system_program_account_info = next_account_info(iter)?;
-
-    let user_account_state = UserAccount::try_from_account_info(user_account_info)?;
-    let mut stats_account_state = Stats::try_from_account_info(stats_account_info)?;
-    // check accounts
-    check_is_signer(user_wallet_account_info)?;
-    check_is_writable(user_wallet_account_info)?;
-
-    check_is_writable(user_account_info)?;
-    check_pda_cannonical_bump(user_account_info, &[b"UserAccount".as_ref(), user_account_state.authority.as_ref()])?;
-
-    check_is_writable(stats_account_info)?;
-    check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    check_pubkey_eq(system_program_account_info, &system_program::ID)?;
-    // check authority
-    if &user_account_state.authority != user_wallet_account_info.key {
-        msg!(
-            "Expect account {} to have authority over account {}",
-            user_wallet_account_info.key,
-            user_account_info.key
-        );
-        return Err(ProgramError::from(BettingError::NoAuthority));
-    }
+    let UserAccountCloseAccounts {
+        user_wallet_account_info,
+        user_account_info,
+        stats_account_info,
+        user_account_state,
+        mut stats_account_state,
+        username_account_info,
+        ..
+    } = UserAccountCloseAccounts::try_from_accounts(accounts)?;
     // check lamports
     if user_account_state.current_lamports > 0 {
         msg!("Lamports must be withdrew before account {} can be closed", user_account_info.key);
@@ -61,14 +71,18 @@ pub fn user_account_close(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
         msg!("Account {} still has active VRF results left", user_account_info.key);
         return Err(ProgramError::from(BettingError::UserAccountNotSettled));
     }
+    // release username
+    if let (Some(username), Some(username_account_info)) = (user_account_state.username(), username_account_info) {
+        UsernameRecord::release(username, username_account_info, user_account_info, user_wallet_account_info)?;
+    }
     // update user wallet account
-    **user_wallet_account_info.lamports.borrow_mut() = user_wallet_account_info.lamports().checked_add(user_account_info.lamports()).unwrap();
+    **user_wallet_account_info.lamports.borrow_mut() = user_wallet_account_info.lamports().try_add(user_account_info.lamports())?;
     // close user account
     user_account_info.data.borrow_mut().fill(0);
     user_account_info.realloc(0, false)?;
     **user_account_info.lamports.borrow_mut() = 0;
     // update stats account
-    stats_account_state.total_users -= 1;
+    stats_account_state.total_users = stats_account_state.total_users.try_sub(1)?;
     stats_account_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
 
     Ok(())
@@ -89,7 +103,7 @@ mod test {
 
     use crate::{
         instructions::BettingInstruction,
-        state::{stats::Stats, user_account::UserAccount},
+        state::{stats::Stats, user_account::UserAccount, username::UsernameRecord},
     };
 
     #[tokio::test]
@@ -121,6 +135,19 @@ mod test {
             },
         );
 
+        let (username_pda, _) = UsernameRecord::find_pda("Username");
+        let username_data = UsernameRecord::new(user_account_pda, user.pubkey()).try_to_vec().unwrap();
+        let username_data_len = username_data.len();
+        program_test.add_account(
+            username_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(username_data_len),
+                data: username_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
         let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
         let mut stats_state = Stats::new();
         stats_state.total_users = 1;
@@ -146,6 +173,7 @@ mod test {
                     AccountMeta::new(user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(username_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -155,11 +183,13 @@ mod test {
 
         // the user account should be closed
         assert!(banks_client.get_account(user_account_pda).await.unwrap().is_none());
+        // the username should be released
+        assert!(banks_client.get_account(username_pda).await.unwrap().is_none());
         // the rent should be returned to the user wallet account
         let user_wallet_account = banks_client.get_account(user.pubkey()).await.unwrap().unwrap();
         assert_eq!(
             user_wallet_account.lamports,
-            LAMPORTS_PER_SOL + Rent::default().minimum_balance(user_account_data_len)
+            LAMPORTS_PER_SOL + Rent::default().minimum_balance(user_account_data_len) + Rent::default().minimum_balance(username_data_len)
         );
         // the stats account should be updated
         let stats_account_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
@@ -476,4 +506,80 @@ mod test {
         let stats_account_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
         assert_eq!(stats_account_state.total_users, 0);
     }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(4)")]
+    async fn test_user_account_close_err_username_held_by_another_user() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let user_account_data = UserAccount::new(user.pubkey(), None, Some("Username".to_string())).try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        // the registry record of the name belongs to another user account
+        let (username_pda, _) = UsernameRecord::find_pda("Username");
+        let username_data = UsernameRecord::new(Pubkey::new_unique(), Pubkey::new_unique()).try_to_vec().unwrap();
+        let username_data_len = username_data.len();
+        program_test.add_account(
+            username_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(username_data_len),
+                data: username_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountClose,
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(username_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
 }
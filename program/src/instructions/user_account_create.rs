@@ -2,19 +2,15 @@ use std::convert::TryInto;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program::invoke_signed,
-    pubkey::Pubkey,
-    rent::Rent,
-    system_instruction, system_program,
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed, pubkey::Pubkey, rent::Rent, system_instruction, system_program,
     sysvar::Sysvar,
 };
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
-    state::{stats::Stats, user_account::UserAccount, BettingAccount},
+    error::BettingError,
+    math::CheckedMath,
+    state::{referral::Referral, stats::Stats, user_account::UserAccount, username::UsernameRecord},
 };
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
@@ -23,30 +19,83 @@ pub struct UserAccountCreateArgs {
     pub referral: Option<Pubkey>,
 }
 
+#[derive(Accounts)]
+#[accounts(args = UserAccountCreateArgs)]
+pub struct UserAccountCreateAccounts<'a, 'info> {
+    #[account(signer, writable, name = "user_wallet_account", desc = "User Wallet Account")]
+    pub user_wallet_account_info: &'a AccountInfo<'info>,
+    // users cannot refer themselves
+    #[account(
+        writable,
+        seeds = [b"UserAccount", user_wallet_account_info.key],
+        bump = user_account_bump,
+        constraint = args
+            .referral
+            .filter(|referral| referral == user_account_info.key || referral == user_wallet_account_info.key)
+            .is_none()
+            @ BettingError::InvalidReferral,
+        name = "user_account",
+        desc = "User Betting Account"
+    )]
+    pub user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_account_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    // the username account is only passed when a username is claimed
+    #[account(
+        writable,
+        optional = args.username.is_some(),
+        name = "username_pda",
+        desc = "Username registry PDA, required when a username is set"
+    )]
+    pub username_account_info: Option<&'a AccountInfo<'info>>,
+    // the referrer accounts are only passed for referred users, the referral must be the user account of a registered
+    // user who is not referred by this user
+    #[account(
+        optional = let Some(referral) = args.referral,
+        address = referral,
+        seeds = [b"UserAccount", referrer_user_account_state.authority],
+        stored_bump = referrer_user_account_state.bump,
+        constraint = referrer_user_account_state.referral() != Some(*user_account_info.key)
+            && referrer_user_account_state.referral() != Some(*user_wallet_account_info.key) @ BettingError::InvalidReferral,
+        name = "referrer_user_account",
+        desc = "User Betting Account of the referrer, required when a referral is set"
+    )]
+    pub referrer_user_account_info: Option<&'a AccountInfo<'info>>,
+    #[account(
+        writable,
+        optional = args.referral.is_some(),
+        name = "referral_pda",
+        desc = "Referral PDA of the referrer, required when a referral is set"
+    )]
+    pub referral_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(stats_account_info)]
+    pub stats_account_state: Stats,
+    #[state(referrer_user_account_info)]
+    pub referrer_user_account_state: Option<UserAccount>,
+    pub user_account_bump: u8,
+}
+
 pub fn user_account_create(program_id: &Pubkey, accounts: &[AccountInfo], args: UserAccountCreateArgs) -> ProgramResult {
     msg!("Instruction: UserAccountCreate");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let user_wallet_account_info = next_account_info(iter)?;
-    let user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let system_program_account_info = next_account_info(iter)?;
-
-    let mut stats_account_state = Stats::try_from_account_info(stats_account_info)?;
-    // check accounts
-    check_is_signer(user_wallet_account_info)?;
-    check_is_writable(user_wallet_account_info)?;
-
-    check_is_writable(user_account_info)?;
-    let user_account_bump = check_pda_cannonical_bump(user_account_info, &[b"UserAccount".as_ref(), user_wallet_account_info.key.as_ref()])?;
-
-    check_is_writable(stats_account_info)?;
-    check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    check_pubkey_eq(system_program_account_info, &system_program::ID)?;
+    let UserAccountCreateAccounts {
+        user_wallet_account_info,
+        user_account_info,
+        stats_account_info,
+        mut stats_account_state,
+        user_account_bump,
+        username_account_info,
+        referral_account_info,
+        ..
+    } = UserAccountCreateAccounts::try_from_accounts(accounts, &args)?;
+    // check username
+    if let Some(username) = &args.username {
+        UserAccount::check_username(username)?;
+    }
     // create user account
-    let user_account_state = UserAccount::new(*user_wallet_account_info.key, args.referral, args.username);
+    let user_account_state = UserAccount::new_with_bump(*user_wallet_account_info.key, args.referral, args.username, user_account_bump);
     let user_account_data = user_account_state.try_to_vec()?;
     let user_account_date_len = user_account_data.len();
     let min_rent = Rent::get()?.minimum_balance(user_account_date_len);
@@ -64,8 +113,18 @@ pub fn user_account_create(program_id: &Pubkey, accounts: &[AccountInfo], args:
         &[user_account_signer_seeds],
     )?;
     user_account_info.data.borrow_mut().copy_from_slice(&user_account_data);
+    // claim username
+    if let (Some(username), Some(username_account_info)) = (user_account_state.username(), username_account_info) {
+        UsernameRecord::claim(username, username_account_info, user_account_info, user_wallet_account_info)?;
+    }
+    // update referral account
+    if let (Some(referral), Some(referral_account_info)) = (user_account_state.referral(), referral_account_info) {
+        let mut referral_state = Referral::load_or_create(&referral, referral_account_info, user_wallet_account_info)?;
+        referral_state.referred_users = referral_state.referred_users.try_add(1)?;
+        referral_state.serialize(&mut &mut referral_account_info.data.borrow_mut()[..])?;
+    }
     // update stats account
-    stats_account_state.total_users += 1;
+    stats_account_state.total_users = stats_account_state.total_users.try_add(1)?;
     stats_account_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
 
     Ok(())
@@ -86,7 +145,7 @@ mod test {
 
     use crate::{
         instructions::BettingInstruction,
-        state::{stats::Stats, user_account::UserAccount, StateAccountType},
+        state::{referral::Referral, stats::Stats, user_account::UserAccount, username::UsernameRecord, StateAccountType},
     };
 
     use super::UserAccountCreateArgs;
@@ -105,9 +164,24 @@ mod test {
             },
         );
 
-        let referral = Pubkey::new_unique();
+        let referrer = Pubkey::new_unique();
+        let (referral, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), referrer.as_ref()], &program_id);
+        let referrer_user_account_data = UserAccount::new(referrer, None, None).try_to_vec().unwrap();
+        let referrer_user_account_data_len = referrer_user_account_data.len();
+        program_test.add_account(
+            referral,
+            Account {
+                lamports: Rent::default().minimum_balance(referrer_user_account_data_len),
+                data: referrer_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
         let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
 
+        let (username_pda, _) = UsernameRecord::find_pda("Username");
+
         let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
         let stats_state = Stats::new();
         let stats_data = stats_state.try_to_vec().unwrap();
@@ -137,6 +211,9 @@ mod test {
                     AccountMeta::new(user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(username_pda, false),
+                    AccountMeta::new_readonly(referral, false),
+                    AccountMeta::new(referral_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -146,14 +223,296 @@ mod test {
 
         // the user account should be created
         let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
-        assert_eq!(user_account_state.account_type, StateAccountType::UserAccount);
+        assert_eq!(user_account_state.account_type, StateAccountType::UserAccount as u8);
         assert_eq!(user_account_state.authority, user.pubkey());
         assert_eq!(user_account_state.total_bets, 0);
         assert_eq!(user_account_state.current_lamports, 0);
         assert_eq!(user_account_state.active_vrf_results, 0);
-        assert_eq!(user_account_state.referral, Some(referral));
-        assert_eq!(user_account_state.username, Some("Username".to_string()));
+        assert_eq!(user_account_state.referral(), Some(referral));
+        assert_eq!(user_account_state.username(), Some("Username"));
         assert_eq!(user_account_state.lamports_deposited, 0);
         assert_eq!(user_account_state.lamports_withdrew, 0);
+        // the username should be claimed
+        let username_state: UsernameRecord = banks_client.get_account_data_with_borsh(username_pda).await.unwrap();
+        assert_eq!(username_state.account_type, StateAccountType::Username);
+        assert_eq!(username_state.user_account, user_account_pda);
+        assert_eq!(username_state.authority, user.pubkey());
+        // the referral account should be created
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.account_type, StateAccountType::Referral);
+        assert_eq!(referral_state.referrer, referral);
+        assert_eq!(referral_state.referred_users, 1);
+    }
+
+    #[tokio::test]
+    async fn test_user_account_create_success_username_pda_prefunded() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+
+        // lamports sent to the registry PDA of the name before it is claimed
+        let (username_pda, _) = UsernameRecord::find_pda("Username");
+        program_test.add_account(
+            username_pda,
+            Account {
+                lamports: 1,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let stats_state = Stats::new();
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountCreate {
+                    args: UserAccountCreateArgs {
+                        username: Some("Username".to_string()),
+                        referral: None,
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(username_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the username should still be claimed, the PDA being topped up to its rent
+        let username_state: UsernameRecord = banks_client.get_account_data_with_borsh(username_pda).await.unwrap();
+        assert_eq!(username_state.user_account, user_account_pda);
+        let username_account = banks_client.get_account(username_pda).await.unwrap().unwrap();
+        assert_eq!(username_account.owner, program_id);
+        assert_eq!(
+            username_account.lamports,
+            Rent::default().minimum_balance(UsernameRecord::new(user_account_pda, user.pubkey()).try_to_vec().unwrap().len())
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(16)")]
+    async fn test_user_account_create_err_username_taken() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+
+        // the name is held by another user regardless of case
+        let (username_pda, _) = UsernameRecord::find_pda("Username");
+        let username_data = UsernameRecord::new(Pubkey::new_unique(), Pubkey::new_unique()).try_to_vec().unwrap();
+        let username_data_len = username_data.len();
+        program_test.add_account(
+            username_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(username_data_len),
+                data: username_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let stats_state = Stats::new();
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountCreate {
+                    args: UserAccountCreateArgs {
+                        username: Some("USERNAME".to_string()),
+                        referral: None,
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(username_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(18)")]
+    async fn test_user_account_create_err_self_referral() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), user_account_pda.as_ref()], &program_id);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let stats_state = Stats::new();
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountCreate {
+                    args: UserAccountCreateArgs {
+                        username: None,
+                        referral: Some(user_account_pda),
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new_readonly(user_account_pda, false),
+                    AccountMeta::new(referral_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(18)")]
+    async fn test_user_account_create_err_circular_referral() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+
+        // the referrer was referred by this user before the user account existed
+        let referrer = Pubkey::new_unique();
+        let (referral, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), referrer.as_ref()], &program_id);
+        let referrer_user_account_data = UserAccount::new(referrer, Some(user_account_pda), None).try_to_vec().unwrap();
+        let referrer_user_account_data_len = referrer_user_account_data.len();
+        program_test.add_account(
+            referral,
+            Account {
+                lamports: Rent::default().minimum_balance(referrer_user_account_data_len),
+                data: referrer_user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let stats_state = Stats::new();
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountCreate {
+                    args: UserAccountCreateArgs {
+                        username: None,
+                        referral: Some(referral),
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new_readonly(referral, false),
+                    AccountMeta::new(referral_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
     }
 }
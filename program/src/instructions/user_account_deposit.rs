@@ -1,56 +1,72 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program::invoke,
-    pubkey::Pubkey,
-    system_instruction, system_program,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, program::invoke, pubkey::Pubkey, system_instruction, system_program,
+    sysvar::Sysvar,
 };
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
-    state::{stats::Stats, user_account::UserAccount, BettingAccount},
+    math::CheckedMath,
+    state::{stats::Stats, user_account::UserAccount, user_limits::UserLimits},
 };
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct UserAccountDepositArgs {
     pub lamports: u64,
 }
+
+#[derive(Accounts)]
+pub struct UserAccountDepositAccounts<'a, 'info> {
+    #[account(signer, writable, name = "depositor", desc = "The account to transfer lamports from")]
+    pub depositor_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", user_account_state.authority],
+        stored_bump = user_account_state.bump,
+        name = "user_account",
+        desc = "User Betting Account"
+    )]
+    pub user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_account_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "user_limits_pda", desc = "User limits PDA Account, may be uninitialized")]
+    pub user_limits_account_info: &'a AccountInfo<'info>,
+    #[state(user_account_info)]
+    pub user_account_state: UserAccount,
+    #[state(stats_account_info)]
+    pub stats_account_state: Stats,
+}
+
 pub fn user_account_deposit(_program_id: &Pubkey, accounts: &[AccountInfo], args: UserAccountDepositArgs) -> ProgramResult {
     msg!("Instruction: UserAccountDeposit");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let depositor_account_info = next_account_info(iter)?;
-    let user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let system_program_account_info = next_account_info(iter)?;
-
-    let mut user_account_state = UserAccount::try_from_account_info(user_account_info)?;
-    let mut stats_account_state = Stats::try_from_account_info(stats_account_info)?;
-    // check accounts
-    check_is_signer(depositor_account_info)?;
-    check_is_writable(depositor_account_info)?;
-
-    check_is_writable(user_account_info)?;
-    check_pda_cannonical_bump(user_account_info, &[b"UserAccount".as_ref(), user_account_state.authority.as_ref()])?;
-
-    check_is_writable(stats_account_info)?;
-    check_pda_cannonical_bump(stats_account_info, &[b"Stats".as_ref()])?;
-
-    check_pubkey_eq(system_program_account_info, &system_program::ID)?;
+    let UserAccountDepositAccounts {
+        depositor_account_info,
+        user_account_info,
+        stats_account_info,
+        user_limits_account_info,
+        mut user_account_state,
+        mut stats_account_state,
+        ..
+    } = UserAccountDepositAccounts::try_from_accounts(accounts)?;
+    // check responsible gambling limits
+    if let Some(mut user_limits_state) = UserLimits::load(user_account_info.key, user_limits_account_info)? {
+        user_limits_state.record_deposit(Clock::get()?.unix_timestamp, args.lamports)?;
+        user_limits_state.serialize(&mut &mut user_limits_account_info.data.borrow_mut()[..])?;
+    }
 
     // transfer lamports
     let lamports_transfer_ix = system_instruction::transfer(depositor_account_info.key, stats_account_info.key, args.lamports);
     invoke(&lamports_transfer_ix, &[depositor_account_info.clone(), stats_account_info.clone()])?;
 
     // update user account
-    user_account_state.current_lamports += args.lamports;
-    user_account_state.lamports_deposited += args.lamports;
+    user_account_state.current_lamports = user_account_state.current_lamports.try_add(args.lamports)?;
+    user_account_state.lamports_deposited = user_account_state.lamports_deposited.try_add(args.lamports)?;
     user_account_state.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
 
     // update stats account
-    stats_account_state.total_lamports_deposited += args.lamports;
+    stats_account_state.total_lamports_deposited = stats_account_state.total_lamports_deposited.try_add(args.lamports)?;
     stats_account_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
 
     Ok(())
@@ -71,7 +87,7 @@ mod test {
 
     use crate::{
         instructions::BettingInstruction,
-        state::{stats::Stats, user_account::UserAccount},
+        state::{stats::Stats, user_account::UserAccount, user_limits::UserLimits},
     };
 
     use super::UserAccountDepositArgs;
@@ -120,6 +136,8 @@ mod test {
             },
         );
 
+        let (user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), user_account_pda.as_ref()], &program_id);
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
             &[Instruction::new_with_borsh(
@@ -132,6 +150,7 @@ mod test {
                     AccountMeta::new(user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(user_limits_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -153,4 +172,85 @@ mod test {
         let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
         assert_eq!(stats_state.total_lamports_deposited, 10000);
     }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(20)")]
+    async fn test_user_account_deposit_err_limit_exceeded() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let referral = Pubkey::new_unique();
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let user_account_state = UserAccount::new(user.pubkey(), Some(referral), Some("Username".to_string()));
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(stats_data_len),
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), user_account_pda.as_ref()], &program_id);
+        let mut user_limits_state = UserLimits::new(user_account_pda);
+        user_limits_state.limits.daily_deposit_cap = 5000;
+        let user_limits_data = user_limits_state.try_to_vec().unwrap();
+        let user_limits_data_len = user_limits_data.len();
+        program_test.add_account(
+            user_limits_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_limits_data_len),
+                data: user_limits_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountDeposit {
+                    args: UserAccountDepositArgs { lamports: 10000 },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(user_limits_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
 }
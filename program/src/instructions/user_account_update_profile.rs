@@ -0,0 +1,337 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::cell::RefMut;
+
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey, system_program};
+use vrf_betting_derive::Accounts;
+
+use crate::state::{user_account::UserAccount, username::UsernameRecord};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct UserAccountUpdateProfileArgs {
+    pub username: Option<String>,
+    pub avatar_uri: Option<String>,
+    pub display_flags: u8,
+}
+
+/// The profile fields are stored at their maximum length, so an update never resizes the user account nor moves its rent,
+/// the user only pays for the registry PDA of a new username.
+#[derive(Accounts)]
+#[accounts(args = UserAccountUpdateProfileArgs)]
+pub struct UserAccountUpdateProfileAccounts<'a, 'info> {
+    #[account(
+        signer,
+        writable,
+        authority = user_account_state.authority,
+        name = "user_wallet_account",
+        desc = "User Wallet Account"
+    )]
+    pub user_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", user_account_state.authority],
+        stored_bump = user_account_state.bump,
+        name = "user_account",
+        desc = "User Betting Account"
+    )]
+    pub user_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        optional = username_changes(&user_account_state, args).0,
+        name = "old_username_pda",
+        desc = "Registry PDA of the current username, required when it is released"
+    )]
+    pub old_username_account_info: Option<&'a AccountInfo<'info>>,
+    #[account(
+        writable,
+        optional = username_changes(&user_account_state, args).1,
+        name = "new_username_pda",
+        desc = "Registry PDA of the new username, required when it is claimed"
+    )]
+    pub new_username_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(user_account_info)]
+    pub user_account_state: RefMut<'a, UserAccount>,
+}
+
+/// Whether the current username is released and whether a new one is claimed, the username accounts are only passed
+/// when the username changes
+fn username_changes(user_account_state: &UserAccount, args: &UserAccountUpdateProfileArgs) -> (bool, bool) {
+    let old_username = user_account_state.username().filter(|username| UserAccount::check_username(username).is_ok());
+    if old_username.map(UsernameRecord::seed) != args.username.as_deref().map(UsernameRecord::seed) {
+        (old_username.is_some(), args.username.is_some())
+    } else {
+        (false, false)
+    }
+}
+
+pub fn user_account_update_profile(_program_id: &Pubkey, accounts: &[AccountInfo], args: UserAccountUpdateProfileArgs) -> ProgramResult {
+    msg!("Instruction: UserAccountUpdateProfile");
+    // get accounts
+    let UserAccountUpdateProfileAccounts {
+        user_wallet_account_info,
+        user_account_info,
+        mut user_account_state,
+        old_username_account_info,
+        new_username_account_info,
+        ..
+    } = UserAccountUpdateProfileAccounts::try_from_accounts(accounts, &args)?;
+    // check profile
+    if let Some(username) = &args.username {
+        UserAccount::check_username(username)?;
+    }
+    if let Some(avatar_uri) = &args.avatar_uri {
+        UserAccount::check_avatar_uri(avatar_uri)?;
+    }
+    UserAccount::check_display_flags(args.display_flags)?;
+    // release the old username and claim the new one
+    if let (Some(username), Some(old_username_account_info)) = (user_account_state.username(), old_username_account_info) {
+        UsernameRecord::release(username, old_username_account_info, user_account_info, user_wallet_account_info)?;
+    }
+    if let (Some(username), Some(new_username_account_info)) = (&args.username, new_username_account_info) {
+        UsernameRecord::claim(username, new_username_account_info, user_account_info, user_wallet_account_info)?;
+    }
+    // update user account, the profile fields have a fixed size and are written in place
+    user_account_state.set_username(args.username.as_deref());
+    user_account_state.set_avatar_uri(args.avatar_uri.as_deref());
+    user_account_state.display_flags = args.display_flags;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            user_account::{UserAccount, DISPLAY_FLAG_HIDE_FROM_LEADERBOARD},
+            username::UsernameRecord,
+        },
+    };
+
+    use super::UserAccountUpdateProfileArgs;
+
+    #[tokio::test]
+    async fn test_user_account_update_profile_success_longer_profile() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let user_account_state = UserAccount::new(user.pubkey(), None, Some("Username".to_string()));
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        // the old username was set before the registry was introduced
+        let (old_username_pda, _) = UsernameRecord::find_pda("Username");
+        let (new_username_pda, _) = UsernameRecord::find_pda("LongerUsername");
+        let username_data_len = UsernameRecord::new(user_account_pda, user.pubkey()).try_to_vec().unwrap().len();
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountUpdateProfile {
+                    args: UserAccountUpdateProfileArgs {
+                        username: Some("LongerUsername".to_string()),
+                        avatar_uri: Some("https://example.com/avatar.png".to_string()),
+                        display_flags: DISPLAY_FLAG_HIDE_FROM_LEADERBOARD,
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(old_username_pda, false),
+                    AccountMeta::new(new_username_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the user account should be updated
+        let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
+        assert_eq!(user_account_state.username(), Some("LongerUsername"));
+        assert_eq!(user_account_state.avatar_uri(), Some("https://example.com/avatar.png"));
+        assert_eq!(user_account_state.display_flags, DISPLAY_FLAG_HIDE_FROM_LEADERBOARD);
+        // the user account should keep its size and rent
+        let user_account = banks_client.get_account(user_account_pda).await.unwrap().unwrap();
+        assert_eq!(user_account.data.len(), user_account_data_len);
+        assert_eq!(user_account.lamports, Rent::default().minimum_balance(user_account_data_len));
+        // the new username should be claimed
+        let username_state: UsernameRecord = banks_client.get_account_data_with_borsh(new_username_pda).await.unwrap();
+        assert_eq!(username_state.user_account, user_account_pda);
+        assert_eq!(username_state.authority, user.pubkey());
+        // only the rent of the username record should be paid by the user
+        let user_wallet_account = banks_client.get_account(user.pubkey()).await.unwrap().unwrap();
+        assert_eq!(
+            user_wallet_account.lamports,
+            LAMPORTS_PER_SOL - Rent::default().minimum_balance(username_data_len)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_user_account_update_profile_success_shorter_profile() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let mut user_account_state = UserAccount::new(user.pubkey(), None, Some("LongerUsername".to_string()));
+        user_account_state.set_avatar_uri(Some("https://example.com/avatar.png"));
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (old_username_pda, _) = UsernameRecord::find_pda("LongerUsername");
+        let username_data = UsernameRecord::new(user_account_pda, user.pubkey()).try_to_vec().unwrap();
+        let username_data_len = username_data.len();
+        program_test.add_account(
+            old_username_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(username_data_len),
+                data: username_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+        let (new_username_pda, _) = UsernameRecord::find_pda("Name");
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountUpdateProfile {
+                    args: UserAccountUpdateProfileArgs {
+                        username: Some("Name".to_string()),
+                        avatar_uri: None,
+                        display_flags: 0,
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(old_username_pda, false),
+                    AccountMeta::new(new_username_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the user account should be updated
+        let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
+        assert_eq!(user_account_state.username(), Some("Name"));
+        // the old username should be released and the new one claimed
+        assert!(banks_client.get_account(old_username_pda).await.unwrap().is_none());
+        let username_state: UsernameRecord = banks_client.get_account_data_with_borsh(new_username_pda).await.unwrap();
+        assert_eq!(username_state.user_account, user_account_pda);
+        assert_eq!(user_account_state.avatar_uri(), None);
+        // the user account should keep its size and rent, the username rent is moved to the new record
+        let user_account = banks_client.get_account(user_account_pda).await.unwrap().unwrap();
+        assert_eq!(user_account.data.len(), user_account_data_len);
+        assert_eq!(user_account.lamports, Rent::default().minimum_balance(user_account_data_len));
+        let user_wallet_account = banks_client.get_account(user.pubkey()).await.unwrap().unwrap();
+        assert_eq!(user_wallet_account.lamports, LAMPORTS_PER_SOL);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(14)")]
+    async fn test_user_account_update_profile_err_invalid_username() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let user_account_state = UserAccount::new(user.pubkey(), None, Some("Username".to_string()));
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountUpdateProfile {
+                    args: UserAccountUpdateProfileArgs {
+                        username: Some("not a valid name!".to_string()),
+                        avatar_uri: None,
+                        display_flags: 0,
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+}
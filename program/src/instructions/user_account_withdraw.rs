@@ -1,89 +1,121 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
-    constants::OPERATOR_PUBKEY,
-    error::BettingError,
-    state::{stats::Stats, user_account::UserAccount, BettingAccount},
+    constants::{OPERATOR_PUBKEY, REFERRAL_SHARE},
+    math::{mul_div, CheckedMath},
+    state::{
+        referral::{Referral, ReferralConfig},
+        stats::Stats,
+        user_account::UserAccount,
+        BettingAccount,
+    },
 };
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
 pub struct UserAccountWithdrawArgs {
     pub lamports: u64,
 }
+
+#[derive(Accounts)]
+pub struct UserAccountWithdrawAccounts<'a, 'info> {
+    #[account(
+        signer,
+        writable,
+        authority = user_account_state.authority,
+        name = "user_wallet_account",
+        desc = "User Wallet Account"
+    )]
+    pub user_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", user_account_state.authority],
+        stored_bump = user_account_state.bump,
+        name = "user_account",
+        desc = "User Betting Account"
+    )]
+    pub user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_account_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    // the referral accounts are only passed for referred users
+    #[account(
+        writable,
+        optional = let Some(referral) = user_account_state.referral(),
+        seeds = [b"Referral", referral],
+        name = "referral_pda",
+        desc = "Referral PDA of the user's referrer, required when the user has a referral"
+    )]
+    pub referral_account_info: Option<&'a AccountInfo<'info>>,
+    #[account(
+        optional = user_account_state.referral().is_some(),
+        seeds = [b"ReferralConfig"],
+        name = "referral_config_pda",
+        desc = "Referral config PDA Account, required when the user has a referral"
+    )]
+    pub referral_config_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(user_account_info)]
+    pub user_account_state: UserAccount,
+    #[state(stats_account_info)]
+    pub stats_account_state: Stats,
+}
+
 pub fn user_account_withdraw(_program_id: &Pubkey, accounts: &[AccountInfo], args: UserAccountWithdrawArgs) -> ProgramResult {
     msg!("Instruction: UserAccountWithdraw");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let user_wallet_account_info = next_account_info(iter)?;
-    let user_account_info = next_account_info(iter)?;
-    let stats_account_info = next_account_info(iter)?;
-    let op_account_info = next_account_info(iter)?;
-
-    let mut user_account_state = UserAccount::try_from_account_info(user_account_info)?;
-    let mut stats_account_state = Stats::try_from_account_info(stats_account_info)?;
-    // check accounts
-    check_is_signer(user_wallet_account_info)?;
-    check_is_writable(user_wallet_account_info)?;
-
-    check_is_writable(user_account_info)?;
-    check_pda_cannonical_bump(user_account_info, &[b"UserAccount".as_ref(), user_account_state.authority.as_ref()])?;
-
-    check_pubkey_eq(op_account_info, &OPERATOR_PUBKEY)?;
-    // check authority
-    if user_wallet_account_info.key != &user_account_state.authority {
-        msg!(
-            "Expect account {} to have authority over account {}",
-            user_wallet_account_info.key,
-            user_account_info.key
-        );
-        return Err(ProgramError::from(BettingError::NoAuthority));
-    }
+    let UserAccountWithdrawAccounts {
+        user_wallet_account_info,
+        user_account_info,
+        stats_account_info,
+        op_account_info,
+        mut user_account_state,
+        mut stats_account_state,
+        referral_account_info,
+        referral_config_account_info,
+    } = UserAccountWithdrawAccounts::try_from_accounts(accounts)?;
     // check withdraw amount
     if user_account_state.current_lamports < args.lamports {
         msg!("Account {} does not have enough lamports", user_account_info.key);
         return Err(ProgramError::InsufficientFunds);
     }
-    // calculate transfer amounts
-    let profit_share = user_account_state.get_profit_share(args.lamports);
-    let user_amount = args.lamports - profit_share;
-    let referral_amount = profit_share / 2;
-    let op_amount = profit_share - referral_amount;
+    // calculate transfer amounts, the withdrawal raises the profit high-water mark
+    let profit_share = user_account_state.record_withdraw(args.lamports)?;
+    let user_amount = args.lamports.try_sub(profit_share)?;
+    // if there's a referral for the user, part of the profit share is credited to the referral account, a referrer
+    // without referral PDA has no accrued rewards and the operator keeps the whole profit share
+    let referral_state = referral_account_info.map(Referral::load).transpose()?.flatten();
+    let referral_amount = match (referral_account_info, referral_state, referral_config_account_info) {
+        (Some(referral_account_info), Some(mut referral_state), Some(referral_config_account_info)) => {
+            // the default share applies until the operator sets the referral tiers
+            let referral_share = if referral_config_account_info.data_is_empty() {
+                REFERRAL_SHARE
+            } else {
+                ReferralConfig::try_from_account_info(referral_config_account_info)?.get_share(referral_state.referred_volume)
+            };
+            let referral_amount = mul_div(profit_share, referral_share, 10000)?;
+            // update referral account state
+            referral_state.total_lamports_earned = referral_state.total_lamports_earned.try_add(referral_amount)?;
+            referral_state.unclaimed_lamports = referral_state.unclaimed_lamports.try_add(referral_amount)?;
+            referral_state.serialize(&mut &mut referral_account_info.data.borrow_mut()[..])?;
+            referral_amount
+        }
+        _ => 0,
+    };
+    let op_amount = profit_share.try_sub(referral_amount)?;
+    let stats_amount = args.lamports.try_sub(referral_amount)?;
     // transfer lamports to user wallet account
-    **user_wallet_account_info.lamports.borrow_mut() = user_wallet_account_info.lamports().checked_add(user_amount).unwrap();
+    **user_wallet_account_info.lamports.borrow_mut() = user_wallet_account_info.lamports().try_add(user_amount)?;
     // update user account state
-    user_account_state.lamports_withdrew += args.lamports;
-    user_account_state.current_lamports -= args.lamports;
     user_account_state.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
-    // update stats account
-    stats_account_state.total_lamports_withdrew += args.lamports;
+    // update stats account, the referral amount stays in the stats account until it is claimed
+    stats_account_state.total_lamports_withdrew = stats_account_state.total_lamports_withdrew.try_add(stats_amount)?;
     stats_account_state.serialize(&mut &mut stats_account_info.data.borrow_mut()[..])?;
-    **stats_account_info.lamports.borrow_mut() = stats_account_info.lamports().checked_sub(args.lamports).unwrap();
+    **stats_account_info.lamports.borrow_mut() = stats_account_info.lamports().try_sub(stats_amount)?;
 
     // transfer lamports to op
-    **op_account_info.lamports.borrow_mut() = op_account_info.lamports().checked_add(op_amount).unwrap();
-
-    // if there's a referral for the user
-    if let Some(referral) = user_account_state.referral {
-        // get referral account
-        let referral_account_info = next_account_info(iter)?;
-        // check referral account
-        check_is_writable(referral_account_info)?;
-        check_pubkey_eq(referral_account_info, &referral)?;
-        // transfer lamports
-        **referral_account_info.lamports.borrow_mut() = referral_account_info.lamports().checked_add(referral_amount).unwrap();
-    } else {
-        // no referral, profit share goes to operator
-        **op_account_info.lamports.borrow_mut() = op_account_info.lamports().checked_add(referral_amount).unwrap();
-    }
+    **op_account_info.lamports.borrow_mut() = op_account_info.lamports().try_add(op_amount)?;
 
     Ok(())
 }
@@ -103,7 +135,11 @@ mod test {
     use crate::{
         constants::OPERATOR_PUBKEY,
         instructions::BettingInstruction,
-        state::{stats::Stats, user_account::UserAccount},
+        state::{
+            referral::{Referral, ReferralConfig, ReferralTier},
+            stats::Stats,
+            user_account::UserAccount,
+        },
     };
 
     use super::UserAccountWithdrawArgs;
@@ -126,6 +162,118 @@ mod test {
         let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
         let mut user_account_state = UserAccount::new(user.pubkey(), Some(referral), Some("Username".to_string()));
         user_account_state.current_lamports = 20000;
+        user_account_state.betting_pnl = 20000;
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        // let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        program_test.add_account(
+            OPERATOR_PUBKEY,
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let referral_data = Referral::new(referral).try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
+        program_test.add_account(
+            referral_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+        let (referral_config_pda, _) = Pubkey::find_program_address(&[b"ReferralConfig".as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountWithdraw {
+                    args: UserAccountWithdrawArgs { lamports: 10000 },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                    AccountMeta::new(referral_pda, false),
+                    AccountMeta::new_readonly(referral_config_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // lamports should be transferred to the user wallet account
+        let user_wallet_account = banks_client.get_account(user.pubkey()).await.unwrap().unwrap();
+        assert_eq!(user_wallet_account.lamports, LAMPORTS_PER_SOL + 9900);
+        // user account state should be updated
+        let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
+        assert_eq!(user_account_state.current_lamports, 10000);
+        assert_eq!(user_account_state.lamports_withdrew, 10000);
+        // lamports except the referral share should be transferred from the stats account
+        let stats_account = banks_client.get_account(stats_pda).await.unwrap().unwrap();
+        assert_eq!(stats_account.lamports, LAMPORTS_PER_SOL - 9950);
+        // stats account state should be updated
+        let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
+        assert_eq!(stats_state.total_lamports_withdrew, 9950);
+        // profit share should be transferred to the operator account
+        let op_account = banks_client.get_account(OPERATOR_PUBKEY).await.unwrap().unwrap();
+        assert_eq!(op_account.lamports, LAMPORTS_PER_SOL + 50);
+        // profit share should be credited to the referral account
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.total_lamports_earned, 50);
+        assert_eq!(referral_state.unclaimed_lamports, 50);
+    }
+
+    #[tokio::test]
+    async fn test_user_account_withdraw_success_with_referral_tier() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let referral = Pubkey::new_unique();
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let mut user_account_state = UserAccount::new(user.pubkey(), Some(referral), Some("Username".to_string()));
+        user_account_state.current_lamports = 20000;
+        user_account_state.betting_pnl = 20000;
         let user_account_data = user_account_state.try_to_vec().unwrap();
         let user_account_data_len = user_account_data.len();
         program_test.add_account(
@@ -161,10 +309,163 @@ mod test {
             },
         );
 
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let mut referral_state = Referral::new(referral);
+        referral_state.referred_volume = 1000;
+        let referral_data = referral_state.try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
+        program_test.add_account(
+            referral_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+        let (referral_config_pda, _) = Pubkey::find_program_address(&[b"ReferralConfig".as_ref()], &program_id);
+        let referral_config_data = ReferralConfig::new(vec![
+            ReferralTier {
+                min_referred_volume: 0,
+                share: 2000,
+            },
+            ReferralTier {
+                min_referred_volume: 1000,
+                share: 8000,
+            },
+        ])
+        .try_to_vec()
+        .unwrap();
+        let referral_config_data_len = referral_config_data.len();
+        program_test.add_account(
+            referral_config_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_config_data_len),
+                data: referral_config_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserAccountWithdraw {
+                    args: UserAccountWithdrawArgs { lamports: 10000 },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new(user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(OPERATOR_PUBKEY, false),
+                    AccountMeta::new(referral_pda, false),
+                    AccountMeta::new_readonly(referral_config_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // lamports should be transferred to the user wallet account
+        let user_wallet_account = banks_client.get_account(user.pubkey()).await.unwrap().unwrap();
+        assert_eq!(user_wallet_account.lamports, LAMPORTS_PER_SOL + 9900);
+        // user account state should be updated
+        let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
+        assert_eq!(user_account_state.current_lamports, 10000);
+        assert_eq!(user_account_state.lamports_withdrew, 10000);
+        // lamports except the referral share should be transferred from the stats account
+        let stats_account = banks_client.get_account(stats_pda).await.unwrap().unwrap();
+        assert_eq!(stats_account.lamports, LAMPORTS_PER_SOL - 9920);
+        // stats account state should be updated
+        let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
+        assert_eq!(stats_state.total_lamports_withdrew, 9920);
+        // profit share should be transferred to the operator account
+        let op_account = banks_client.get_account(OPERATOR_PUBKEY).await.unwrap().unwrap();
+        assert_eq!(op_account.lamports, LAMPORTS_PER_SOL + 20);
+        // the share of the reached tier should be credited to the referral account
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.total_lamports_earned, 80);
+        assert_eq!(referral_state.unclaimed_lamports, 80);
+    }
+
+    #[tokio::test]
+    async fn test_user_account_withdraw_success_without_referral_pda() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let referral = Pubkey::new_unique();
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let mut user_account_state = UserAccount::new(user.pubkey(), Some(referral), Some("Username".to_string()));
+        user_account_state.current_lamports = 20000;
+        user_account_state.betting_pnl = 20000;
+        let user_account_data = user_account_state.try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
         program_test.add_account(
-            referral,
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        let mut stats_state = Stats::new();
+        stats_state.total_users = 1;
+        let stats_data = stats_state.try_to_vec().unwrap();
+        // let stats_data_len = stats_data.len();
+        program_test.add_account(
+            stats_pda,
             Account {
                 lamports: LAMPORTS_PER_SOL,
+                data: stats_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        program_test.add_account(
+            OPERATOR_PUBKEY,
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        // the user was referred before the referral PDAs were created
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let (referral_config_pda, _) = Pubkey::find_program_address(&[b"ReferralConfig".as_ref()], &program_id);
+        let referral_config_data = ReferralConfig::new(vec![
+            ReferralTier {
+                min_referred_volume: 0,
+                share: 2000,
+            },
+            ReferralTier {
+                min_referred_volume: 1000,
+                share: 8000,
+            },
+        ])
+        .try_to_vec()
+        .unwrap();
+        let referral_config_data_len = referral_config_data.len();
+        program_test.add_account(
+            referral_config_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(referral_config_data_len),
+                data: referral_config_data,
+                owner: program_id,
                 ..Default::default()
             },
         );
@@ -181,7 +482,8 @@ mod test {
                     AccountMeta::new(user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(OPERATOR_PUBKEY, false),
-                    AccountMeta::new(referral, false),
+                    AccountMeta::new(referral_pda, false),
+                    AccountMeta::new_readonly(referral_config_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -196,18 +498,17 @@ mod test {
         let user_account_state: UserAccount = banks_client.get_account_data_with_borsh(user_account_pda).await.unwrap();
         assert_eq!(user_account_state.current_lamports, 10000);
         assert_eq!(user_account_state.lamports_withdrew, 10000);
-        // lamports should be transferred from the stats account
+        // all lamports should be transferred from the stats account
         let stats_account = banks_client.get_account(stats_pda).await.unwrap().unwrap();
         assert_eq!(stats_account.lamports, LAMPORTS_PER_SOL - 10000);
         // stats account state should be updated
         let stats_state: Stats = banks_client.get_account_data_with_borsh(stats_pda).await.unwrap();
         assert_eq!(stats_state.total_lamports_withdrew, 10000);
-        // profit share should be transferred to the operator account
+        // the whole profit share should be transferred to the operator account
         let op_account = banks_client.get_account(OPERATOR_PUBKEY).await.unwrap().unwrap();
-        assert_eq!(op_account.lamports, LAMPORTS_PER_SOL + 50);
-        // profit share should be transferred to the referral wallet account
-        let referral_account = banks_client.get_account(referral).await.unwrap().unwrap();
-        assert_eq!(referral_account.lamports, LAMPORTS_PER_SOL + 50);
+        assert_eq!(op_account.lamports, LAMPORTS_PER_SOL + 100);
+        // the referral PDA should not be created
+        assert!(banks_client.get_account(referral_pda).await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -227,6 +528,7 @@ mod test {
         let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
         let mut user_account_state = UserAccount::new(user.pubkey(), None, Some("Username".to_string()));
         user_account_state.current_lamports = 20000;
+        user_account_state.betting_pnl = 20000;
         let user_account_data = user_account_state.try_to_vec().unwrap();
         let user_account_data_len = user_account_data.len();
         program_test.add_account(
@@ -353,13 +655,19 @@ mod test {
             },
         );
 
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let referral_data = Referral::new(referral).try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
         program_test.add_account(
-            referral,
+            referral_pda,
             Account {
-                lamports: LAMPORTS_PER_SOL,
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
                 ..Default::default()
             },
         );
+        let (referral_config_pda, _) = Pubkey::find_program_address(&[b"ReferralConfig".as_ref()], &program_id);
 
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
@@ -373,7 +681,8 @@ mod test {
                     AccountMeta::new(user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(OPERATOR_PUBKEY, false),
-                    AccountMeta::new(referral, false),
+                    AccountMeta::new(referral_pda, false),
+                    AccountMeta::new_readonly(referral_config_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -397,9 +706,9 @@ mod test {
         // no profit share should be transferred to the operator account
         let op_account = banks_client.get_account(OPERATOR_PUBKEY).await.unwrap().unwrap();
         assert_eq!(op_account.lamports, LAMPORTS_PER_SOL);
-        // no profit share should be transferred to the referral wallet account
-        let referral_account = banks_client.get_account(referral).await.unwrap().unwrap();
-        assert_eq!(referral_account.lamports, LAMPORTS_PER_SOL);
+        // no profit share should be credited to the referral account
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.unclaimed_lamports, 0);
     }
 
     #[tokio::test]
@@ -429,6 +738,7 @@ mod test {
         let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
         let mut user_account_state = UserAccount::new(user.pubkey(), Some(referral), Some("Username".to_string()));
         user_account_state.current_lamports = 20000;
+        user_account_state.betting_pnl = 20000;
         let user_account_data = user_account_state.try_to_vec().unwrap();
         let user_account_data_len = user_account_data.len();
         program_test.add_account(
@@ -464,13 +774,19 @@ mod test {
             },
         );
 
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let referral_data = Referral::new(referral).try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
         program_test.add_account(
-            referral,
+            referral_pda,
             Account {
-                lamports: LAMPORTS_PER_SOL,
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
                 ..Default::default()
             },
         );
+        let (referral_config_pda, _) = Pubkey::find_program_address(&[b"ReferralConfig".as_ref()], &program_id);
 
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
@@ -484,7 +800,8 @@ mod test {
                     AccountMeta::new(user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(OPERATOR_PUBKEY, false),
-                    AccountMeta::new(referral, false),
+                    AccountMeta::new(referral_pda, false),
+                    AccountMeta::new_readonly(referral_config_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -508,9 +825,9 @@ mod test {
         // profit share should be transferred to the operator account
         let op_account = banks_client.get_account(OPERATOR_PUBKEY).await.unwrap().unwrap();
         assert_eq!(op_account.lamports, LAMPORTS_PER_SOL + 50);
-        // profit share should be transferred to the referral wallet account
-        let referral_account = banks_client.get_account(referral).await.unwrap().unwrap();
-        assert_eq!(referral_account.lamports, LAMPORTS_PER_SOL + 50);
+        // profit share should be credited to the referral account
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.unclaimed_lamports, 50);
     }
 
     #[tokio::test]
@@ -532,6 +849,7 @@ mod test {
         let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
         let mut user_account_state = UserAccount::new(user.pubkey(), Some(referral), Some("Username".to_string()));
         user_account_state.current_lamports = 20000;
+        user_account_state.betting_pnl = 20000;
         let user_account_data = user_account_state.try_to_vec().unwrap();
         let user_account_data_len = user_account_data.len();
         program_test.add_account(
@@ -567,13 +885,19 @@ mod test {
             },
         );
 
+        let (referral_pda, _) = Pubkey::find_program_address(&[b"Referral".as_ref(), referral.as_ref()], &program_id);
+        let referral_data = Referral::new(referral).try_to_vec().unwrap();
+        let referral_data_len = referral_data.len();
         program_test.add_account(
-            referral,
+            referral_pda,
             Account {
-                lamports: LAMPORTS_PER_SOL,
+                lamports: Rent::default().minimum_balance(referral_data_len),
+                data: referral_data,
+                owner: program_id,
                 ..Default::default()
             },
         );
+        let (referral_config_pda, _) = Pubkey::find_program_address(&[b"ReferralConfig".as_ref()], &program_id);
 
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
@@ -587,7 +911,8 @@ mod test {
                     AccountMeta::new(user_account_pda, false),
                     AccountMeta::new(stats_pda, false),
                     AccountMeta::new(OPERATOR_PUBKEY, false),
-                    AccountMeta::new(referral, false),
+                    AccountMeta::new(referral_pda, false),
+                    AccountMeta::new_readonly(referral_config_pda, false),
                 ],
             )],
             Some(&payer.pubkey()),
@@ -611,8 +936,8 @@ mod test {
         // profit share should be transferred to the operator account
         let op_account = banks_client.get_account(OPERATOR_PUBKEY).await.unwrap().unwrap();
         assert_eq!(op_account.lamports, LAMPORTS_PER_SOL + 50);
-        // profit share should be transferred to the referral wallet account
-        let referral_account = banks_client.get_account(referral).await.unwrap().unwrap();
-        assert_eq!(referral_account.lamports, LAMPORTS_PER_SOL + 50);
+        // profit share should be credited to the referral account
+        let referral_state: Referral = banks_client.get_account_data_with_borsh(referral_pda).await.unwrap();
+        assert_eq!(referral_state.unclaimed_lamports, 50);
     }
 }
@@ -0,0 +1,144 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg, pubkey::Pubkey, system_program, sysvar::Sysvar};
+use vrf_betting_derive::Accounts;
+
+use crate::state::{
+    user_account::UserAccount,
+    user_limits::{LimitSettings, UserLimits},
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct UserLimitsSetArgs {
+    pub limits: LimitSettings,
+}
+
+#[derive(Accounts)]
+pub struct UserLimitsSetAccounts<'a, 'info> {
+    #[account(
+        signer,
+        writable,
+        authority = user_account_state.authority,
+        name = "user_wallet_account",
+        desc = "User Wallet Account"
+    )]
+    pub user_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        seeds = [b"UserAccount", user_account_state.authority],
+        stored_bump = user_account_state.bump,
+        name = "user_account",
+        desc = "User Betting Account"
+    )]
+    pub user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "user_limits_pda", desc = "User limits PDA Account")]
+    pub user_limits_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[state(user_account_info)]
+    pub user_account_state: UserAccount,
+}
+
+pub fn user_limits_set(_program_id: &Pubkey, accounts: &[AccountInfo], args: UserLimitsSetArgs) -> ProgramResult {
+    msg!("Instruction: UserLimitsSet");
+    // get accounts
+    let UserLimitsSetAccounts {
+        user_wallet_account_info,
+        user_account_info,
+        user_limits_account_info,
+        ..
+    } = UserLimitsSetAccounts::try_from_accounts(accounts)?;
+    // update limits
+    let mut user_limits_state = UserLimits::load_or_create(user_account_info.key, user_limits_account_info, user_wallet_account_info)?;
+    user_limits_state.set_limits(args.limits, Clock::get()?.unix_timestamp);
+    user_limits_state.serialize(&mut &mut user_limits_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            user_account::UserAccount,
+            user_limits::{LimitSettings, UserLimits, NO_LIMIT},
+            StateAccountType,
+        },
+    };
+
+    use super::UserLimitsSetArgs;
+
+    #[tokio::test]
+    async fn test_user_limits_set_success() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let user = Keypair::new();
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let (user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), user.pubkey().as_ref()], &program_id);
+        let user_account_data = UserAccount::new(user.pubkey(), None, None).try_to_vec().unwrap();
+        let user_account_data_len = user_account_data.len();
+        program_test.add_account(
+            user_account_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(user_account_data_len),
+                data: user_account_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::UserLimitsSet {
+                    args: UserLimitsSetArgs {
+                        limits: LimitSettings {
+                            daily_deposit_cap: 10000,
+                            weekly_loss_cap: 50000,
+                            ..LimitSettings::none()
+                        },
+                    },
+                },
+                vec![
+                    AccountMeta::new(user.pubkey(), true),
+                    AccountMeta::new_readonly(user_account_pda, false),
+                    AccountMeta::new(user_limits_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&user, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the limits account should be created with the tightened limits applied
+        let user_limits_state: UserLimits = banks_client.get_account_data_with_borsh(user_limits_pda).await.unwrap();
+        assert_eq!(user_limits_state.account_type, StateAccountType::UserLimits);
+        assert_eq!(user_limits_state.user_account, user_account_pda);
+        assert_eq!(user_limits_state.limits.daily_deposit_cap, 10000);
+        assert_eq!(user_limits_state.limits.weekly_deposit_cap, NO_LIMIT);
+        assert_eq!(user_limits_state.limits.weekly_loss_cap, 50000);
+        assert_eq!(user_limits_state.pending_limits_effective_at, 0);
+    }
+}
@@ -1,54 +1,70 @@
 use borsh::BorshSerialize;
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-    system_program,
-};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, system_program};
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
+    constants::CLOSE_CRANK_TIP_LAMPORTS,
     error::BettingError,
-    state::{user_account::UserAccount, vrf_result::VrfResult, BettingAccount},
+    math::CheckedMath,
+    state::{user_account::UserAccount, vrf_result::VrfResult},
 };
 
+/// Anyone can close a bet its bettor marked for close, the optional cranker is paid a close tip out of the rent and the
+/// bettor gets the rest.
+#[derive(Accounts)]
+pub struct VrfResultCloseAccounts<'a, 'info> {
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        address = bettor_user_account_state.authority,
+        constraint = bettor_wallet_account_info.key == &vrf_result_state.owner @ BettingError::WrongPubkey,
+        name = "bettor",
+        desc = "Bettor wallet account"
+    )]
+    pub bettor_wallet_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", bettor_user_account_state.authority],
+        stored_bump = bettor_user_account_state.bump,
+        name = "bettor_user_account",
+        desc = "Bettor user account"
+    )]
+    pub bettor_user_account_info: &'a AccountInfo<'info>,
+    #[account(address = system_program::ID, name = "system_program", desc = "System Program Account")]
+    pub system_program_account_info: &'a AccountInfo<'info>,
+    #[account(
+        signer,
+        writable,
+        optional = !iter.as_slice().is_empty(),
+        name = "cranker",
+        desc = "Signer closing the bet, paid the close tip out of its rent"
+    )]
+    pub cranker_account_info: Option<&'a AccountInfo<'info>>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+    #[state(bettor_user_account_info)]
+    pub bettor_user_account_state: UserAccount,
+}
+
 pub fn vrf_result_close(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     msg!("Instruction: VrfResultClose");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let vrf_result_account_info = next_account_info(iter)?;
-    let bettor_wallet_account_info = next_account_info(iter)?;
-    let bettor_user_account_info = next_account_info(iter)?;
-    let system_program_account_info = next_account_info(iter)?;
-
-    let vrf_result_state = VrfResult::try_from_account_info(vrf_result_account_info)?;
-    let mut bettor_user_account_state = UserAccount::try_from_account_info(bettor_user_account_info)?;
-    // check accounts
-    check_is_writable(vrf_result_account_info)?;
-    check_pda_cannonical_bump(
+    let VrfResultCloseAccounts {
         vrf_result_account_info,
-        &[
-            b"VrfResult".as_ref(),
-            vrf_result_state.game.as_ref(),
-            vrf_result_state.owner.as_ref(),
-            &vrf_result_state.bet_id.to_le_bytes(),
-        ],
-    )?;
-
-    check_is_writable(bettor_wallet_account_info)?;
-    check_pubkey_eq(bettor_wallet_account_info, &bettor_user_account_state.authority)?;
-    check_pubkey_eq(bettor_wallet_account_info, &vrf_result_state.owner)?;
-
-    check_is_writable(bettor_user_account_info)?;
-    check_pda_cannonical_bump(
+        bettor_wallet_account_info,
         bettor_user_account_info,
-        &[b"UserAccount".as_ref(), bettor_user_account_state.authority.as_ref()],
-    )?;
-
-    check_pubkey_eq(system_program_account_info, &system_program::ID)?;
+        cranker_account_info,
+        vrf_result_state,
+        mut bettor_user_account_state,
+        ..
+    } = VrfResultCloseAccounts::try_from_accounts(accounts)?;
     // check conditions
     if !vrf_result_state.marked_for_close {
         msg!("VRF result account {} is not marked for close", vrf_result_account_info.key);
@@ -62,13 +78,19 @@ pub fn vrf_result_close(_program_id: &Pubkey, accounts: &[AccountInfo]) -> Progr
         msg!("VRF result account {} is not used", vrf_result_account_info.key);
         return Err(ProgramError::from(BettingError::VrfResultNotUsed));
     }
-    // close vrf result account
+    // close vrf result account, the rent goes back to the bettor but for the close tip of the cranker
     vrf_result_account_info.data.borrow_mut().fill(0);
     vrf_result_account_info.realloc(0, false)?;
-    **bettor_wallet_account_info.lamports.borrow_mut() = bettor_wallet_account_info.lamports().checked_add(vrf_result_account_info.lamports()).unwrap();
+    let mut rent_lamports = vrf_result_account_info.lamports();
+    if let Some(cranker_account_info) = cranker_account_info {
+        let close_tip_lamports = CLOSE_CRANK_TIP_LAMPORTS.min(rent_lamports);
+        **cranker_account_info.lamports.borrow_mut() = cranker_account_info.lamports().try_add(close_tip_lamports)?;
+        rent_lamports = rent_lamports.try_sub(close_tip_lamports)?;
+    }
+    **bettor_wallet_account_info.lamports.borrow_mut() = bettor_wallet_account_info.lamports().try_add(rent_lamports)?;
     **vrf_result_account_info.lamports.borrow_mut() = 0;
     // update bettor user account
-    bettor_user_account_state.active_vrf_results -= 1;
+    bettor_user_account_state.active_vrf_results = bettor_user_account_state.active_vrf_results.try_sub(1)?;
     bettor_user_account_state.serialize(&mut &mut bettor_user_account_info.data.borrow_mut()[..])?;
     Ok(())
 }
@@ -84,9 +106,10 @@ mod test {
         system_program,
     };
     use solana_program_test::{tokio, ProgramTest};
-    use solana_sdk::{account::Account, signer::Signer, transaction::Transaction};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
 
     use crate::{
+        constants::CLOSE_CRANK_TIP_LAMPORTS,
         instructions::BettingInstruction,
         state::{
             game::{
@@ -98,11 +121,21 @@ mod test {
         },
     };
 
-    #[tokio::test]
-    async fn test_vrf_result_close_success() {
+    /// Close a bet marked for close, signed by a cranker when given, and return the rent of the bet with the lamports
+    /// the bettor and the cranker received
+    async fn run_vrf_result_close(with_cranker: bool) -> (u64, u64, u64) {
         let program_id = crate::id();
         let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
 
+        let cranker = Keypair::new();
+        program_test.add_account(
+            cranker.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
         let bettor = Pubkey::new_unique();
         program_test.add_account(
             bettor,
@@ -162,31 +195,56 @@ mod test {
             },
         );
 
+        let mut accounts = vec![
+            AccountMeta::new(vrf_result_pda, false),
+            AccountMeta::new(bettor, false),
+            AccountMeta::new(bettor_user_account_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        if with_cranker {
+            accounts.push(AccountMeta::new(cranker.pubkey(), true));
+        }
+
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
         let mut transaction = Transaction::new_with_payer(
-            &[Instruction::new_with_borsh(
-                program_id,
-                &BettingInstruction::VrfResultClose,
-                vec![
-                    AccountMeta::new(vrf_result_pda, false),
-                    AccountMeta::new(bettor, false),
-                    AccountMeta::new(bettor_user_account_pda, false),
-                    AccountMeta::new_readonly(system_program::id(), false),
-                ],
-            )],
+            &[Instruction::new_with_borsh(program_id, &BettingInstruction::VrfResultClose, accounts)],
             Some(&payer.pubkey()),
         );
-        transaction.sign(&[&payer], recent_blockhash);
+        if with_cranker {
+            transaction.sign(&[&cranker, &payer], recent_blockhash);
+        } else {
+            transaction.sign(&[&payer], recent_blockhash);
+        }
         banks_client.process_transaction(transaction).await.unwrap();
 
         // the vrf result account should be closed
         assert!(banks_client.get_account(vrf_result_pda).await.unwrap().is_none());
-        // the rent should be returned to the bettor
-        let bettor_account = banks_client.get_account(bettor).await.unwrap().unwrap();
-        assert_eq!(bettor_account.lamports, LAMPORTS_PER_SOL + Rent::default().minimum_balance(vrf_result_data_len));
         // the bettor user account should be updated
         let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
         assert_eq!(bettor_user_account_state.active_vrf_results, 0);
+        let bettor_account = banks_client.get_account(bettor).await.unwrap().unwrap();
+        let cranker_account = banks_client.get_account(cranker.pubkey()).await.unwrap().unwrap();
+        (
+            Rent::default().minimum_balance(vrf_result_data_len),
+            bettor_account.lamports - LAMPORTS_PER_SOL,
+            cranker_account.lamports - LAMPORTS_PER_SOL,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_vrf_result_close_success() {
+        let (rent_lamports, bettor_lamports, cranker_lamports) = run_vrf_result_close(false).await;
+        // the rent should be returned to the bettor
+        assert_eq!(bettor_lamports, rent_lamports);
+        assert_eq!(cranker_lamports, 0);
+    }
+
+    #[tokio::test]
+    async fn test_vrf_result_close_success_crank_tip() {
+        let (rent_lamports, bettor_lamports, cranker_lamports) = run_vrf_result_close(true).await;
+        // the close tip should be paid to the cranker out of the rent
+        assert_eq!(cranker_lamports, CLOSE_CRANK_TIP_LAMPORTS);
+        assert_eq!(bettor_lamports, rent_lamports - CLOSE_CRANK_TIP_LAMPORTS);
     }
 
     #[tokio::test]
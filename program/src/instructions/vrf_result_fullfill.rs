@@ -1,17 +1,11 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
 
 use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump, check_pubkey_eq},
     constants::OPERATOR_PUBKEY,
     error::BettingError,
-    state::{vrf_result::VrfResult, BettingAccount},
+    state::{randomness::Randomness, vrf_result::VrfResult},
 };
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
@@ -20,30 +14,39 @@ pub struct VrfResultFullfillArgs {
     pub pi: [u8; 80],
 }
 
+#[derive(Accounts)]
+pub struct VrfResultFullfillAccounts<'a, 'info> {
+    #[account(signer, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+}
+
 pub fn vrf_result_fullfill(_program_id: &Pubkey, accounts: &[AccountInfo], args: VrfResultFullfillArgs) -> ProgramResult {
     msg!("Instruction VrfResultFullfill");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let op_account_info = next_account_info(iter)?;
-    let vrf_result_account_info = next_account_info(iter)?;
-
-    let mut vrf_result_state = VrfResult::try_from_account_info(vrf_result_account_info)?;
-    // check accounts
-    check_is_signer(op_account_info)?;
-    check_pubkey_eq(op_account_info, &OPERATOR_PUBKEY)?;
-
-    check_is_writable(vrf_result_account_info)?;
-    check_pda_cannonical_bump(
+    let VrfResultFullfillAccounts {
         vrf_result_account_info,
-        &[
-            b"VrfResult".as_ref(),
-            vrf_result_state.game.as_ref(),
-            vrf_result_state.owner.as_ref(),
-            &vrf_result_state.bet_id.to_le_bytes(),
-        ],
-    )?;
+        mut vrf_result_state,
+        ..
+    } = VrfResultFullfillAccounts::try_from_accounts(accounts)?;
+    // update vrf result account
+    fullfill_vrf_result(vrf_result_account_info, &mut vrf_result_state, &args)?;
+    vrf_result_state.serialize(&mut &mut vrf_result_account_info.data.borrow_mut()[..])?;
 
+    Ok(())
+}
+
+/// Write the output and proof of the operator's VRF to an unfullfilled bet
+pub(crate) fn fullfill_vrf_result(vrf_result_account_info: &AccountInfo, vrf_result_state: &mut VrfResult, args: &VrfResultFullfillArgs) -> ProgramResult {
     // check conditions
     if vrf_result_state.is_fullfilled {
         msg!("VRF result account {} is already fullfilled", vrf_result_account_info.key);
@@ -53,11 +56,14 @@ pub fn vrf_result_fullfill(_program_id: &Pubkey, accounts: &[AccountInfo], args:
         msg!("VRF result account {} is already used", vrf_result_account_info.key);
         return Err(ProgramError::from(BettingError::VrfResultAlreadyUsed));
     }
-    // update vrf result account
+    if vrf_result_state.randomness != Randomness::Vrf {
+        msg!("VRF result account {} is not settled by the operator's VRF", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::WrongRandomnessSource));
+    }
+    // the proof is kept for anyone to verify the output off-chain
     vrf_result_state.is_fullfilled = true;
     vrf_result_state.beta = args.beta;
     vrf_result_state.pi = args.pi;
-    vrf_result_state.serialize(&mut &mut vrf_result_account_info.data.borrow_mut()[..])?;
 
     Ok(())
 }
@@ -82,6 +88,7 @@ mod test {
                 coinflip::{CoinFlipInput, CoinFlipSide},
                 BetInput,
             },
+            randomness::Randomness,
             vrf_result::VrfResult,
         },
     };
@@ -292,4 +299,67 @@ mod test {
         assert_eq!(vrf_result_state.beta, [1; 64]);
         assert_eq!(vrf_result_state.pi, [1; 80]);
     }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(22)")]
+    async fn test_vrf_result_fullfill_err_wrong_randomness_source() {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let game_pda = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let bet_id = 0;
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_id,
+            [0; 72],
+            100,
+            100,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 100,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.randomness = Randomness::HashChain { index: 0 };
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) =
+            Pubkey::find_program_address(&[b"VrfResult".as_ref(), game_pda.as_ref(), bettor.as_ref(), &bet_id.to_le_bytes()], &program_id);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::VrfResultFullfill {
+                    args: VrfResultFullfillArgs { beta: [1; 64], pi: [1; 80] },
+                },
+                vec![AccountMeta::new_readonly(operator.pubkey(), true), AccountMeta::new(vrf_result_pda, false)],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
 }
@@ -0,0 +1,259 @@
+use std::cell::RefMut;
+
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    constants::OPERATOR_PUBKEY,
+    error::BettingError,
+    state::{bankroll::GameBankroll, game::Game, stats::Stats, user_account::UserAccount, vrf_result::VrfResult},
+};
+
+use super::{
+    game_resolve_vrf_result::GameResolveVrfResultAccounts,
+    vrf_result_fullfill::{fullfill_vrf_result, VrfResultFullfillArgs},
+};
+
+#[derive(Accounts)]
+pub struct VrfResultFullfillAndResolveAccounts<'a, 'info> {
+    #[account(signer, writable, address = OPERATOR_PUBKEY, name = "operator", desc = "Operator Account")]
+    pub op_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        constraint = game_account_info.key == &vrf_result_state.game @ BettingError::WrongPubkey,
+        constraint = bettor_user_account_state.authority == vrf_result_state.owner @ BettingError::WrongPubkey,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"GameBankroll", game_account_info.key],
+        name = "game_bankroll_pda",
+        desc = "Game bankroll PDA account"
+    )]
+    pub game_bankroll_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"UserAccount", bettor_user_account_state.authority],
+        stored_bump = bettor_user_account_state.bump,
+        name = "bettor_user_account",
+        desc = "Bettor user account"
+    )]
+    pub bettor_user_account_info: &'a AccountInfo<'info>,
+    #[account(writable, seeds = [b"Stats"], stored_bump = stats_state.bump, name = "stats_pda", desc = "Stats PDA Account")]
+    pub stats_account_info: &'a AccountInfo<'info>,
+    #[account(writable, name = "bettor_user_limits_pda", desc = "Bettor user limits PDA Account, may be uninitialized")]
+    pub bettor_user_limits_account_info: &'a AccountInfo<'info>,
+    #[state(game_account_info)]
+    pub game_state: RefMut<'a, Game>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+    #[state(game_bankroll_account_info)]
+    pub game_bankroll_state: GameBankroll,
+    #[state(bettor_user_account_info)]
+    pub bettor_user_account_state: RefMut<'a, UserAccount>,
+    #[state(stats_account_info)]
+    pub stats_state: RefMut<'a, Stats>,
+}
+
+pub fn vrf_result_fullfill_and_resolve(_program_id: &Pubkey, accounts: &[AccountInfo], args: VrfResultFullfillArgs) -> ProgramResult {
+    msg!("Instruction: VrfResultFullfillAndResolve");
+    // get accounts
+    let VrfResultFullfillAndResolveAccounts {
+        op_account_info,
+        game_account_info,
+        vrf_result_account_info,
+        game_bankroll_account_info,
+        bettor_user_account_info,
+        stats_account_info,
+        bettor_user_limits_account_info,
+        game_state,
+        mut vrf_result_state,
+        game_bankroll_state,
+        bettor_user_account_state,
+        stats_state,
+    } = VrfResultFullfillAndResolveAccounts::try_from_accounts(accounts)?;
+    // fullfill the bet and settle it in the same instruction, the operator takes the crank tip
+    fullfill_vrf_result(vrf_result_account_info, &mut vrf_result_state, &args)?;
+    GameResolveVrfResultAccounts {
+        game_account_info,
+        vrf_result_account_info,
+        game_bankroll_account_info,
+        bettor_user_account_info,
+        stats_account_info,
+        bettor_user_limits_account_info,
+        op_account_info,
+        // the operator fullfilling the bet takes the crank tip
+        cranker_account_info: Some(op_account_info),
+        game_state,
+        vrf_result_state,
+        game_bankroll_state,
+        bettor_user_account_state,
+        stats_state,
+    }
+    .resolve()
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use home::home_dir;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, BanksClient, ProgramTest};
+    use solana_sdk::{account::Account, signature::read_keypair_file, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::{vrf_result_fullfill::VrfResultFullfillArgs, BettingInstruction},
+        state::{
+            bankroll::GameBankroll,
+            game::{
+                coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+                BetInput, Game, GameTypeConfig, ProcessVrfResult,
+            },
+            stats::Stats,
+            user_account::UserAccount,
+            vrf_result::VrfResult,
+        },
+    };
+
+    const CONFIG: CoinFlipConfig = CoinFlipConfig {
+        host_probability_advantage: 100,
+        payout_rate: 9900,
+    };
+
+    fn add_account<T: BorshSerialize>(program_test: &mut ProgramTest, pubkey: Pubkey, state: &T) {
+        let data = state.try_to_vec().unwrap();
+        program_test.add_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: crate::id(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Fullfill and resolve a bet placed on a coin flip game, which may already be fullfilled
+    async fn fullfill_and_resolve(is_already_fullfilled: bool) -> (BanksClient, VrfResult, Pubkey, Pubkey, Pubkey) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let key_file_path = home_dir().unwrap().join(".config/solana/id.json");
+        let operator = read_keypair_file(key_file_path).unwrap();
+        program_test.add_account(
+            operator.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        let bettor = Pubkey::new_unique();
+        let (bettor_user_account_pda, _) = Pubkey::find_program_address(&[b"UserAccount".as_ref(), bettor.as_ref()], &program_id);
+        let mut bettor_user_account_state = UserAccount::new(bettor, None, None);
+        bettor_user_account_state.active_vrf_results = 1;
+        add_account(&mut program_test, bettor_user_account_pda, &bettor_user_account_state);
+
+        let mut game_state = Game::new(Pubkey::new_unique(), 1000, 10000, GameTypeConfig::CoinFlip { config: CONFIG });
+        game_state.unresolved_vrf_result = 1;
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        add_account(&mut program_test, game_pda, &game_state);
+
+        let (game_bankroll_pda, _) = Pubkey::find_program_address(&[b"GameBankroll".as_ref(), game_pda.as_ref()], &program_id);
+        let mut game_bankroll_state = GameBankroll::new(game_pda, game_state.host);
+        game_bankroll_state.locked_lamports = 1980;
+        add_account(&mut program_test, game_bankroll_pda, &game_bankroll_state);
+
+        let (stats_pda, _) = Pubkey::find_program_address(&[b"Stats".as_ref()], &program_id);
+        add_account(&mut program_test, stats_pda, &Stats::new());
+
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            0,
+            [1; 72],
+            2000,
+            1980,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.is_fullfilled = is_already_fullfilled;
+        let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, 0);
+        add_account(&mut program_test, vrf_result_pda, &vrf_result_state);
+        let (bettor_user_limits_pda, _) = Pubkey::find_program_address(&[b"UserLimits".as_ref(), bettor_user_account_pda.as_ref()], &program_id);
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let args = VrfResultFullfillArgs { beta: [5; 64], pi: [6; 80] };
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::VrfResultFullfillAndResolve { args },
+                vec![
+                    AccountMeta::new(operator.pubkey(), true),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                    AccountMeta::new(game_bankroll_pda, false),
+                    AccountMeta::new(bettor_user_account_pda, false),
+                    AccountMeta::new(stats_pda, false),
+                    AccountMeta::new(bettor_user_limits_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&operator, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // the bet as it is fullfilled by the operator
+        vrf_result_state.is_fullfilled = true;
+        vrf_result_state.beta = args.beta;
+        (banks_client, vrf_result_state, game_pda, vrf_result_pda, bettor_user_account_pda)
+    }
+
+    #[tokio::test]
+    async fn test_vrf_result_fullfill_and_resolve_success() {
+        let (mut banks_client, fullfilled_vrf_result_state, game_pda, vrf_result_pda, bettor_user_account_pda) = fullfill_and_resolve(false).await;
+        let (host_gain, bettor_gain) = CONFIG
+            .process_vrf_result(&fullfilled_vrf_result_state, &fullfilled_vrf_result_state.randomness)
+            .unwrap();
+        assert_eq!(host_gain + bettor_gain, 2000 + 1980);
+
+        let game_state: Game = banks_client.get_account_data_with_borsh(game_pda).await.unwrap();
+        assert_eq!(game_state.unresolved_vrf_result, 0);
+        assert_eq!(game_state.total_lamports_out, bettor_gain);
+        // the bet should be fullfilled and settled in the same instruction
+        let vrf_result_state: VrfResult = banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap();
+        assert!(vrf_result_state.is_fullfilled);
+        assert!(vrf_result_state.is_used);
+        assert_eq!(vrf_result_state.beta, [5; 64]);
+        assert_eq!(vrf_result_state.pi, [6; 80]);
+        let bettor_user_account_state: UserAccount = banks_client.get_account_data_with_borsh(bettor_user_account_pda).await.unwrap();
+        assert_eq!(bettor_user_account_state.current_lamports, bettor_gain);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(10)")]
+    async fn test_vrf_result_fullfill_and_resolve_err_already_fullfilled() {
+        fullfill_and_resolve(true).await;
+    }
+}
@@ -1,51 +1,33 @@
 use borsh::BorshSerialize;
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-};
-
-use crate::{
-    checks::{check_is_signer, check_is_writable, check_pda_cannonical_bump},
-    error::BettingError,
-    state::{vrf_result::VrfResult, BettingAccount},
-};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::state::vrf_result::VrfResult;
+
+#[derive(Accounts)]
+pub struct VrfResultMarkCloseAccounts<'a, 'info> {
+    #[account(signer, authority = vrf_result_state.owner, name = "bettor", desc = "Bettor wallet account")]
+    pub bettor_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+}
 
 pub fn vrf_result_mark_close(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     msg!("Instruction: VrfResultMarkClose");
     // get accounts
-    let iter = &mut accounts.iter();
-
-    let bettor_account_info = next_account_info(iter)?;
-    let vrf_result_account_info = next_account_info(iter)?;
-
-    let mut vrf_result_state = VrfResult::try_from_account_info(vrf_result_account_info)?;
-
-    // check accounts
-    check_is_signer(bettor_account_info)?;
-
-    check_is_writable(vrf_result_account_info)?;
-    check_pda_cannonical_bump(
+    let VrfResultMarkCloseAccounts {
         vrf_result_account_info,
-        &[
-            b"VrfResult".as_ref(),
-            vrf_result_state.game.as_ref(),
-            vrf_result_state.owner.as_ref(),
-            &vrf_result_state.bet_id.to_le_bytes(),
-        ],
-    )?;
-
-    // check authority
-    if &vrf_result_state.owner != bettor_account_info.key {
-        msg!(
-            "Expect VRF result account {} to be owned by user {}",
-            vrf_result_account_info.key,
-            bettor_account_info.key
-        );
-        return Err(ProgramError::from(BettingError::NoAuthority));
-    }
+        mut vrf_result_state,
+        ..
+    } = VrfResultMarkCloseAccounts::try_from_accounts(accounts)?;
     // update vrf result account
     vrf_result_state.marked_for_close = true;
     vrf_result_state.serialize(&mut &mut vrf_result_account_info.data.borrow_mut()[..])?;
@@ -0,0 +1,240 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    error::BettingError,
+    state::{
+        randomness::{OracleConfig, Randomness},
+        vrf_result::VrfResult,
+    },
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct VrfResultOracleFullfillArgs {
+    pub beta: [u8; 64],
+}
+
+#[derive(Accounts)]
+pub struct VrfResultOracleFullfillAccounts<'a, 'info> {
+    #[account(signer, name = "oracle", desc = "Oracle Account")]
+    pub oracle_account_info: &'a AccountInfo<'info>,
+    #[account(seeds = [b"OracleConfig"], name = "oracle_config_pda", desc = "Oracle config PDA Account")]
+    pub oracle_config_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[state(oracle_config_account_info)]
+    pub oracle_config_state: OracleConfig,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+}
+
+pub fn vrf_result_oracle_fullfill(_program_id: &Pubkey, accounts: &[AccountInfo], args: VrfResultOracleFullfillArgs) -> ProgramResult {
+    msg!("Instruction: VrfResultOracleFullfill");
+    // get accounts
+    let VrfResultOracleFullfillAccounts {
+        oracle_account_info,
+        vrf_result_account_info,
+        oracle_config_state,
+        mut vrf_result_state,
+        ..
+    } = VrfResultOracleFullfillAccounts::try_from_accounts(accounts)?;
+    // check conditions
+    let oracle_index = match oracle_config_state.get_oracle_index(oracle_account_info.key) {
+        Some(oracle_index) => oracle_index,
+        None => {
+            msg!("Account {} is not an oracle", oracle_account_info.key);
+            return Err(ProgramError::from(BettingError::NoAuthority));
+        }
+    };
+    if vrf_result_state.is_fullfilled {
+        msg!("VRF result account {} is already fullfilled", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyFullfilled));
+    }
+    if vrf_result_state.is_used {
+        msg!("VRF result account {} is already used", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyUsed));
+    }
+    let (threshold, contributions) = match vrf_result_state.randomness {
+        Randomness::Oracles { threshold, contributions } => (threshold, contributions),
+        _ => {
+            msg!("VRF result account {} is not settled by oracles", vrf_result_account_info.key);
+            return Err(ProgramError::from(BettingError::WrongRandomnessSource));
+        }
+    };
+    if contributions & (1 << oracle_index) != 0 {
+        msg!(
+            "Oracle {} already contributed to VRF result account {}",
+            oracle_account_info.key,
+            vrf_result_account_info.key
+        );
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyFullfilled));
+    }
+    // update vrf result account, the outputs are combined until enough oracles contributed
+    for (beta, output) in vrf_result_state.beta.iter_mut().zip(args.beta) {
+        *beta ^= output;
+    }
+    vrf_result_state.randomness = Randomness::Oracles {
+        threshold,
+        contributions: contributions | (1 << oracle_index),
+    };
+    vrf_result_state.is_fullfilled = vrf_result_state.randomness.is_complete();
+    vrf_result_state.serialize(&mut &mut vrf_result_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_instruction,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            game::{
+                coinflip::{CoinFlipInput, CoinFlipSide},
+                BetInput,
+            },
+            randomness::{OracleConfig, Randomness},
+            vrf_result::VrfResult,
+        },
+    };
+
+    use super::VrfResultOracleFullfillArgs;
+
+    /// Fullfill a bet needing 2 of 3 oracles with the outputs of the given oracles, in order
+    async fn oracle_fullfill(contributors: &[usize]) -> VrfResult {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let oracles: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+        for oracle in &oracles {
+            program_test.add_account(
+                oracle.pubkey(),
+                Account {
+                    lamports: LAMPORTS_PER_SOL,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let (oracle_config_pda, _) = Pubkey::find_program_address(&[b"OracleConfig".as_ref()], &program_id);
+        let oracle_config_data = OracleConfig::new(oracles.iter().map(|oracle| oracle.pubkey()).collect()).try_to_vec().unwrap();
+        program_test.add_account(
+            oracle_config_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(oracle_config_data.len()),
+                data: oracle_config_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let game_pda = Pubkey::new_unique();
+        let bettor = Pubkey::new_unique();
+        let bet_id = 0;
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_id,
+            [0; 72],
+            100,
+            100,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 100,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.randomness = Randomness::Oracles {
+            threshold: 2,
+            contributions: 0,
+        };
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, bet_id);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        for (nonce, oracle_index) in contributors.iter().enumerate() {
+            let oracle = &oracles[*oracle_index];
+            let mut transaction = Transaction::new_with_payer(
+                &[
+                    Instruction::new_with_borsh(
+                        program_id,
+                        &BettingInstruction::VrfResultOracleFullfill {
+                            args: VrfResultOracleFullfillArgs { beta: [1 << oracle_index; 64] },
+                        },
+                        vec![
+                            AccountMeta::new_readonly(oracle.pubkey(), true),
+                            AccountMeta::new_readonly(oracle_config_pda, false),
+                            AccountMeta::new(vrf_result_pda, false),
+                        ],
+                    ),
+                    // repeated contributions would otherwise be the same transaction
+                    system_instruction::transfer(&payer.pubkey(), &oracle.pubkey(), nonce as u64 + 1),
+                ],
+                Some(&payer.pubkey()),
+            );
+            transaction.sign(&[oracle, &payer], recent_blockhash);
+            banks_client.process_transaction(transaction).await.unwrap();
+        }
+
+        banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_vrf_result_oracle_fullfill_success() {
+        // a single oracle is below the threshold
+        let vrf_result_state = oracle_fullfill(&[2]).await;
+        assert!(!vrf_result_state.is_fullfilled);
+        assert_eq!(
+            vrf_result_state.randomness,
+            Randomness::Oracles {
+                threshold: 2,
+                contributions: 0b100
+            }
+        );
+        // the outputs of the oracles are combined
+        let vrf_result_state = oracle_fullfill(&[2, 0]).await;
+        assert!(vrf_result_state.is_fullfilled);
+        assert_eq!(vrf_result_state.beta, [0b101; 64]);
+        assert_eq!(
+            vrf_result_state.randomness,
+            Randomness::Oracles {
+                threshold: 2,
+                contributions: 0b101
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(10)")]
+    async fn test_vrf_result_oracle_fullfill_err_already_contributed() {
+        oracle_fullfill(&[1, 1]).await;
+    }
+}
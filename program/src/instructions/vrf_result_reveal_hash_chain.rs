@@ -0,0 +1,236 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
+use vrf_betting_derive::Accounts;
+
+use crate::{
+    error::BettingError,
+    state::{game::Game, randomness::Randomness, vrf_result::VrfResult},
+};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct VrfResultRevealHashChainArgs {
+    pub server_seed: [u8; 32],
+}
+
+#[derive(Accounts)]
+pub struct VrfResultRevealHashChainAccounts<'a, 'info> {
+    #[account(signer, address = game_state.host, name = "host", desc = "The wallet account of the host")]
+    pub host_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"Game", game_state.common_config_seed(), game_state.game_type_config_seed()],
+        stored_bump = game_state.bump,
+        name = "game_pda",
+        desc = "Game PDA Account"
+    )]
+    pub game_account_info: &'a AccountInfo<'info>,
+    #[account(
+        writable,
+        seeds = [b"VrfResult", vrf_result_state.game, vrf_result_state.owner, vrf_result_state.bet_id.to_le_bytes()],
+        stored_bump = vrf_result_state.bump,
+        constraint = game_account_info.key == &vrf_result_state.game @ BettingError::WrongPubkey,
+        name = "vrf_result_pda",
+        desc = "VRF result PDA account"
+    )]
+    pub vrf_result_account_info: &'a AccountInfo<'info>,
+    #[state(game_account_info)]
+    pub game_state: RefMut<'a, Game>,
+    #[state(vrf_result_account_info)]
+    pub vrf_result_state: VrfResult,
+}
+
+pub fn vrf_result_reveal_hash_chain(_program_id: &Pubkey, accounts: &[AccountInfo], args: VrfResultRevealHashChainArgs) -> ProgramResult {
+    msg!("Instruction: VrfResultRevealHashChain");
+    // get accounts
+    let VrfResultRevealHashChainAccounts {
+        vrf_result_account_info,
+        mut game_state,
+        mut vrf_result_state,
+        ..
+    } = VrfResultRevealHashChainAccounts::try_from_accounts(accounts)?;
+    // update game and vrf result accounts, the game is written in place
+    reveal_hash_chain_link(vrf_result_account_info, &mut vrf_result_state, &mut game_state, &args.server_seed)?;
+    vrf_result_state.serialize(&mut &mut vrf_result_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Fullfill a hash chain bet with the server seed of its link, which becomes the head of the game's hash chain
+pub(crate) fn reveal_hash_chain_link(
+    vrf_result_account_info: &AccountInfo,
+    vrf_result_state: &mut VrfResult,
+    game_state: &mut Game,
+    server_seed: &[u8; 32],
+) -> ProgramResult {
+    if vrf_result_state.is_fullfilled {
+        msg!("VRF result account {} is already fullfilled", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyFullfilled));
+    }
+    if vrf_result_state.is_used {
+        msg!("VRF result account {} is already used", vrf_result_account_info.key);
+        return Err(ProgramError::from(BettingError::VrfResultAlreadyUsed));
+    }
+    let index = match vrf_result_state.randomness {
+        Randomness::HashChain { index } => index,
+        _ => {
+            msg!("VRF result account {} is not settled by a hash chain", vrf_result_account_info.key);
+            return Err(ProgramError::from(BettingError::WrongRandomnessSource));
+        }
+    };
+    game_state.reveal_hash_chain_link(index, server_seed)?;
+    vrf_result_state.is_fullfilled = true;
+    vrf_result_state.beta[..32].copy_from_slice(server_seed);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{
+        hash::hashv,
+        instruction::{AccountMeta, Instruction},
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use crate::{
+        instructions::BettingInstruction,
+        state::{
+            game::{
+                coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide},
+                BetInput, Game, GameTypeConfig,
+            },
+            randomness::{Randomness, RandomnessSourceConfig},
+            vrf_result::VrfResult,
+        },
+    };
+
+    use super::VrfResultRevealHashChainArgs;
+
+    async fn process_reveal(bet_index: u32, server_seed: [u8; 32]) -> (Game, VrfResult) {
+        let program_id = crate::id();
+        let mut program_test = ProgramTest::new("vrf_betting", program_id, None);
+
+        let host = Keypair::new();
+        program_test.add_account(
+            host.pubkey(),
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                ..Default::default()
+            },
+        );
+
+        // the host committed to the chain [9; 32] -> h([9; 32]) -> h(h([9; 32]))
+        let mut game_state = Game::new(
+            host.pubkey(),
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        let head = hashv(&[&hashv(&[&[9; 32]]).to_bytes()]).to_bytes();
+        game_state.set_randomness_source(RandomnessSourceConfig::HashChain { head }).unwrap();
+        game_state.next_randomness().unwrap();
+        let game_data = game_state.try_to_vec().unwrap();
+        let game_data_len = game_data.len();
+        let (game_pda, _) = Pubkey::find_program_address(&game_state.seeds(), &program_id);
+        program_test.add_account(
+            game_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(game_data_len),
+                data: game_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let bettor = Pubkey::new_unique();
+        let mut vrf_result_state = VrfResult::new(
+            bettor,
+            game_pda,
+            bet_index,
+            [1; 72],
+            1000,
+            990,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 1000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result_state.randomness = Randomness::HashChain { index: bet_index };
+        let vrf_result_data = vrf_result_state.try_to_vec().unwrap();
+        let vrf_result_data_len = vrf_result_data.len();
+        let (vrf_result_pda, _) = VrfResult::find_pda(&game_pda, &bettor, bet_index);
+        program_test.add_account(
+            vrf_result_pda,
+            Account {
+                lamports: Rent::default().minimum_balance(vrf_result_data_len),
+                data: vrf_result_data,
+                owner: program_id,
+                ..Default::default()
+            },
+        );
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let mut transaction = Transaction::new_with_payer(
+            &[Instruction::new_with_borsh(
+                program_id,
+                &BettingInstruction::VrfResultRevealHashChain {
+                    args: VrfResultRevealHashChainArgs { server_seed },
+                },
+                vec![
+                    AccountMeta::new_readonly(host.pubkey(), true),
+                    AccountMeta::new(game_pda, false),
+                    AccountMeta::new(vrf_result_pda, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&host, &payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        (
+            banks_client.get_account_data_with_borsh(game_pda).await.unwrap(),
+            banks_client.get_account_data_with_borsh(vrf_result_pda).await.unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_vrf_result_reveal_hash_chain_success() {
+        let server_seed = hashv(&[&[9; 32]]).to_bytes();
+        let (game_state, vrf_result_state) = process_reveal(0, server_seed).await;
+
+        // the revealed link should be the new head of the game's hash chain
+        assert_eq!(game_state.hash_chain_head, server_seed);
+        assert_eq!(game_state.hash_chain_revealed, 1);
+        // the vrf result should be fullfilled with the server seed
+        assert!(vrf_result_state.is_fullfilled);
+        assert_eq!(vrf_result_state.beta[..32], server_seed);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(23)")]
+    async fn test_vrf_result_reveal_hash_chain_err_not_preimage() {
+        // the server seed two links down the chain does not hash to the head
+        process_reveal(0, [9; 32]).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Custom(23)")]
+    async fn test_vrf_result_reveal_hash_chain_err_wrong_index() {
+        // the first link revealed as the second one
+        process_reveal(1, hashv(&[&[9; 32]]).to_bytes()).await;
+    }
+}
@@ -2,10 +2,16 @@ pub mod checks;
 pub mod constants;
 pub mod entrypoint;
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod math;
 pub mod processor;
+pub mod rng;
 pub mod state;
 
+#[cfg(test)]
+mod cpi_test;
+
 pub use solana_program;
 
 solana_program::declare_id!("9f2Yc9UYah7ARVMvJLVTqnJCnDv3ofSG1PSvJwikgEn4");
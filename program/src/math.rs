@@ -0,0 +1,79 @@
+use solana_program::{msg, program_error::ProgramError};
+
+use crate::error::BettingError;
+
+fn math_overflow() -> ProgramError {
+    msg!("Math overflow");
+    ProgramError::from(BettingError::MathOverflow)
+}
+
+/// Arithmetic failing with `BettingError::MathOverflow` instead of wrapping or panicking
+pub trait CheckedMath: Sized {
+    fn try_add(self, rhs: Self) -> Result<Self, ProgramError>;
+    fn try_sub(self, rhs: Self) -> Result<Self, ProgramError>;
+    fn try_mul(self, rhs: Self) -> Result<Self, ProgramError>;
+}
+macro_rules! impl_checked_math {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedMath for $t {
+                fn try_add(self, rhs: Self) -> Result<Self, ProgramError> {
+                    self.checked_add(rhs).ok_or_else(math_overflow)
+                }
+                fn try_sub(self, rhs: Self) -> Result<Self, ProgramError> {
+                    self.checked_sub(rhs).ok_or_else(math_overflow)
+                }
+                fn try_mul(self, rhs: Self) -> Result<Self, ProgramError> {
+                    self.checked_mul(rhs).ok_or_else(math_overflow)
+                }
+            }
+        )*
+    };
+}
+impl_checked_math!(u32, u64, i64);
+
+/// `value * numerator / denominator` without overflowing the intermediate product
+pub fn mul_div(value: u64, numerator: u64, denominator: u64) -> Result<u64, ProgramError> {
+    if denominator == 0 {
+        return Err(math_overflow());
+    }
+    u64::try_from(value as u128 * numerator as u128 / denominator as u128).map_err(|_| math_overflow())
+}
+
+/// Signed result of receiving `gain` lamports for `stake` lamports
+pub fn signed_diff(gain: u64, stake: u64) -> Result<i64, ProgramError> {
+    i64::try_from(gain as i128 - stake as i128).map_err(|_| math_overflow())
+}
+
+/// Lamports as a signed amount
+pub fn to_signed(lamports: u64) -> Result<i64, ProgramError> {
+    i64::try_from(lamports).map_err(|_| math_overflow())
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::program_error::ProgramError;
+
+    use crate::error::BettingError;
+
+    use super::{mul_div, signed_diff, to_signed, CheckedMath};
+
+    #[test]
+    fn test_checked_math() {
+        let overflow = ProgramError::from(BettingError::MathOverflow);
+        assert_eq!(1u64.try_add(2).unwrap(), 3);
+        assert_eq!(u64::MAX.try_add(1).unwrap_err(), overflow);
+        assert_eq!(0u32.try_sub(1).unwrap_err(), overflow);
+        assert_eq!((-1i64).try_sub(1).unwrap(), -2);
+        assert_eq!(u64::MAX.try_mul(2).unwrap_err(), overflow);
+
+        assert_eq!(mul_div(u64::MAX, 9900, 10000).unwrap(), (u64::MAX as u128 * 9900 / 10000) as u64);
+        assert_eq!(mul_div(u64::MAX, 2, 1).unwrap_err(), overflow);
+        assert_eq!(mul_div(1, 1, 0).unwrap_err(), overflow);
+
+        assert_eq!(signed_diff(0, 2000).unwrap(), -2000);
+        assert_eq!(signed_diff(3980, 2000).unwrap(), 1980);
+        assert_eq!(signed_diff(u64::MAX, 0).unwrap_err(), overflow);
+        assert_eq!(to_signed(u64::MAX).unwrap_err(), overflow);
+    }
+}
@@ -1,9 +1,35 @@
+use crate::instructions::account_migrate::account_migrate;
+use crate::instructions::game_claim_bankroll_fee::game_claim_bankroll_fee;
 use crate::instructions::game_close::game_close;
+use crate::instructions::game_defund_bankroll::game_defund_bankroll;
+use crate::instructions::game_fund_bankroll::game_fund_bankroll;
+use crate::instructions::game_resolve_hash_chain::game_resolve_hash_chain;
+use crate::instructions::game_resolve_late_fullfill::game_resolve_late_fullfill;
+use crate::instructions::game_resolve_timed_out::game_resolve_timed_out;
 use crate::instructions::game_resolve_vrf_result::game_resolve_vrf_result;
+use crate::instructions::game_set_bankroll_fee::game_set_bankroll_fee;
+use crate::instructions::game_set_commission::game_set_commission;
+use crate::instructions::game_set_crank_tip::game_set_crank_tip;
+use crate::instructions::operator_bond_request_withdrawal::operator_bond_request_withdrawal;
+use crate::instructions::operator_bond_set_config::operator_bond_set_config;
+use crate::instructions::operator_bond_top_up::operator_bond_top_up;
+use crate::instructions::operator_bond_withdraw::operator_bond_withdraw;
+use crate::instructions::oracle_config_set::oracle_config_set;
+use crate::instructions::randomness_request::randomness_request;
+use crate::instructions::randomness_request_fullfill::randomness_request_fullfill;
+use crate::instructions::referral_account_create::referral_account_create;
+use crate::instructions::referral_claim::referral_claim;
+use crate::instructions::referral_config_set::referral_config_set;
+use crate::instructions::user_account_update_profile::user_account_update_profile;
 use crate::instructions::user_account_withdraw::user_account_withdraw;
+use crate::instructions::user_limits_set::user_limits_set;
+use crate::instructions::user_self_exclude::user_self_exclude;
 use crate::instructions::vrf_result_close::vrf_result_close;
 use crate::instructions::vrf_result_fullfill::vrf_result_fullfill;
+use crate::instructions::vrf_result_fullfill_and_resolve::vrf_result_fullfill_and_resolve;
 use crate::instructions::vrf_result_mark_close::vrf_result_mark_close;
+use crate::instructions::vrf_result_oracle_fullfill::vrf_result_oracle_fullfill;
+use crate::instructions::vrf_result_reveal_hash_chain::vrf_result_reveal_hash_chain;
 use crate::instructions::{game_create::game_create, stats_account_create::stats_account_create};
 use crate::instructions::{game_place_bet::game_place_bet, user_account_deposit::user_account_deposit};
 use crate::instructions::{game_set_active::game_set_active, user_account_close::user_account_close};
@@ -29,6 +55,32 @@ impl Processor {
             BettingInstruction::VrfResultClose => vrf_result_close(program_id, accounts),
             BettingInstruction::GameResolveVrfResult => game_resolve_vrf_result(program_id, accounts),
             BettingInstruction::GameClose => game_close(program_id, accounts),
+            BettingInstruction::AccountMigrate => account_migrate(program_id, accounts),
+            BettingInstruction::UserAccountUpdateProfile { args } => user_account_update_profile(program_id, accounts, args),
+            BettingInstruction::ReferralConfigSet { args } => referral_config_set(program_id, accounts, args),
+            BettingInstruction::ReferralAccountCreate { args } => referral_account_create(program_id, accounts, args),
+            BettingInstruction::ReferralClaim => referral_claim(program_id, accounts),
+            BettingInstruction::UserLimitsSet { args } => user_limits_set(program_id, accounts, args),
+            BettingInstruction::UserSelfExclude { args } => user_self_exclude(program_id, accounts, args),
+            BettingInstruction::GameFundBankroll { args } => game_fund_bankroll(program_id, accounts, args),
+            BettingInstruction::GameDefundBankroll { args } => game_defund_bankroll(program_id, accounts, args),
+            BettingInstruction::GameSetBankrollFee { args } => game_set_bankroll_fee(program_id, accounts, args),
+            BettingInstruction::GameClaimBankrollFee => game_claim_bankroll_fee(program_id, accounts),
+            BettingInstruction::GameSetCommission { args } => game_set_commission(program_id, accounts, args),
+            BettingInstruction::OracleConfigSet { args } => oracle_config_set(program_id, accounts, args),
+            BettingInstruction::VrfResultRevealHashChain { args } => vrf_result_reveal_hash_chain(program_id, accounts, args),
+            BettingInstruction::VrfResultOracleFullfill { args } => vrf_result_oracle_fullfill(program_id, accounts, args),
+            BettingInstruction::GameResolveHashChain { args } => game_resolve_hash_chain(program_id, accounts, args),
+            BettingInstruction::GameResolveTimedOut => game_resolve_timed_out(program_id, accounts),
+            BettingInstruction::OperatorBondSetConfig { args } => operator_bond_set_config(program_id, accounts, args),
+            BettingInstruction::OperatorBondTopUp { args } => operator_bond_top_up(program_id, accounts, args),
+            BettingInstruction::OperatorBondRequestWithdrawal { args } => operator_bond_request_withdrawal(program_id, accounts, args),
+            BettingInstruction::OperatorBondWithdraw => operator_bond_withdraw(program_id, accounts),
+            BettingInstruction::GameResolveLateFullfill => game_resolve_late_fullfill(program_id, accounts),
+            BettingInstruction::RandomnessRequest { args } => randomness_request(program_id, accounts, args),
+            BettingInstruction::RandomnessRequestFullfill { args } => randomness_request_fullfill(program_id, accounts, args),
+            BettingInstruction::VrfResultFullfillAndResolve { args } => vrf_result_fullfill_and_resolve(program_id, accounts, args),
+            BettingInstruction::GameSetCrankTip { args } => game_set_crank_tip(program_id, accounts, args),
         }
     }
 }
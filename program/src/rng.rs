@@ -0,0 +1,169 @@
+//! Deterministic randomness expanded from a VRF output.
+//!
+//! The stream is the concatenation of the blocks `sha256(seed || counter)`, with the counter a little-endian `u64`
+//! starting at zero. Integers are read as little-endian `u64`, and ranges are sampled by rejecting the draws at or above
+//! the largest multiple of the range length so that every outcome is equally likely. Anyone holding the VRF output can
+//! replay a bet with the same steps, the test vectors below pin them down.
+use std::ops::Range;
+
+use solana_program::{hash::hashv, program_error::ProgramError};
+
+use crate::math::CheckedMath;
+
+pub struct Rng<'a> {
+    seed: &'a [u8],
+    counter: u64,
+    block: [u8; 32],
+    offset: usize,
+}
+
+impl<'a> Rng<'a> {
+    pub fn new(seed: &'a [u8]) -> Self {
+        Self {
+            seed,
+            counter: 0,
+            block: [0; 32],
+            offset: 32,
+        }
+    }
+    pub fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            if self.offset == self.block.len() {
+                self.block = hashv(&[self.seed, &self.counter.to_le_bytes()]).to_bytes();
+                self.counter += 1;
+                self.offset = 0;
+            }
+            *byte = self.block[self.offset];
+            self.offset += 1;
+        }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+    /// Uniform draw in `0..len`, `len` must not be zero
+    fn below(&mut self, len: u64) -> u64 {
+        let limit = u64::MAX - u64::MAX % len;
+        loop {
+            let draw = self.next_u64();
+            if draw < limit {
+                return draw % len;
+            }
+        }
+    }
+    /// Uniform draw in a non-empty range
+    pub fn uniform(&mut self, range: Range<u64>) -> Result<u64, ProgramError> {
+        if range.is_empty() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(range.start + self.below(range.end - range.start))
+    }
+    /// True with a probability of `basis_points` out of 10000
+    pub fn bernoulli(&mut self, basis_points: u64) -> bool {
+        self.below(10000) < basis_points
+    }
+    /// Fisher-Yates shuffle, swapping each position from the last one down with a draw among the positions before it
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+    /// Index drawn with a probability proportional to its weight, the weights must not all be zero
+    pub fn weighted_choice(&mut self, weights: &[u64]) -> Result<usize, ProgramError> {
+        let total = weights.iter().try_fold(0u64, |total, weight| total.try_add(*weight))?;
+        if total == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let mut draw = self.below(total);
+        for (index, weight) in weights.iter().enumerate() {
+            if draw < *weight {
+                return Ok(index);
+            }
+            draw -= weight;
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::program_error::ProgramError;
+
+    use super::Rng;
+
+    fn seed() -> Vec<u8> {
+        (0..64).collect()
+    }
+
+    #[test]
+    fn test_rng_vectors() {
+        let seed = seed();
+        let mut bytes = [0; 40];
+        Rng::new(&seed).fill_bytes(&mut bytes);
+        assert_eq!(
+            bytes.to_vec(),
+            hex("d17cf353aed507c38b1a44f6675bd28bb49767fd7717e0ee21c0eea3723d56dd819f5a98ce1e2623")
+        );
+        let mut rng = Rng::new(&seed);
+        let draws: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(draws, [14053436106942414033, 10075215818452638347, 17212783579930007476]);
+
+        let mut rng = Rng::new(&seed);
+        let draws: Vec<u64> = (0..5).map(|_| rng.uniform(0..10000).unwrap()).collect();
+        assert_eq!(draws, [4033, 8347, 7476, 9313, 4849]);
+        let draws: Vec<u64> = (0..5).map(|_| rng.uniform(1..7).unwrap()).collect();
+        assert_eq!(draws, [5, 6, 2, 5, 2]);
+        assert_eq!(rng.uniform(0..u64::MAX).unwrap(), 349428172528561902);
+
+        let mut rng = Rng::new(&seed);
+        let draws: Vec<bool> = (0..8).map(|_| rng.bernoulli(5000)).collect();
+        assert_eq!(draws, [true, false, false, false, true, false, false, true]);
+
+        let mut items: Vec<u32> = (0..10).collect();
+        Rng::new(&seed).shuffle(&mut items);
+        assert_eq!(items, [2, 0, 7, 1, 9, 5, 6, 4, 8, 3]);
+
+        let mut rng = Rng::new(&seed);
+        let draws: Vec<usize> = (0..8).map(|_| rng.weighted_choice(&[1, 0, 3, 6]).unwrap()).collect();
+        assert_eq!(draws, [2, 3, 3, 2, 3, 3, 3, 3]);
+
+        let zero_seed = [0; 64];
+        let mut rng = Rng::new(&zero_seed);
+        let draws: Vec<u64> = (0..3).map(|_| rng.uniform(0..10000).unwrap()).collect();
+        assert_eq!(draws, [3955, 8494, 6715]);
+    }
+
+    #[test]
+    fn test_rng_rejects_draws_above_the_last_full_range() {
+        let seed = seed();
+        // the first four words of the stream are above 2^63 + 1 and get rejected
+        let mut rng = Rng::new(&seed);
+        let draws: Vec<u64> = (0..4).map(|_| rng.uniform(0..(1 << 63) + 1).unwrap()).collect();
+        assert_eq!(draws, [2532745713110654849, 4054186260189077218, 3652606435885476809, 2606778134478652255]);
+        let mut rng = Rng::new(&seed);
+        let words: Vec<u64> = (0..8).map(|_| rng.next_u64()).collect();
+        assert_eq!(words[4..], draws);
+    }
+
+    #[test]
+    fn test_rng_invalid_arguments() {
+        let seed = seed();
+        let mut rng = Rng::new(&seed);
+        assert!(matches!(rng.uniform(5..5), Err(ProgramError::InvalidArgument)));
+        assert!(matches!(rng.weighted_choice(&[]), Err(ProgramError::InvalidArgument)));
+        assert!(matches!(rng.weighted_choice(&[0, 0]), Err(ProgramError::InvalidArgument)));
+        assert!(rng.weighted_choice(&[u64::MAX, 1]).is_err());
+        assert!(!rng.bernoulli(0));
+        assert!(rng.bernoulli(10000));
+        let mut items: [u8; 0] = [];
+        rng.shuffle(&mut items);
+        let mut items = [1];
+        rng.shuffle(&mut items);
+        assert_eq!(items, [1]);
+    }
+
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+}
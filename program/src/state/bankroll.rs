@@ -0,0 +1,325 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankAccount;
+use solana_program::{
+    account_info::AccountInfo, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar,
+};
+
+use crate::{
+    checks::{check_is_writable, check_pda_cannonical_bump},
+    math::{mul_div, CheckedMath},
+};
+
+use super::{BettingAccount, StateAccountType};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct GameBankroll {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub game: Pubkey,
+    pub host: Pubkey,
+    // lamports available to cover new bets
+    pub current_lamports: u64,
+    // lamports locked by unresolved bets
+    pub locked_lamports: u64,
+    pub lamports_funded: u64,
+    pub lamports_defunded: u64,
+    // shares issued to the stakers of the bankroll
+    pub total_shares: u64,
+    // basis points of the bankroll winnings taken by the host
+    pub management_fee: u64,
+    pub unclaimed_fee_lamports: u64,
+}
+
+impl GameBankroll {
+    pub fn new(game: Pubkey, host: Pubkey) -> Self {
+        Self {
+            account_type: StateAccountType::GameBankroll,
+            version: Self::VERSION,
+            game,
+            host,
+            current_lamports: 0,
+            locked_lamports: 0,
+            lamports_funded: 0,
+            lamports_defunded: 0,
+            total_shares: 0,
+            management_fee: 0,
+            unclaimed_fee_lamports: 0,
+        }
+    }
+    /// Net asset value of the bankroll, the lamports locked by unresolved bets included
+    pub fn total_lamports(&self) -> Result<u64, ProgramError> {
+        self.current_lamports.try_add(self.locked_lamports)
+    }
+    /// Whether unresolved bets still lock lamports, the net asset value is only final once they are settled
+    pub fn has_pending_liability(&self) -> bool {
+        self.locked_lamports > 0
+    }
+    /// Shares minted for a deposit at the current net asset value, `None` if the deposit is worth no share
+    pub fn shares_for_lamports(&self, lamports: u64) -> Result<Option<u64>, ProgramError> {
+        if self.total_shares == 0 {
+            return Ok(Some(lamports).filter(|shares| *shares > 0));
+        }
+        let total_lamports = self.total_lamports()?;
+        if total_lamports == 0 {
+            return Ok(None);
+        }
+        Ok(Some(mul_div(lamports, self.total_shares, total_lamports)?).filter(|shares| *shares > 0))
+    }
+    /// Lamports redeemed by burning shares at the current net asset value
+    pub fn lamports_for_shares(&self, shares: u64) -> Result<u64, ProgramError> {
+        if self.total_shares == 0 {
+            return Ok(0);
+        }
+        mul_div(shares, self.total_lamports()?, self.total_shares)
+    }
+    /// Release the lamports locked by a resolved bet, taking the management fee on the bankroll winnings
+    pub fn settle(&mut self, locked_lamports: u64, host_gain: u64) -> Result<(), ProgramError> {
+        self.locked_lamports = self.locked_lamports.try_sub(locked_lamports)?;
+        let fee = mul_div(host_gain.saturating_sub(locked_lamports), self.management_fee, 10000)?;
+        self.unclaimed_fee_lamports = self.unclaimed_fee_lamports.try_add(fee)?;
+        self.current_lamports = self.current_lamports.try_add(host_gain.try_sub(fee)?)?;
+        Ok(())
+    }
+    /// Deserialize the bankroll PDA of a game, creating it first if it does not exist yet
+    pub fn load_or_create<'a>(
+        game: &Pubkey,
+        host: &Pubkey,
+        game_bankroll_account_info: &AccountInfo<'a>,
+        payer_account_info: &AccountInfo<'a>,
+    ) -> Result<Self, ProgramError> {
+        check_is_writable(game_bankroll_account_info)?;
+        let game_bankroll_pda_bump = check_pda_cannonical_bump(game_bankroll_account_info, &[b"GameBankroll".as_ref(), game.as_ref()])?;
+        if !game_bankroll_account_info.data_is_empty() {
+            return Self::try_from_account_info(game_bankroll_account_info);
+        }
+
+        let game_bankroll_state = Self::new(*game, *host);
+        let game_bankroll_data = game_bankroll_state.try_to_vec()?;
+        let game_bankroll_data_len = game_bankroll_data.len();
+        let min_rent = Rent::get()?.minimum_balance(game_bankroll_data_len);
+        let game_bankroll_pda_signer_seeds = &[b"GameBankroll".as_ref(), game.as_ref(), &[game_bankroll_pda_bump]];
+        let game_bankroll_create_ix = system_instruction::create_account(
+            payer_account_info.key,
+            game_bankroll_account_info.key,
+            min_rent,
+            game_bankroll_data_len.try_into().unwrap(),
+            &crate::id(),
+        );
+        invoke_signed(
+            &game_bankroll_create_ix,
+            &[payer_account_info.clone(), game_bankroll_account_info.clone()],
+            &[game_bankroll_pda_signer_seeds],
+        )?;
+        game_bankroll_account_info.data.borrow_mut().copy_from_slice(&game_bankroll_data);
+
+        Ok(game_bankroll_state)
+    }
+}
+impl BettingAccount for GameBankroll {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::GameBankroll;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct BankrollShare {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub game_bankroll: Pubkey,
+    pub owner: Pubkey,
+    pub shares: u64,
+    pub lamports_deposited: u64,
+    pub lamports_withdrew: u64,
+}
+
+impl BankrollShare {
+    pub fn new(game_bankroll: Pubkey, owner: Pubkey) -> Self {
+        Self {
+            account_type: StateAccountType::BankrollShare,
+            version: Self::VERSION,
+            game_bankroll,
+            owner,
+            shares: 0,
+            lamports_deposited: 0,
+            lamports_withdrew: 0,
+        }
+    }
+    /// Deserialize the share ledger PDA of a staker, creating it first if it does not exist yet
+    pub fn load_or_create<'a>(
+        game_bankroll: &Pubkey,
+        owner: &Pubkey,
+        bankroll_share_account_info: &AccountInfo<'a>,
+        payer_account_info: &AccountInfo<'a>,
+    ) -> Result<Self, ProgramError> {
+        check_is_writable(bankroll_share_account_info)?;
+        let bankroll_share_pda_bump = check_pda_cannonical_bump(
+            bankroll_share_account_info,
+            &[b"BankrollShare".as_ref(), game_bankroll.as_ref(), owner.as_ref()],
+        )?;
+        if !bankroll_share_account_info.data_is_empty() {
+            return Self::try_from_account_info(bankroll_share_account_info);
+        }
+
+        let bankroll_share_state = Self::new(*game_bankroll, *owner);
+        let bankroll_share_data = bankroll_share_state.try_to_vec()?;
+        let bankroll_share_data_len = bankroll_share_data.len();
+        let min_rent = Rent::get()?.minimum_balance(bankroll_share_data_len);
+        let bankroll_share_pda_signer_seeds = &[b"BankrollShare".as_ref(), game_bankroll.as_ref(), owner.as_ref(), &[bankroll_share_pda_bump]];
+        let bankroll_share_create_ix = system_instruction::create_account(
+            payer_account_info.key,
+            bankroll_share_account_info.key,
+            min_rent,
+            bankroll_share_data_len.try_into().unwrap(),
+            &crate::id(),
+        );
+        invoke_signed(
+            &bankroll_share_create_ix,
+            &[payer_account_info.clone(), bankroll_share_account_info.clone()],
+            &[bankroll_share_pda_signer_seeds],
+        )?;
+        bankroll_share_account_info.data.borrow_mut().copy_from_slice(&bankroll_share_data);
+
+        Ok(bankroll_share_state)
+    }
+}
+impl BettingAccount for BankrollShare {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::BankrollShare;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::error::BettingError;
+
+    use super::GameBankroll;
+
+    #[derive(Clone, Debug)]
+    enum Action {
+        Fund(u64),
+        Defund(u64),
+        Lock(u64),
+        Settle { host_gain: u64 },
+    }
+
+    fn action_strategy() -> impl Strategy<Value = Action> {
+        let lamports = prop_oneof![1..1_000_000u64, Just(u64::MAX / 2), Just(u64::MAX)];
+        prop_oneof![
+            lamports.clone().prop_map(Action::Fund),
+            lamports.clone().prop_map(Action::Defund),
+            lamports.clone().prop_map(Action::Lock),
+            lamports.prop_map(|host_gain| Action::Settle { host_gain }),
+        ]
+    }
+
+    /// Apply an action the way the instructions do, every arithmetic failure must surface as `MathOverflow`
+    fn apply(game_bankroll: &mut GameBankroll, locks: &mut Vec<u64>, action: &Action) -> Result<(), ProgramError> {
+        use crate::math::CheckedMath;
+        match *action {
+            Action::Fund(_) | Action::Defund(_) if game_bankroll.has_pending_liability() => {}
+            Action::Fund(lamports) => {
+                if let Some(shares) = game_bankroll.shares_for_lamports(lamports)? {
+                    let total_shares = game_bankroll.total_shares.try_add(shares)?;
+                    game_bankroll.current_lamports = game_bankroll.current_lamports.try_add(lamports)?;
+                    game_bankroll.total_shares = total_shares;
+                }
+            }
+            Action::Defund(shares) => {
+                let shares = shares.min(game_bankroll.total_shares);
+                let lamports = game_bankroll.lamports_for_shares(shares)?;
+                if lamports <= game_bankroll.current_lamports {
+                    game_bankroll.current_lamports -= lamports;
+                    game_bankroll.total_shares -= shares;
+                }
+            }
+            Action::Lock(lamports) => {
+                if lamports <= game_bankroll.current_lamports {
+                    game_bankroll.locked_lamports = game_bankroll.locked_lamports.try_add(lamports)?;
+                    game_bankroll.current_lamports -= lamports;
+                    locks.push(lamports);
+                }
+            }
+            Action::Settle { host_gain } => {
+                if let Some(&locked_lamports) = locks.last() {
+                    game_bankroll.settle(locked_lamports, host_gain)?;
+                    locks.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_game_bankroll_state_transitions_never_panic(actions in prop::collection::vec(action_strategy(), 1..50), management_fee in 0..=2000u64) {
+            let mut game_bankroll = GameBankroll::new(Pubkey::new_unique(), Pubkey::new_unique());
+            game_bankroll.management_fee = management_fee;
+            let mut locks = vec![];
+            for action in &actions {
+                let before = game_bankroll.clone();
+                match apply(&mut game_bankroll, &mut locks, action) {
+                    Ok(()) => {}
+                    Err(err) => {
+                        prop_assert_eq!(err, BettingError::MathOverflow.into());
+                        game_bankroll = before;
+                    }
+                }
+                // the locked lamports always cover the unresolved bets
+                prop_assert_eq!(game_bankroll.locked_lamports, locks.iter().sum::<u64>());
+                // redeeming every share never pays more than the bankroll holds
+                if let Ok(total_lamports) = game_bankroll.total_lamports() {
+                    prop_assert!(game_bankroll.lamports_for_shares(game_bankroll.total_shares).unwrap() <= total_lamports);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_bankroll_shares() {
+        let mut game_bankroll = GameBankroll::new(Pubkey::new_unique(), Pubkey::new_unique());
+        // the first deposit is minted one share per lamport
+        assert_eq!(game_bankroll.shares_for_lamports(0).unwrap(), None);
+        assert_eq!(game_bankroll.shares_for_lamports(6000).unwrap(), Some(6000));
+        game_bankroll.current_lamports = 6000;
+        game_bankroll.total_shares = 6000;
+        // the winnings accrue to the share value
+        game_bankroll.current_lamports = 10000;
+        game_bankroll.locked_lamports = 2000;
+        assert_eq!(game_bankroll.shares_for_lamports(3000).unwrap(), Some(1500));
+        assert_eq!(game_bankroll.shares_for_lamports(1).unwrap(), None);
+        assert_eq!(game_bankroll.lamports_for_shares(1500).unwrap(), 3000);
+        // a wiped out bankroll does not mint shares
+        game_bankroll.current_lamports = 0;
+        game_bankroll.locked_lamports = 0;
+        assert_eq!(game_bankroll.shares_for_lamports(3000).unwrap(), None);
+        assert_eq!(game_bankroll.lamports_for_shares(1500).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_game_bankroll_settle() {
+        let mut game_bankroll = GameBankroll::new(Pubkey::new_unique(), Pubkey::new_unique());
+        game_bankroll.management_fee = 1000;
+        game_bankroll.locked_lamports = 4000;
+        // the host wins the wager of 2000
+        game_bankroll.settle(1980, 1980 + 2000).unwrap();
+        assert_eq!(game_bankroll.locked_lamports, 2020);
+        assert_eq!(game_bankroll.unclaimed_fee_lamports, 200);
+        assert_eq!(game_bankroll.current_lamports, 3780);
+        // the host loses, no fee is taken
+        game_bankroll.settle(2020, 0).unwrap();
+        assert_eq!(game_bankroll.locked_lamports, 0);
+        assert_eq!(game_bankroll.unclaimed_fee_lamports, 200);
+        assert_eq!(game_bankroll.current_lamports, 3780);
+    }
+}
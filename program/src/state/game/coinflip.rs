@@ -1,9 +1,14 @@
-use std::convert::TryInto;
-
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::program_error::{self, ProgramError};
+use solana_program::{
+    msg,
+    program_error::{self, ProgramError},
+};
 
-use crate::state::{user_account::UserAccount, vrf_result::VrfResult};
+use crate::{
+    math::{mul_div, CheckedMath},
+    rng::Rng,
+    state::{bankroll::GameBankroll, randomness::RandomnessSource, user_account::UserAccount, vrf_result::VrfResult},
+};
 
 use super::{BetInput, CheckBetInput, Game, GameTypeConfig, ProcessVrfResult};
 
@@ -24,9 +29,20 @@ pub enum CoinFlipSide {
     Tail,
 }
 
+impl CoinFlipConfig {
+    /// The host advantage is taken on both sides of the middle draw, it can not exceed the half of the draws
+    pub fn check_config(&self) -> Result<(), ProgramError> {
+        if self.host_probability_advantage > 5000 {
+            msg!("Host probability advantage must be at most 5000");
+            Err(ProgramError::InvalidArgument)
+        } else {
+            Ok(())
+        }
+    }
+}
 impl CheckBetInput for CoinFlipInput {
     fn check_bet_input(&self, game: &Game) -> Result<(), program_error::ProgramError> {
-        if !matches!(game.game_type_config, GameTypeConfig::CoinFlip { .. })
+        if !matches!(game.game_type_config()?, GameTypeConfig::CoinFlip { .. })
             || self.wager < game.common_config.min_wager
             || self.wager > game.common_config.max_wager
         {
@@ -44,10 +60,10 @@ impl CheckBetInput for CoinFlipInput {
         }
     }
 
-    fn check_host_balance(&self, game: &Game, user_account: &UserAccount) -> Result<u64, ProgramError> {
-        if let GameTypeConfig::CoinFlip { config } = game.game_type_config {
-            let payout_if_bettor_win = self.wager * config.payout_rate / 10000;
-            if user_account.current_lamports >= payout_if_bettor_win {
+    fn check_host_balance(&self, game: &Game, bankroll: &GameBankroll) -> Result<u64, ProgramError> {
+        if let GameTypeConfig::CoinFlip { config } = game.game_type_config()? {
+            let payout_if_bettor_win = mul_div(self.wager, config.payout_rate, 10000)?;
+            if bankroll.current_lamports >= payout_if_bettor_win {
                 Ok(payout_if_bettor_win)
             } else {
                 Err(ProgramError::InsufficientFunds)
@@ -58,30 +74,23 @@ impl CheckBetInput for CoinFlipInput {
     }
 }
 impl ProcessVrfResult for CoinFlipConfig {
-    fn process_vrf_result(&self, vrf_result: &VrfResult) -> Result<(u64, u64), ProgramError> {
+    fn process_vrf_result(&self, vrf_result: &VrfResult, randomness: &dyn RandomnessSource) -> Result<(u64, u64), ProgramError> {
         self.check_vrf_result(vrf_result)?;
         if let BetInput::CoinFlip {
             input: CoinFlipInput { wager: _, side },
         } = vrf_result.bet_input
         {
-            let mut rand_bytes: [u8; 16] = Default::default();
-            rand_bytes.copy_from_slice(&vrf_result.beta[0..16]);
-            let rand_number: u64 = (u128::from_le_bytes(rand_bytes) % 10000).try_into().unwrap();
-            match side {
-                CoinFlipSide::Head => {
-                    if rand_number < 5000 - self.host_probability_advantage {
-                        Ok((0, vrf_result.locked_bettor_lamports + vrf_result.locked_host_lamports))
-                    } else {
-                        Ok((vrf_result.locked_bettor_lamports + vrf_result.locked_host_lamports, 0))
-                    }
-                }
-                CoinFlipSide::Tail => {
-                    if rand_number > 5000 + self.host_probability_advantage {
-                        Ok((0, vrf_result.locked_bettor_lamports + vrf_result.locked_host_lamports))
-                    } else {
-                        Ok((vrf_result.locked_bettor_lamports + vrf_result.locked_host_lamports, 0))
-                    }
-                }
+            // the host keeps the draws within its advantage of the middle on either side
+            let rand_number = Rng::new(&randomness.seed(vrf_result)?).uniform(0..10000)?;
+            let locked_lamports = vrf_result.locked_bettor_lamports.try_add(vrf_result.locked_host_lamports)?;
+            let is_bettor_win = match side {
+                CoinFlipSide::Head => rand_number < 5000u64.try_sub(self.host_probability_advantage)?,
+                CoinFlipSide::Tail => rand_number > 5000u64.try_add(self.host_probability_advantage)?,
+            };
+            if is_bettor_win {
+                Ok((0, locked_lamports))
+            } else {
+                Ok((locked_lamports, 0))
             }
         } else {
             Err(ProgramError::InvalidArgument)
@@ -94,17 +103,35 @@ mod test {
 
     use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
-    use crate::state::{
-        game::{
-            crash::{CrashConfig, CrashInput},
-            BetInput, CheckBetInput, Game, GameTypeConfig, ProcessVrfResult,
+    use crate::{
+        error::BettingError,
+        state::{
+            bankroll::GameBankroll,
+            game::{
+                crash::{CrashConfig, CrashInput},
+                BetInput, CheckBetInput, Game, GameTypeConfig, ProcessVrfResult,
+            },
+            user_account::UserAccount,
+            vrf_result::VrfResult,
         },
-        user_account::UserAccount,
-        vrf_result::VrfResult,
     };
 
     use super::{CoinFlipConfig, CoinFlipInput, CoinFlipSide};
 
+    #[test]
+    fn test_coinflip_check_config() {
+        let config = CoinFlipConfig {
+            host_probability_advantage: 5000,
+            payout_rate: 9900,
+        };
+        assert!(config.check_config().is_ok());
+        let config = CoinFlipConfig {
+            host_probability_advantage: 5001,
+            payout_rate: 9900,
+        };
+        assert!(matches!(config.check_config().unwrap_err(), ProgramError::InvalidArgument));
+    }
+
     #[test]
     fn test_coinflip_check_bet_input() {
         let input = CoinFlipInput {
@@ -210,15 +237,15 @@ mod test {
                 },
             },
         );
-        let mut user_account = UserAccount::new(Pubkey::new_unique(), None, None);
-        user_account.current_lamports = 5000;
+        let mut bankroll = GameBankroll::new(Pubkey::new_unique(), Pubkey::new_unique());
+        bankroll.current_lamports = 5000;
 
         // ok
-        assert_eq!(input.check_host_balance(&game, &user_account).unwrap(), 2000 * 9900 / 10000);
+        assert_eq!(input.check_host_balance(&game, &bankroll).unwrap(), 2000 * 9900 / 10000);
         // not enough money
-        user_account.current_lamports = 1;
+        bankroll.current_lamports = 1;
         assert!(matches!(
-            input.check_host_balance(&game, &user_account).unwrap_err(),
+            input.check_host_balance(&game, &bankroll).unwrap_err(),
             ProgramError::InsufficientFunds
         ));
         // wrong game type
@@ -232,10 +259,24 @@ mod test {
                 },
             },
         );
-        assert!(matches!(
-            input.check_host_balance(&game, &user_account).unwrap_err(),
-            ProgramError::InvalidArgument
-        ));
+        assert!(matches!(input.check_host_balance(&game, &bankroll).unwrap_err(), ProgramError::InvalidArgument));
+        // payout beyond the lamport supply
+        let input = CoinFlipInput {
+            wager: u64::MAX,
+            side: CoinFlipSide::Head,
+        };
+        let game = Game::new(
+            Pubkey::new_unique(),
+            1000,
+            u64::MAX,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 20000,
+                },
+            },
+        );
+        assert_eq!(input.check_host_balance(&game, &bankroll).unwrap_err(), BettingError::MathOverflow.into());
     }
     #[test]
     fn test_coinflip_process_vrf() {
@@ -258,14 +299,14 @@ mod test {
             },
         );
         vrf_result.is_fullfilled = true;
-        // bettor win head
-        vrf_result.beta[0..16].copy_from_slice(&2000_u128.to_le_bytes());
-        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result).unwrap();
+        // bettor win head, the first draw of the stream is 1324
+        vrf_result.beta[0..16].copy_from_slice(&8000_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
         assert_eq!(host_gain, 0);
         assert_eq!(bettor_gain, 4000);
-        // bettor lose head with host probability advantage
-        vrf_result.beta[0..16].copy_from_slice(&4901_u128.to_le_bytes());
-        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result).unwrap();
+        // bettor lose head with host probability advantage, drawing 4985
+        vrf_result.beta[0..16].copy_from_slice(&62_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
         assert_eq!(host_gain, 4000);
         assert_eq!(bettor_gain, 0);
         // bettor lose tail with host probability advantage
@@ -275,16 +316,43 @@ mod test {
                 side: CoinFlipSide::Tail,
             },
         };
-        vrf_result.beta[0..16].copy_from_slice(&5100_u128.to_le_bytes());
-        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result).unwrap();
+        // drawing 5035
+        vrf_result.beta[0..16].copy_from_slice(&61_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
         assert_eq!(host_gain, 4000);
         assert_eq!(bettor_gain, 0);
-        // bettor win tail
-        vrf_result.beta[0..16].copy_from_slice(&8000_u128.to_le_bytes());
-        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result).unwrap();
+        // bettor win tail, drawing 5679
+        vrf_result.beta[0..16].copy_from_slice(&2000_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
         assert_eq!(host_gain, 0);
         assert_eq!(bettor_gain, 4000);
 
+        // the draws at the edges of the host advantage go to the host on both sides
+        let game_config = CoinFlipConfig {
+            host_probability_advantage: 35,
+            payout_rate: 9900,
+        };
+        // drawing 5035
+        vrf_result.beta[0..16].copy_from_slice(&61_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
+        assert_eq!(host_gain, 4000);
+        assert_eq!(bettor_gain, 0);
+        let game_config = CoinFlipConfig {
+            host_probability_advantage: 15,
+            payout_rate: 9900,
+        };
+        vrf_result.bet_input = BetInput::CoinFlip {
+            input: CoinFlipInput {
+                wager: 2000,
+                side: CoinFlipSide::Head,
+            },
+        };
+        // drawing 4985
+        vrf_result.beta[0..16].copy_from_slice(&62_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
+        assert_eq!(host_gain, 4000);
+        assert_eq!(bettor_gain, 0);
+
         // wrong input type
         vrf_result.bet_input = BetInput::Crash {
             input: CrashInput {
@@ -293,7 +361,7 @@ mod test {
             },
         };
         assert!(matches!(
-            game_config.process_vrf_result(&vrf_result).unwrap_err(),
+            game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap_err(),
             ProgramError::InvalidArgument
         ));
     }
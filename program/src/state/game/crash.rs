@@ -1,7 +1,11 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::program_error::ProgramError;
 
-use crate::state::{user_account::UserAccount, vrf_result::VrfResult};
+use crate::{
+    math::CheckedMath,
+    rng::Rng,
+    state::{bankroll::GameBankroll, randomness::RandomnessSource, user_account::UserAccount, vrf_result::VrfResult},
+};
 
 use super::{BetInput, CheckBetInput, Game, GameTypeConfig, ProcessVrfResult};
 
@@ -17,7 +21,7 @@ pub struct CrashInput {
 
 impl CheckBetInput for CrashInput {
     fn check_bet_input(&self, game: &Game) -> Result<(), ProgramError> {
-        if !matches!(game.game_type_config, GameTypeConfig::Crash { .. })
+        if !matches!(game.game_type_config()?, GameTypeConfig::Crash { .. })
             || self.wager < game.common_config.min_wager
             || self.wager > game.common_config.max_wager
         {
@@ -35,10 +39,10 @@ impl CheckBetInput for CrashInput {
         }
     }
 
-    fn check_host_balance(&self, _game: &Game, user_account: &UserAccount) -> Result<u64, ProgramError> {
+    fn check_host_balance(&self, _game: &Game, bankroll: &GameBankroll) -> Result<u64, ProgramError> {
         let target_f = self.target_multiplier as f64 / 100.0;
         let payout_if_bettor_win = (self.wager as f64 * target_f).floor() as u64;
-        if user_account.current_lamports >= payout_if_bettor_win {
+        if bankroll.current_lamports >= payout_if_bettor_win {
             Ok(payout_if_bettor_win)
         } else {
             Err(ProgramError::InsufficientFunds)
@@ -46,29 +50,23 @@ impl CheckBetInput for CrashInput {
     }
 }
 impl ProcessVrfResult for CrashConfig {
-    fn process_vrf_result(&self, vrf_result: &VrfResult) -> Result<(u64, u64), ProgramError> {
+    fn process_vrf_result(&self, vrf_result: &VrfResult, randomness: &dyn RandomnessSource) -> Result<(u64, u64), ProgramError> {
         self.check_vrf_result(vrf_result)?;
         if let BetInput::Crash {
             input: CrashInput { target_multiplier, wager: _ },
         } = vrf_result.bet_input
         {
-            let target_f = target_multiplier as f64 / 100.0;
-            // get random number
-            let mut rand_bytes: [u8; 16] = Default::default();
-            rand_bytes.copy_from_slice(&vrf_result.beta[0..16]);
-            let rand_number: u128 = u128::from_le_bytes(rand_bytes);
-            // calculate multiplier
-            let divisor = (1.0 / (self.multiplier_straight_one_possibility as f64 / 10000.0)) as u128;
-            let multiplier = if rand_number % divisor == 0 {
-                1.0
-            } else {
-                ((100.0 * u64::MAX as f64 - rand_number as f64) / (u64::MAX as f64 - rand_number as f64)) / 100.0
-            };
+            // the game busts at 1.00x with the configured possibility, otherwise the multiplier reaches a target above
+            // 1.00x with a probability of 0.99 / (target - 0.01)
+            let seed = randomness.seed(vrf_result)?;
+            let mut rng = Rng::new(&seed);
+            let is_bust = rng.bernoulli(self.multiplier_straight_one_possibility);
+            let is_hit = target_multiplier <= 100 || (!is_bust && rng.uniform(0..target_multiplier - 1)? < 99);
             // result
-            if target_f <= multiplier {
+            if is_hit {
                 Ok((vrf_result.locked_bettor_lamports, vrf_result.locked_host_lamports))
             } else {
-                Ok((vrf_result.locked_host_lamports + vrf_result.locked_bettor_lamports, 0))
+                Ok((vrf_result.locked_host_lamports.try_add(vrf_result.locked_bettor_lamports)?, 0))
             }
         } else {
             Err(ProgramError::InvalidArgument)
@@ -81,6 +79,7 @@ mod test {
     use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
     use crate::state::{
+        bankroll::GameBankroll,
         game::{
             coinflip::{CoinFlipConfig, CoinFlipInput, CoinFlipSide::Head},
             BetInput, CheckBetInput, Game, GameTypeConfig, ProcessVrfResult,
@@ -191,15 +190,15 @@ mod test {
                 },
             },
         );
-        let mut user_account = UserAccount::new(Pubkey::new_unique(), None, None);
-        user_account.current_lamports = 5000;
+        let mut bankroll = GameBankroll::new(Pubkey::new_unique(), Pubkey::new_unique());
+        bankroll.current_lamports = 5000;
 
         // ok
-        assert_eq!(input.check_host_balance(&game, &user_account).unwrap(), 1200);
+        assert_eq!(input.check_host_balance(&game, &bankroll).unwrap(), 1200);
         // not enough money
-        user_account.current_lamports = 1;
+        bankroll.current_lamports = 1;
         assert!(matches!(
-            input.check_host_balance(&game, &user_account).unwrap_err(),
+            input.check_host_balance(&game, &bankroll).unwrap_err(),
             ProgramError::InsufficientFunds
         ));
     }
@@ -226,15 +225,28 @@ mod test {
         vrf_result.is_fullfilled = true;
 
         // target multiplier hit
-        vrf_result.beta[0..16].copy_from_slice(&(u64::MAX as u128 / 2).to_le_bytes());
-        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result).unwrap();
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
         assert_eq!(host_gain, 1000);
         assert_eq!(bettor_gain, 1200);
         // target multiplier miss
-        vrf_result.beta[0..16].copy_from_slice(&(u64::MAX as u128 / 100).to_le_bytes());
-        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result).unwrap();
+        vrf_result.beta[0..16].copy_from_slice(&2_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
+        assert_eq!(host_gain, 2200);
+        assert_eq!(bettor_gain, 0);
+        // instant bust, only a 1.00x target survives it
+        vrf_result.beta[0..16].copy_from_slice(&35_u128.to_le_bytes());
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
         assert_eq!(host_gain, 2200);
         assert_eq!(bettor_gain, 0);
+        vrf_result.bet_input = BetInput::Crash {
+            input: CrashInput {
+                target_multiplier: 100,
+                wager: 1000,
+            },
+        };
+        let (host_gain, bettor_gain) = game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap();
+        assert_eq!(host_gain, 1000);
+        assert_eq!(bettor_gain, 1200);
         // wrong input type
         let mut vrf_result = VrfResult::new(
             Pubkey::new_unique(),
@@ -249,7 +261,7 @@ mod test {
         );
         vrf_result.is_fullfilled = true;
         assert!(matches!(
-            game_config.process_vrf_result(&vrf_result).unwrap_err(),
+            game_config.process_vrf_result(&vrf_result, &vrf_result.randomness).unwrap_err(),
             ProgramError::InvalidArgument
         ));
     }
@@ -1,50 +1,193 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
 use shank::ShankAccount;
-use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program::{hash::hashv, msg, program_error::ProgramError, pubkey::Pubkey};
 
-use crate::error::BettingError;
+use crate::{
+    constants::{COMMISSION_OPERATOR_SHARE, MAX_GAME_TYPE_CONFIG_LEN, MAX_ORACLES},
+    error::BettingError,
+    math::{mul_div, CheckedMath},
+};
 
 use self::{
     coinflip::{CoinFlipConfig, CoinFlipInput},
     crash::{CrashConfig, CrashInput},
 };
 
-use super::{user_account::UserAccount, vrf_result::VrfResult, BettingAccount, StateAccountType};
+use super::{
+    bankroll::GameBankroll,
+    impl_pod_borsh,
+    legacy::{GameV0, GameV1, GameV2, GameV3},
+    randomness::{
+        check_hash_chain_link, Randomness, RandomnessSource, RandomnessSourceConfig, RANDOMNESS_SOURCE_HASH_CHAIN, RANDOMNESS_SOURCE_ORACLES,
+        RANDOMNESS_SOURCE_VRF,
+    },
+    unique_legacy_layout,
+    user_account::UserAccount,
+    vrf_result::VrfResult,
+    BettingAccount, StateAccountType, ZeroCopyAccount,
+};
 
 pub mod coinflip;
 pub mod crash;
 
-#[derive(BorshDeserialize, BorshSerialize, Clone, ShankAccount)]
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, ShankAccount)]
 pub struct Game {
-    pub account_type: StateAccountType,
-    pub host: Pubkey,
-    pub is_active: bool,
+    pub account_type: u8,
+    pub version: u8,
+    pub bump: u8,
+    pub active: u8,
     pub unresolved_vrf_result: u32,
+    pub host: Pubkey,
     pub total_lamports_in: u64,
     pub total_lamports_out: u64,
+    // basis points of the bettor's net win charged at resolve time
+    pub commission: u64,
     pub common_config: CommonGameConfig,
-    pub game_type_config: GameTypeConfig,
+    // borsh encoding of the game type config, also the last seed of the game PDA
+    pub game_type_config_len: u8,
+    // shank only reads literal lengths, MAX_GAME_TYPE_CONFIG_LEN
+    pub game_type_config_bytes: [u8; 31],
+    pub randomness_source: u8,
+    pub oracle_threshold: u8,
+    pub padding: [u8; 2],
+    // lamports of the bankroll escrowed with every bet and paid to whoever resolves it
+    pub crank_tip_lamports: u32,
+    // hash chain links handed to bets and revealed so far, the head is the last revealed server seed
+    pub hash_chain_assigned: u32,
+    pub hash_chain_revealed: u32,
+    pub hash_chain_head: [u8; 32],
 }
 
 impl Game {
     pub fn new(host: Pubkey, min_wager: u64, max_wager: u64, game_type_config: GameTypeConfig) -> Self {
+        let mut game = Self::new_with_bump(host, min_wager, max_wager, game_type_config, 0);
+        game.bump = Pubkey::find_program_address(&game.seeds(), &crate::id()).1;
+        game
+    }
+    pub fn new_with_bump(host: Pubkey, min_wager: u64, max_wager: u64, game_type_config: GameTypeConfig, bump: u8) -> Self {
+        let mut game_type_config_bytes = [0; MAX_GAME_TYPE_CONFIG_LEN];
+        let mut writer = &mut game_type_config_bytes[..];
+        // the configs of all game types fit the fixed field
+        game_type_config.serialize(&mut writer).unwrap();
+        let game_type_config_len = (MAX_GAME_TYPE_CONFIG_LEN - writer.len()) as u8;
         Self {
-            account_type: StateAccountType::Game,
-            host,
-            is_active: true,
+            account_type: StateAccountType::Game as u8,
+            version: Self::VERSION,
+            bump,
+            active: 1,
             unresolved_vrf_result: 0,
+            host,
             total_lamports_in: 0,
             total_lamports_out: 0,
+            commission: 0,
             common_config: CommonGameConfig { min_wager, max_wager },
-            game_type_config,
+            game_type_config_len,
+            game_type_config_bytes,
+            randomness_source: RANDOMNESS_SOURCE_VRF,
+            oracle_threshold: 0,
+            padding: [0; 2],
+            crank_tip_lamports: 0,
+            hash_chain_assigned: 0,
+            hash_chain_revealed: 0,
+            hash_chain_head: [0; 32],
         }
     }
+    pub fn is_active(&self) -> bool {
+        self.active != 0
+    }
+    pub fn set_active(&mut self, is_active: bool) {
+        self.active = is_active as u8;
+    }
+    pub fn game_type_config(&self) -> Result<GameTypeConfig, ProgramError> {
+        GameTypeConfig::try_from_slice(self.game_type_config_seed()).map_err(|_| ProgramError::InvalidAccountData)
+    }
+    /// The config seeds are their borsh encodings, the common config is laid out the same in memory
+    pub fn common_config_seed(&self) -> &[u8] {
+        bytemuck::bytes_of(&self.common_config)
+    }
+    pub fn game_type_config_seed(&self) -> &[u8] {
+        &self.game_type_config_bytes[..self.game_type_config_len as usize]
+    }
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [b"Game".as_ref(), self.common_config_seed(), self.game_type_config_seed()]
+    }
+    pub fn set_randomness_source(&mut self, config: RandomnessSourceConfig) -> Result<(), ProgramError> {
+        match config {
+            RandomnessSourceConfig::Vrf => self.randomness_source = RANDOMNESS_SOURCE_VRF,
+            RandomnessSourceConfig::HashChain { head } => {
+                self.randomness_source = RANDOMNESS_SOURCE_HASH_CHAIN;
+                self.hash_chain_head = head;
+            }
+            RandomnessSourceConfig::Oracles { threshold } => {
+                if threshold == 0 || threshold as usize > MAX_ORACLES {
+                    msg!("Oracle threshold must be between 1 and {}", MAX_ORACLES);
+                    return Err(ProgramError::from(BettingError::InvalidOracleConfig));
+                }
+                self.randomness_source = RANDOMNESS_SOURCE_ORACLES;
+                self.oracle_threshold = threshold;
+            }
+        }
+        Ok(())
+    }
+    /// Randomness of the next bet, hash chain links are handed out in placement order
+    pub fn next_randomness(&mut self) -> Result<Randomness, ProgramError> {
+        match self.randomness_source {
+            RANDOMNESS_SOURCE_VRF => Ok(Randomness::Vrf),
+            RANDOMNESS_SOURCE_HASH_CHAIN => {
+                let index = self.hash_chain_assigned;
+                self.hash_chain_assigned = index.try_add(1)?;
+                Ok(Randomness::HashChain { index })
+            }
+            RANDOMNESS_SOURCE_ORACLES => Ok(Randomness::Oracles {
+                threshold: self.oracle_threshold,
+                contributions: 0,
+            }),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+    /// Reveal the server seed of a hash chain link, which becomes the head the next links are checked against. Links of
+    /// bets that timed out can be skipped, the server seed then hashes to the head once per skipped link.
+    pub fn reveal_hash_chain_link(&mut self, index: u32, server_seed: &[u8; 32]) -> Result<(), ProgramError> {
+        if self.randomness_source != RANDOMNESS_SOURCE_HASH_CHAIN || index < self.hash_chain_revealed {
+            msg!("Expect a hash chain link from {} to be revealed", self.hash_chain_revealed);
+            return Err(ProgramError::from(BettingError::InvalidHashChainLink));
+        }
+        let mut link = *server_seed;
+        for _ in self.hash_chain_revealed..index {
+            link = hashv(&[&link]).to_bytes();
+        }
+        check_hash_chain_link(&self.hash_chain_head, &link)?;
+        self.hash_chain_head = *server_seed;
+        self.hash_chain_revealed = index.try_add(1)?;
+        Ok(())
+    }
+    /// Commission charged on the net win of a bettor, split into the host and the operator parts
+    pub fn get_commission(&self, bettor_net_win: u64) -> Result<(u64, u64), ProgramError> {
+        let commission = mul_div(bettor_net_win, self.commission, 10000)?;
+        let operator_commission = mul_div(commission, COMMISSION_OPERATOR_SHARE, 10000)?;
+        Ok((commission.try_sub(operator_commission)?, operator_commission))
+    }
 }
+impl_pod_borsh!(Game);
 impl BettingAccount for Game {
     const ACCOUNT_TYPE: StateAccountType = StateAccountType::Game;
+    const VERSION: u8 = 4;
+
+    fn try_from_legacy_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        unique_legacy_layout([
+            GameV3::try_from_slice(data).ok().filter(|v3| v3.version == 3).map(Game::from),
+            GameV2::try_from_slice(data).ok().filter(|v2| v2.version == 2).map(Game::from),
+            GameV1::try_from_slice(data).ok().filter(|v1| v1.version == 1).map(|v1| GameV2::from(v1).into()),
+            GameV0::try_from_slice(data).ok().map(|v0| GameV2::from(v0).into()),
+        ])
+    }
 }
+impl ZeroCopyAccount for Game {}
 
-#[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Pod, Zeroable)]
 pub struct CommonGameConfig {
     pub min_wager: u64,
     pub max_wager: u64,
@@ -61,6 +204,13 @@ impl GameTypeConfig {
             GameTypeConfig::Crash { config } => Box::new(*config),
         }
     }
+    /// Check that the bets of a new game can be resolved with its config
+    pub fn check_config(&self) -> Result<(), ProgramError> {
+        match self {
+            GameTypeConfig::CoinFlip { config } => config.check_config(),
+            GameTypeConfig::Crash { .. } => Ok(()),
+        }
+    }
 }
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
 pub enum BetInput {
@@ -76,7 +226,7 @@ impl BetInput {
     }
 }
 pub trait ProcessVrfResult {
-    fn process_vrf_result(&self, vrf_result: &VrfResult) -> Result<(u64, u64), ProgramError>;
+    fn process_vrf_result(&self, vrf_result: &VrfResult, randomness: &dyn RandomnessSource) -> Result<(u64, u64), ProgramError>;
     fn check_vrf_result(&self, vrf_result: &VrfResult) -> Result<(), ProgramError> {
         if !vrf_result.is_fullfilled {
             Err(ProgramError::from(BettingError::VrfResultNotFullfilled))
@@ -90,5 +240,130 @@ pub trait ProcessVrfResult {
 pub trait CheckBetInput {
     fn check_bet_input(&self, game: &Game) -> Result<(), ProgramError>;
     fn check_bettor_balance(&self, game: &Game, user_account: &UserAccount) -> Result<u64, ProgramError>;
-    fn check_host_balance(&self, game: &Game, user_account: &UserAccount) -> Result<u64, ProgramError>;
+    fn check_host_balance(&self, game: &Game, bankroll: &GameBankroll) -> Result<u64, ProgramError>;
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::pubkey::Pubkey;
+
+    use borsh::BorshSerialize;
+
+    use solana_program::{hash::hashv, program_error::ProgramError};
+
+    use crate::{
+        constants::MAX_GAME_TYPE_CONFIG_LEN,
+        error::BettingError,
+        state::randomness::{Randomness, RandomnessSourceConfig},
+    };
+
+    use super::{coinflip::CoinFlipConfig, crash::CrashConfig, Game, GameTypeConfig};
+
+    #[test]
+    fn test_game_get_commission() {
+        let mut game = Game::new(
+            Pubkey::new_unique(),
+            1000,
+            10000,
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: 100,
+                    payout_rate: 9900,
+                },
+            },
+        );
+        assert_eq!(game.get_commission(1980).unwrap(), (0, 0));
+        game.commission = 500;
+        // 99 lamports of commission, 20% of it to the operator
+        assert_eq!(game.get_commission(1980).unwrap(), (80, 19));
+        assert_eq!(game.get_commission(0).unwrap(), (0, 0));
+        assert_eq!(game.get_commission(u64::MAX).unwrap(), (u64::MAX / 20 - u64::MAX / 20 / 5, u64::MAX / 20 / 5));
+    }
+
+    #[test]
+    fn test_game_type_configs_fit_the_fixed_layout() {
+        let configs = [
+            GameTypeConfig::CoinFlip {
+                config: CoinFlipConfig {
+                    host_probability_advantage: u64::MAX,
+                    payout_rate: u64::MAX,
+                },
+            },
+            GameTypeConfig::Crash {
+                config: CrashConfig {
+                    multiplier_straight_one_possibility: u64::MAX,
+                },
+            },
+        ];
+        for config in configs {
+            let seed = config.try_to_vec().unwrap();
+            assert!(seed.len() <= MAX_GAME_TYPE_CONFIG_LEN);
+            let game = Game::new(Pubkey::new_unique(), 1000, 10000, config);
+            assert_eq!(game.game_type_config_seed(), seed);
+            assert_eq!(game.game_type_config().unwrap().try_to_vec().unwrap(), seed);
+            assert_eq!(game.common_config_seed(), game.common_config.try_to_vec().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_game_randomness_source() {
+        let mut game = Game::new(
+            Pubkey::new_unique(),
+            1000,
+            10000,
+            GameTypeConfig::Crash {
+                config: CrashConfig {
+                    multiplier_straight_one_possibility: 100,
+                },
+            },
+        );
+        assert_eq!(game.next_randomness().unwrap(), Randomness::Vrf);
+        // oracle thresholds must be reachable
+        for threshold in [0, 9] {
+            assert_eq!(
+                game.set_randomness_source(RandomnessSourceConfig::Oracles { threshold }).unwrap_err(),
+                ProgramError::from(BettingError::InvalidOracleConfig)
+            );
+        }
+        game.set_randomness_source(RandomnessSourceConfig::Oracles { threshold: 3 }).unwrap();
+        assert_eq!(
+            game.next_randomness().unwrap(),
+            Randomness::Oracles {
+                threshold: 3,
+                contributions: 0
+            }
+        );
+        // hash chain links are handed out and revealed in order
+        let links: Vec<[u8; 32]> = (0..3)
+            .scan([7; 32], |link, _| {
+                *link = hashv(&[link.as_ref()]).to_bytes();
+                Some(*link)
+            })
+            .collect();
+        game.set_randomness_source(RandomnessSourceConfig::HashChain { head: links[2] }).unwrap();
+        assert_eq!(game.next_randomness().unwrap(), Randomness::HashChain { index: 0 });
+        assert_eq!(game.next_randomness().unwrap(), Randomness::HashChain { index: 1 });
+        assert_eq!(
+            game.reveal_hash_chain_link(1, &links[1]).unwrap_err(),
+            ProgramError::from(BettingError::InvalidHashChainLink)
+        );
+        assert_eq!(
+            game.reveal_hash_chain_link(0, &links[0]).unwrap_err(),
+            ProgramError::from(BettingError::InvalidHashChainLink)
+        );
+        game.reveal_hash_chain_link(0, &links[1]).unwrap();
+        assert_eq!(game.hash_chain_head, links[1]);
+        assert_eq!(game.hash_chain_revealed, 1);
+        // revealed links cannot be revealed again
+        assert_eq!(
+            game.reveal_hash_chain_link(0, &links[1]).unwrap_err(),
+            ProgramError::from(BettingError::InvalidHashChainLink)
+        );
+        // the link of a timed out bet is skipped
+        game.next_randomness().unwrap();
+        let server_seed = [7; 32];
+        game.reveal_hash_chain_link(2, &server_seed).unwrap();
+        assert_eq!(game.hash_chain_head, server_seed);
+        assert_eq!(game.hash_chain_revealed, 3);
+    }
 }
@@ -0,0 +1,1139 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    constants::{MAX_AVATAR_URI_LEN, MAX_USERNAME_LEN},
+    error::BettingError,
+};
+
+use super::{
+    game::{BetInput, CommonGameConfig, Game, GameTypeConfig},
+    impl_pod_borsh,
+    randomness::Randomness,
+    stats::Stats,
+    user_account::UserAccount,
+    vrf_result::VrfResult,
+    BettingAccount, StateAccountType,
+};
+
+// account layouts of previous versions, upgraded to the latest layout on deserialization
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct StatsV0 {
+    pub account_type: StateAccountType,
+    pub total_games: u64,
+    pub total_users: u32,
+    pub total_bets: u32,
+    pub total_wager: u64,
+    pub total_lamports_won_by_bettors: u64,
+    pub total_lamports_deposited: u64,
+    pub total_lamports_withdrew: u64,
+}
+impl From<StatsV0> for StatsV3 {
+    fn from(v0: StatsV0) -> Self {
+        Self {
+            account_type: v0.account_type,
+            version: 3,
+            total_games: v0.total_games,
+            total_users: v0.total_users,
+            total_bets: v0.total_bets,
+            total_wager: v0.total_wager,
+            total_bettor_pnl: v0.total_lamports_won_by_bettors as i64,
+            total_host_pnl: 0,
+            total_lamports_deposited: v0.total_lamports_deposited,
+            total_lamports_withdrew: v0.total_lamports_withdrew,
+            total_host_commission_lamports: 0,
+            total_operator_commission_lamports: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct StatsV1 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub total_games: u64,
+    pub total_users: u32,
+    pub total_bets: u32,
+    pub total_wager: u64,
+    pub total_lamports_won_by_bettors: u64,
+    pub total_lamports_deposited: u64,
+    pub total_lamports_withdrew: u64,
+}
+impl From<StatsV1> for StatsV3 {
+    fn from(v1: StatsV1) -> Self {
+        Self {
+            account_type: v1.account_type,
+            version: 3,
+            total_games: v1.total_games,
+            total_users: v1.total_users,
+            total_bets: v1.total_bets,
+            total_wager: v1.total_wager,
+            total_bettor_pnl: v1.total_lamports_won_by_bettors as i64,
+            total_host_pnl: 0,
+            total_lamports_deposited: v1.total_lamports_deposited,
+            total_lamports_withdrew: v1.total_lamports_withdrew,
+            total_host_commission_lamports: 0,
+            total_operator_commission_lamports: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct StatsV2 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub total_games: u64,
+    pub total_users: u32,
+    pub total_bets: u32,
+    pub total_wager: u64,
+    pub total_lamports_won_by_bettors: u64,
+    pub total_lamports_deposited: u64,
+    pub total_lamports_withdrew: u64,
+    pub total_host_commission_lamports: u64,
+    pub total_operator_commission_lamports: u64,
+}
+// the unsigned total wrapped around whenever a bettor lost, reinterpreting it as signed recovers the net result
+impl From<StatsV2> for StatsV3 {
+    fn from(v2: StatsV2) -> Self {
+        Self {
+            account_type: v2.account_type,
+            version: 3,
+            total_games: v2.total_games,
+            total_users: v2.total_users,
+            total_bets: v2.total_bets,
+            total_wager: v2.total_wager,
+            total_bettor_pnl: v2.total_lamports_won_by_bettors as i64,
+            total_host_pnl: 0,
+            total_lamports_deposited: v2.total_lamports_deposited,
+            total_lamports_withdrew: v2.total_lamports_withdrew,
+            total_host_commission_lamports: v2.total_host_commission_lamports,
+            total_operator_commission_lamports: v2.total_operator_commission_lamports,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct StatsV3 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub total_games: u64,
+    pub total_users: u32,
+    pub total_bets: u32,
+    pub total_wager: u64,
+    pub total_bettor_pnl: i64,
+    pub total_host_pnl: i64,
+    pub total_lamports_deposited: u64,
+    pub total_lamports_withdrew: u64,
+    pub total_host_commission_lamports: u64,
+    pub total_operator_commission_lamports: u64,
+}
+impl From<StatsV3> for Stats {
+    fn from(v3: StatsV3) -> Self {
+        Self {
+            total_games: v3.total_games,
+            total_users: v3.total_users,
+            total_bets: v3.total_bets,
+            total_wager: v3.total_wager,
+            total_bettor_pnl: v3.total_bettor_pnl,
+            total_host_pnl: v3.total_host_pnl,
+            total_lamports_deposited: v3.total_lamports_deposited,
+            total_lamports_withdrew: v3.total_lamports_withdrew,
+            total_host_commission_lamports: v3.total_host_commission_lamports,
+            total_operator_commission_lamports: v3.total_operator_commission_lamports,
+            ..Stats::new()
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct UserAccountV0 {
+    pub account_type: StateAccountType,
+    pub authority: Pubkey,
+    pub total_bets: u32,
+    pub current_lamports: u64,
+    pub lamports_deposited: u64,
+    pub lamports_withdrew: u64,
+    pub active_vrf_results: u32,
+    pub games_hosted: u32,
+    pub referral: Option<Pubkey>,
+    pub username: Option<String>,
+}
+impl From<UserAccountV0> for UserAccountV3 {
+    fn from(v0: UserAccountV0) -> Self {
+        Self {
+            account_type: v0.account_type,
+            version: 3,
+            authority: v0.authority,
+            total_bets: v0.total_bets,
+            current_lamports: v0.current_lamports,
+            lamports_deposited: v0.lamports_deposited,
+            lamports_withdrew: v0.lamports_withdrew,
+            active_vrf_results: v0.active_vrf_results,
+            games_hosted: v0.games_hosted,
+            referral: v0.referral,
+            username: v0.username,
+            avatar_uri: None,
+            display_flags: 0,
+            betting_pnl: 0,
+            hosting_pnl: 0,
+            profit_high_water_mark: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct UserAccountV1 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub authority: Pubkey,
+    pub total_bets: u32,
+    pub current_lamports: u64,
+    pub lamports_deposited: u64,
+    pub lamports_withdrew: u64,
+    pub active_vrf_results: u32,
+    pub games_hosted: u32,
+    pub referral: Option<Pubkey>,
+    pub username: Option<String>,
+}
+impl From<UserAccountV1> for UserAccountV3 {
+    fn from(v1: UserAccountV1) -> Self {
+        Self {
+            account_type: v1.account_type,
+            version: 3,
+            authority: v1.authority,
+            total_bets: v1.total_bets,
+            current_lamports: v1.current_lamports,
+            lamports_deposited: v1.lamports_deposited,
+            lamports_withdrew: v1.lamports_withdrew,
+            active_vrf_results: v1.active_vrf_results,
+            games_hosted: v1.games_hosted,
+            referral: v1.referral,
+            username: v1.username,
+            avatar_uri: None,
+            display_flags: 0,
+            betting_pnl: 0,
+            hosting_pnl: 0,
+            profit_high_water_mark: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct UserAccountV2 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub authority: Pubkey,
+    pub total_bets: u32,
+    pub current_lamports: u64,
+    pub lamports_deposited: u64,
+    pub lamports_withdrew: u64,
+    pub active_vrf_results: u32,
+    pub games_hosted: u32,
+    pub referral: Option<Pubkey>,
+    pub username: Option<String>,
+    pub avatar_uri: Option<String>,
+    pub display_flags: u8,
+}
+// the balance of an upgraded account counts as principal, only the betting profit made after the upgrade is charged
+impl From<UserAccountV2> for UserAccountV3 {
+    fn from(v2: UserAccountV2) -> Self {
+        Self {
+            account_type: v2.account_type,
+            version: 3,
+            authority: v2.authority,
+            total_bets: v2.total_bets,
+            current_lamports: v2.current_lamports,
+            lamports_deposited: v2.lamports_deposited,
+            lamports_withdrew: v2.lamports_withdrew,
+            active_vrf_results: v2.active_vrf_results,
+            games_hosted: v2.games_hosted,
+            referral: v2.referral,
+            username: v2.username,
+            avatar_uri: v2.avatar_uri,
+            display_flags: v2.display_flags,
+            betting_pnl: 0,
+            hosting_pnl: 0,
+            profit_high_water_mark: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct UserAccountV3 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub authority: Pubkey,
+    pub total_bets: u32,
+    pub current_lamports: u64,
+    pub lamports_deposited: u64,
+    pub lamports_withdrew: u64,
+    pub active_vrf_results: u32,
+    pub games_hosted: u32,
+    pub referral: Option<Pubkey>,
+    pub username: Option<String>,
+    pub avatar_uri: Option<String>,
+    pub display_flags: u8,
+    pub betting_pnl: i64,
+    pub hosting_pnl: i64,
+    pub profit_high_water_mark: i64,
+}
+/// The profile fields of the fixed layout are bounded, a longer legacy username or avatar URI fails the migration
+/// instead of being dropped
+impl TryFrom<UserAccountV3> for UserAccount {
+    type Error = ProgramError;
+
+    fn try_from(v3: UserAccountV3) -> Result<Self, Self::Error> {
+        if matches!(&v3.username, Some(username) if username.len() > MAX_USERNAME_LEN) {
+            msg!("Legacy username does not fit in {} bytes", MAX_USERNAME_LEN);
+            return Err(ProgramError::from(BettingError::InvalidUsername));
+        }
+        if matches!(&v3.avatar_uri, Some(avatar_uri) if avatar_uri.len() > MAX_AVATAR_URI_LEN) {
+            msg!("Legacy avatar URI does not fit in {} bytes", MAX_AVATAR_URI_LEN);
+            return Err(ProgramError::from(BettingError::InvalidAvatarUri));
+        }
+        let mut user_account = Self {
+            total_bets: v3.total_bets,
+            current_lamports: v3.current_lamports,
+            lamports_deposited: v3.lamports_deposited,
+            lamports_withdrew: v3.lamports_withdrew,
+            active_vrf_results: v3.active_vrf_results,
+            games_hosted: v3.games_hosted,
+            display_flags: v3.display_flags,
+            betting_pnl: v3.betting_pnl,
+            hosting_pnl: v3.hosting_pnl,
+            profit_high_water_mark: v3.profit_high_water_mark,
+            ..UserAccount::new(v3.authority, v3.referral, v3.username)
+        };
+        user_account.set_avatar_uri(v3.avatar_uri.as_deref());
+        Ok(user_account)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VrfResultV0 {
+    pub account_type: StateAccountType,
+    pub is_fullfilled: bool,
+    pub is_used: bool,
+    pub marked_for_close: bool,
+    pub owner: Pubkey,
+    pub game: Pubkey,
+    pub bet_id: u32,
+    pub alpha: [u8; 72],
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+    pub locked_bettor_lamports: u64,
+    pub locked_host_lamports: u64,
+    pub bet_input: BetInput,
+}
+impl From<VrfResultV0> for VrfResultV1 {
+    fn from(v0: VrfResultV0) -> Self {
+        Self {
+            account_type: v0.account_type,
+            version: 1,
+            is_fullfilled: v0.is_fullfilled,
+            is_used: v0.is_used,
+            marked_for_close: v0.marked_for_close,
+            owner: v0.owner,
+            game: v0.game,
+            bet_id: v0.bet_id,
+            alpha: v0.alpha,
+            beta: v0.beta,
+            pi: v0.pi,
+            locked_bettor_lamports: v0.locked_bettor_lamports,
+            locked_host_lamports: v0.locked_host_lamports,
+            bet_input: v0.bet_input,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VrfResultV1 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub is_fullfilled: bool,
+    pub is_used: bool,
+    pub marked_for_close: bool,
+    pub owner: Pubkey,
+    pub game: Pubkey,
+    pub bet_id: u32,
+    pub alpha: [u8; 72],
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+    pub locked_bettor_lamports: u64,
+    pub locked_host_lamports: u64,
+    pub bet_input: BetInput,
+}
+impl From<VrfResultV1> for VrfResult {
+    fn from(v1: VrfResultV1) -> Self {
+        Self {
+            account_type: v1.account_type,
+            version: VrfResult::VERSION,
+            bump: VrfResult::find_pda(&v1.game, &v1.owner, v1.bet_id).1,
+            is_fullfilled: v1.is_fullfilled,
+            is_used: v1.is_used,
+            marked_for_close: v1.marked_for_close,
+            owner: v1.owner,
+            game: v1.game,
+            bet_id: v1.bet_id,
+            alpha: v1.alpha,
+            beta: v1.beta,
+            pi: v1.pi,
+            locked_bettor_lamports: v1.locked_bettor_lamports,
+            locked_host_lamports: v1.locked_host_lamports,
+            bet_input: v1.bet_input,
+            randomness: Randomness::Vrf,
+            slashed_lamports: 0,
+            close_on_resolve: false,
+            crank_tip_lamports: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VrfResultV2 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub bump: u8,
+    pub is_fullfilled: bool,
+    pub is_used: bool,
+    pub marked_for_close: bool,
+    pub owner: Pubkey,
+    pub game: Pubkey,
+    pub bet_id: u32,
+    pub alpha: [u8; 72],
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+    pub locked_bettor_lamports: u64,
+    pub locked_host_lamports: u64,
+    pub bet_input: BetInput,
+}
+impl From<VrfResultV2> for VrfResult {
+    fn from(v2: VrfResultV2) -> Self {
+        Self {
+            account_type: v2.account_type,
+            version: VrfResult::VERSION,
+            bump: v2.bump,
+            is_fullfilled: v2.is_fullfilled,
+            is_used: v2.is_used,
+            marked_for_close: v2.marked_for_close,
+            owner: v2.owner,
+            game: v2.game,
+            bet_id: v2.bet_id,
+            alpha: v2.alpha,
+            beta: v2.beta,
+            pi: v2.pi,
+            locked_bettor_lamports: v2.locked_bettor_lamports,
+            locked_host_lamports: v2.locked_host_lamports,
+            bet_input: v2.bet_input,
+            randomness: Randomness::Vrf,
+            slashed_lamports: 0,
+            close_on_resolve: false,
+            crank_tip_lamports: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VrfResultV3 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub bump: u8,
+    pub is_fullfilled: bool,
+    pub is_used: bool,
+    pub marked_for_close: bool,
+    pub owner: Pubkey,
+    pub game: Pubkey,
+    pub bet_id: u32,
+    pub alpha: [u8; 72],
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+    pub locked_bettor_lamports: u64,
+    pub locked_host_lamports: u64,
+    pub bet_input: BetInput,
+    pub randomness: Randomness,
+}
+impl From<VrfResultV3> for VrfResult {
+    fn from(v3: VrfResultV3) -> Self {
+        Self {
+            account_type: v3.account_type,
+            version: VrfResult::VERSION,
+            bump: v3.bump,
+            is_fullfilled: v3.is_fullfilled,
+            is_used: v3.is_used,
+            marked_for_close: v3.marked_for_close,
+            owner: v3.owner,
+            game: v3.game,
+            bet_id: v3.bet_id,
+            alpha: v3.alpha,
+            beta: v3.beta,
+            pi: v3.pi,
+            locked_bettor_lamports: v3.locked_bettor_lamports,
+            locked_host_lamports: v3.locked_host_lamports,
+            bet_input: v3.bet_input,
+            randomness: v3.randomness,
+            slashed_lamports: 0,
+            close_on_resolve: false,
+            crank_tip_lamports: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VrfResultV4 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub bump: u8,
+    pub is_fullfilled: bool,
+    pub is_used: bool,
+    pub marked_for_close: bool,
+    pub owner: Pubkey,
+    pub game: Pubkey,
+    pub bet_id: u32,
+    pub alpha: [u8; 72],
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+    pub locked_bettor_lamports: u64,
+    pub locked_host_lamports: u64,
+    pub bet_input: BetInput,
+    pub randomness: Randomness,
+    pub slashed_lamports: u64,
+}
+impl From<VrfResultV4> for VrfResult {
+    fn from(v4: VrfResultV4) -> Self {
+        Self {
+            account_type: v4.account_type,
+            version: VrfResult::VERSION,
+            bump: v4.bump,
+            is_fullfilled: v4.is_fullfilled,
+            is_used: v4.is_used,
+            marked_for_close: v4.marked_for_close,
+            owner: v4.owner,
+            game: v4.game,
+            bet_id: v4.bet_id,
+            alpha: v4.alpha,
+            beta: v4.beta,
+            pi: v4.pi,
+            locked_bettor_lamports: v4.locked_bettor_lamports,
+            locked_host_lamports: v4.locked_host_lamports,
+            bet_input: v4.bet_input,
+            randomness: v4.randomness,
+            slashed_lamports: v4.slashed_lamports,
+            close_on_resolve: false,
+            crank_tip_lamports: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct VrfResultV5 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub bump: u8,
+    pub is_fullfilled: bool,
+    pub is_used: bool,
+    pub marked_for_close: bool,
+    pub owner: Pubkey,
+    pub game: Pubkey,
+    pub bet_id: u32,
+    pub alpha: [u8; 72],
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+    pub locked_bettor_lamports: u64,
+    pub locked_host_lamports: u64,
+    pub bet_input: BetInput,
+    pub randomness: Randomness,
+    pub slashed_lamports: u64,
+    pub close_on_resolve: bool,
+}
+impl From<VrfResultV5> for VrfResult {
+    fn from(v5: VrfResultV5) -> Self {
+        Self {
+            account_type: v5.account_type,
+            version: VrfResult::VERSION,
+            bump: v5.bump,
+            is_fullfilled: v5.is_fullfilled,
+            is_used: v5.is_used,
+            marked_for_close: v5.marked_for_close,
+            owner: v5.owner,
+            game: v5.game,
+            bet_id: v5.bet_id,
+            alpha: v5.alpha,
+            beta: v5.beta,
+            pi: v5.pi,
+            locked_bettor_lamports: v5.locked_bettor_lamports,
+            locked_host_lamports: v5.locked_host_lamports,
+            bet_input: v5.bet_input,
+            randomness: v5.randomness,
+            slashed_lamports: v5.slashed_lamports,
+            close_on_resolve: v5.close_on_resolve,
+            crank_tip_lamports: 0,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct GameV0 {
+    pub account_type: StateAccountType,
+    pub host: Pubkey,
+    pub is_active: bool,
+    pub unresolved_vrf_result: u32,
+    pub total_lamports_in: u64,
+    pub total_lamports_out: u64,
+    pub common_config: CommonGameConfig,
+    pub game_type_config: GameTypeConfig,
+}
+impl From<GameV0> for GameV2 {
+    fn from(v0: GameV0) -> Self {
+        Self {
+            account_type: v0.account_type,
+            version: 2,
+            host: v0.host,
+            is_active: v0.is_active,
+            unresolved_vrf_result: v0.unresolved_vrf_result,
+            total_lamports_in: v0.total_lamports_in,
+            total_lamports_out: v0.total_lamports_out,
+            commission: 0,
+            common_config: v0.common_config,
+            game_type_config: v0.game_type_config,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct GameV1 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub host: Pubkey,
+    pub is_active: bool,
+    pub unresolved_vrf_result: u32,
+    pub total_lamports_in: u64,
+    pub total_lamports_out: u64,
+    pub common_config: CommonGameConfig,
+    pub game_type_config: GameTypeConfig,
+}
+impl From<GameV1> for GameV2 {
+    fn from(v1: GameV1) -> Self {
+        Self {
+            account_type: v1.account_type,
+            version: 2,
+            host: v1.host,
+            is_active: v1.is_active,
+            unresolved_vrf_result: v1.unresolved_vrf_result,
+            total_lamports_in: v1.total_lamports_in,
+            total_lamports_out: v1.total_lamports_out,
+            commission: 0,
+            common_config: v1.common_config,
+            game_type_config: v1.game_type_config,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct GameV2 {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub host: Pubkey,
+    pub is_active: bool,
+    pub unresolved_vrf_result: u32,
+    pub total_lamports_in: u64,
+    pub total_lamports_out: u64,
+    pub commission: u64,
+    pub common_config: CommonGameConfig,
+    pub game_type_config: GameTypeConfig,
+}
+impl From<GameV2> for Game {
+    fn from(v2: GameV2) -> Self {
+        let mut game = Self {
+            unresolved_vrf_result: v2.unresolved_vrf_result,
+            total_lamports_in: v2.total_lamports_in,
+            total_lamports_out: v2.total_lamports_out,
+            commission: v2.commission,
+            ..Game::new(v2.host, v2.common_config.min_wager, v2.common_config.max_wager, v2.game_type_config)
+        };
+        game.set_active(v2.is_active);
+        game
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct GameV3 {
+    pub account_type: u8,
+    pub version: u8,
+    pub bump: u8,
+    pub active: u8,
+    pub unresolved_vrf_result: u32,
+    pub host: Pubkey,
+    pub total_lamports_in: u64,
+    pub total_lamports_out: u64,
+    pub commission: u64,
+    pub common_config: CommonGameConfig,
+    pub game_type_config_len: u8,
+    pub game_type_config_bytes: [u8; 31],
+}
+impl_pod_borsh!(GameV3);
+impl From<GameV3> for Game {
+    fn from(v3: GameV3) -> Self {
+        Self {
+            account_type: v3.account_type,
+            version: Game::VERSION,
+            bump: v3.bump,
+            active: v3.active,
+            unresolved_vrf_result: v3.unresolved_vrf_result,
+            host: v3.host,
+            total_lamports_in: v3.total_lamports_in,
+            total_lamports_out: v3.total_lamports_out,
+            commission: v3.commission,
+            common_config: v3.common_config,
+            game_type_config_len: v3.game_type_config_len,
+            game_type_config_bytes: v3.game_type_config_bytes,
+            // earlier games settle with the VRF
+            ..bytemuck::Zeroable::zeroed()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::{
+        constants::{MAX_AVATAR_URI_LEN, MAX_USERNAME_LEN},
+        error::BettingError,
+        state::{
+            game::{
+                coinflip::{CoinFlipInput, CoinFlipSide},
+                crash::CrashConfig,
+                BetInput, CommonGameConfig, Game, GameTypeConfig,
+            },
+            randomness::{Randomness, RANDOMNESS_SOURCE_VRF},
+            stats::Stats,
+            user_account::UserAccount,
+            vrf_result::VrfResult,
+            BettingAccount, StateAccountType,
+        },
+    };
+
+    use super::{GameV1, GameV3, StatsV0, StatsV1, StatsV2, UserAccountV0, UserAccountV2, VrfResultV1, VrfResultV2, VrfResultV3, VrfResultV4, VrfResultV5};
+
+    #[test]
+    fn test_unpack_legacy_user_account() {
+        let authority = Pubkey::new_unique();
+        let referral = Pubkey::new_unique();
+        let v0 = UserAccountV0 {
+            account_type: StateAccountType::UserAccount,
+            authority,
+            total_bets: 3,
+            current_lamports: 1000,
+            lamports_deposited: 2000,
+            lamports_withdrew: 500,
+            active_vrf_results: 1,
+            games_hosted: 2,
+            referral: Some(referral),
+            username: Some("Username".to_string()),
+        };
+        let data = v0.try_to_vec().unwrap();
+
+        let state = UserAccount::unpack(&data).unwrap();
+        assert_eq!(state.version, UserAccount::VERSION);
+        assert_eq!(state.authority, authority);
+        assert_eq!(state.total_bets, 3);
+        assert_eq!(state.current_lamports, 1000);
+        assert_eq!(state.lamports_deposited, 2000);
+        assert_eq!(state.lamports_withdrew, 500);
+        assert_eq!(state.active_vrf_results, 1);
+        assert_eq!(state.games_hosted, 2);
+        assert_eq!(state.referral(), Some(referral));
+        assert_eq!(state.username(), Some("Username"));
+        assert_eq!(state.avatar_uri(), None);
+        assert_eq!(state.display_flags, 0);
+    }
+    #[test]
+    fn test_unpack_legacy_user_account_authority_like_version() {
+        // the first byte of the authority of a layout without version must not select a versioned layout
+        for version in 1..=UserAccount::VERSION {
+            let authority = Pubkey::new_from_array([version; 32]);
+            let v0 = UserAccountV0 {
+                account_type: StateAccountType::UserAccount,
+                authority,
+                total_bets: 1,
+                current_lamports: 1000,
+                lamports_deposited: 1000,
+                lamports_withdrew: 0,
+                active_vrf_results: 0,
+                games_hosted: 0,
+                referral: None,
+                username: None,
+            };
+            let state = UserAccount::unpack(&v0.try_to_vec().unwrap()).unwrap();
+            assert_eq!(state.authority, authority);
+            assert_eq!(state.current_lamports, 1000);
+        }
+    }
+    #[test]
+    fn test_unpack_legacy_user_account_err_profile_too_long() {
+        let v2 = UserAccountV2 {
+            account_type: StateAccountType::UserAccount,
+            version: 2,
+            authority: Pubkey::new_unique(),
+            total_bets: 0,
+            current_lamports: 0,
+            lamports_deposited: 0,
+            lamports_withdrew: 0,
+            active_vrf_results: 0,
+            games_hosted: 0,
+            referral: None,
+            username: Some("a".repeat(MAX_USERNAME_LEN + 1)),
+            avatar_uri: None,
+            display_flags: 0,
+        };
+        assert!(matches!(
+            UserAccount::unpack(&v2.try_to_vec().unwrap()),
+            Err(err) if err == ProgramError::from(BettingError::InvalidUsername)
+        ));
+        let v2 = UserAccountV2 {
+            username: Some("Username".to_string()),
+            avatar_uri: Some("a".repeat(MAX_AVATAR_URI_LEN + 1)),
+            ..v2
+        };
+        assert!(matches!(
+            UserAccount::unpack(&v2.try_to_vec().unwrap()),
+            Err(err) if err == ProgramError::from(BettingError::InvalidAvatarUri)
+        ));
+    }
+    #[test]
+    fn test_unpack_current_and_legacy_stats() {
+        let mut stats = Stats::new();
+        stats.total_bets = 7;
+        let state = Stats::unpack(&stats.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, Stats::VERSION);
+        assert_eq!(state.total_bets, 7);
+
+        let v0 = StatsV0 {
+            account_type: StateAccountType::Stats,
+            total_games: 1,
+            total_users: 2,
+            total_bets: 3,
+            total_wager: 4,
+            total_lamports_won_by_bettors: 5,
+            total_lamports_deposited: 6,
+            total_lamports_withdrew: 7,
+        };
+        let state = Stats::unpack(&v0.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, Stats::VERSION);
+        assert_eq!(state.total_games, 1);
+        assert_eq!(state.total_users, 2);
+        assert_eq!(state.total_bets, 3);
+        assert_eq!(state.total_wager, 4);
+        assert_eq!(state.total_bettor_pnl, 5);
+        assert_eq!(state.total_lamports_deposited, 6);
+        assert_eq!(state.total_lamports_withdrew, 7);
+    }
+    #[test]
+    fn test_unpack_legacy_v1_stats_and_game() {
+        let v1 = StatsV1 {
+            account_type: StateAccountType::Stats,
+            version: 1,
+            total_games: 1,
+            total_users: 2,
+            total_bets: 3,
+            total_wager: 4,
+            total_lamports_won_by_bettors: 5,
+            total_lamports_deposited: 6,
+            total_lamports_withdrew: 7,
+        };
+        let state = Stats::unpack(&v1.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, Stats::VERSION);
+        assert_eq!(state.total_games, 1);
+        assert_eq!(state.total_lamports_withdrew, 7);
+        assert_eq!(state.total_host_commission_lamports, 0);
+        assert_eq!(state.total_operator_commission_lamports, 0);
+
+        let host = Pubkey::new_unique();
+        let v1 = GameV1 {
+            account_type: StateAccountType::Game,
+            version: 1,
+            host,
+            is_active: true,
+            unresolved_vrf_result: 2,
+            total_lamports_in: 3000,
+            total_lamports_out: 1000,
+            common_config: CommonGameConfig {
+                min_wager: 1000,
+                max_wager: 10000,
+            },
+            game_type_config: GameTypeConfig::Crash {
+                config: CrashConfig {
+                    multiplier_straight_one_possibility: 100,
+                },
+            },
+        };
+        let state = Game::unpack(&v1.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, Game::VERSION);
+        assert_eq!(state.host, host);
+        assert_eq!(state.unresolved_vrf_result, 2);
+        assert_eq!(state.total_lamports_in, 3000);
+        assert_eq!(state.total_lamports_out, 1000);
+        assert_eq!(state.commission, 0);
+        assert_eq!(state.common_config.max_wager, 10000);
+    }
+
+    #[test]
+    fn test_unpack_legacy_v2_stats_with_wrapped_bettor_total() {
+        let v2 = StatsV2 {
+            account_type: StateAccountType::Stats,
+            version: 2,
+            total_games: 1,
+            total_users: 2,
+            total_bets: 3,
+            total_wager: 4000,
+            // bettors lost 500 lamports in total, the unsigned total wrapped around
+            total_lamports_won_by_bettors: (-500i64) as u64,
+            total_lamports_deposited: 6,
+            total_lamports_withdrew: 7,
+            total_host_commission_lamports: 8,
+            total_operator_commission_lamports: 9,
+        };
+        let state = Stats::unpack(&v2.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, Stats::VERSION);
+        assert_eq!(state.total_wager, 4000);
+        assert_eq!(state.total_bettor_pnl, -500);
+        assert_eq!(state.total_host_pnl, 0);
+        assert_eq!(state.total_host_commission_lamports, 8);
+        assert_eq!(state.total_operator_commission_lamports, 9);
+    }
+
+    #[test]
+    fn test_unpack_legacy_accounts_store_canonical_bumps() {
+        let owner = Pubkey::new_unique();
+        let game = Pubkey::new_unique();
+        let v1 = VrfResultV1 {
+            account_type: StateAccountType::Vrf,
+            version: 1,
+            is_fullfilled: false,
+            is_used: false,
+            marked_for_close: false,
+            owner,
+            game,
+            bet_id: 3,
+            alpha: [1; 72],
+            beta: [0; 64],
+            pi: [0; 80],
+            locked_bettor_lamports: 2000,
+            locked_host_lamports: 1980,
+            bet_input: BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        };
+        let state = VrfResult::unpack(&v1.try_to_vec().unwrap()).unwrap();
+        let (vrf_result_pda, vrf_result_bump) = VrfResult::find_pda(&game, &owner, 3);
+        assert_eq!(state.version, VrfResult::VERSION);
+        assert_eq!(state.bump, vrf_result_bump);
+        assert_eq!(state.locked_host_lamports, 1980);
+        assert_eq!(
+            Pubkey::create_program_address(
+                &[b"VrfResult".as_ref(), game.as_ref(), owner.as_ref(), &3u32.to_le_bytes(), &[state.bump]],
+                &crate::id()
+            )
+            .unwrap(),
+            vrf_result_pda
+        );
+        // the current layout keeps the bump it was written with
+        let state = VrfResult::unpack(&state.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.bump, vrf_result_bump);
+
+        let authority = Pubkey::new_unique();
+        let v0 = UserAccountV0 {
+            account_type: StateAccountType::UserAccount,
+            authority,
+            total_bets: 0,
+            current_lamports: 0,
+            lamports_deposited: 0,
+            lamports_withdrew: 0,
+            active_vrf_results: 0,
+            games_hosted: 0,
+            referral: None,
+            username: None,
+        };
+        let state = UserAccount::unpack(&v0.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.bump, UserAccount::find_pda(&authority).1);
+
+        let v0 = StatsV0 {
+            account_type: StateAccountType::Stats,
+            total_games: 0,
+            total_users: 0,
+            total_bets: 0,
+            total_wager: 0,
+            total_lamports_won_by_bettors: 0,
+            total_lamports_deposited: 0,
+            total_lamports_withdrew: 0,
+        };
+        let state = Stats::unpack(&v0.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.bump, Stats::find_pda().1);
+    }
+
+    #[test]
+    fn test_unpack_accounts_from_before_randomness_sources() {
+        let game = Game::new(
+            Pubkey::new_unique(),
+            1000,
+            10000,
+            GameTypeConfig::Crash {
+                config: CrashConfig {
+                    multiplier_straight_one_possibility: 100,
+                },
+            },
+        );
+        let v3 = GameV3 {
+            account_type: StateAccountType::Game as u8,
+            version: 3,
+            bump: game.bump,
+            active: 1,
+            unresolved_vrf_result: 2,
+            host: game.host,
+            total_lamports_in: 3000,
+            total_lamports_out: 1000,
+            commission: 500,
+            common_config: game.common_config,
+            game_type_config_len: game.game_type_config_len,
+            game_type_config_bytes: game.game_type_config_bytes,
+        };
+        let state = Game::unpack(&v3.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, Game::VERSION);
+        assert_eq!(state.unresolved_vrf_result, 2);
+        assert_eq!(state.commission, 500);
+        assert_eq!(state.seeds(), game.seeds());
+        // earlier games settle with the VRF
+        assert_eq!(state.randomness_source, RANDOMNESS_SOURCE_VRF);
+
+        let v2 = VrfResultV2 {
+            account_type: StateAccountType::Vrf,
+            version: 2,
+            bump: 254,
+            is_fullfilled: true,
+            is_used: false,
+            marked_for_close: false,
+            owner: Pubkey::new_unique(),
+            game: Pubkey::new_unique(),
+            bet_id: 1,
+            alpha: [1; 72],
+            beta: [2; 64],
+            pi: [3; 80],
+            locked_bettor_lamports: 2000,
+            locked_host_lamports: 1980,
+            bet_input: BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Tail,
+                },
+            },
+        };
+        let state = VrfResult::unpack(&v2.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, VrfResult::VERSION);
+        assert_eq!(state.bump, 254);
+        assert_eq!(state.beta, [2; 64]);
+        assert_eq!(state.randomness, Randomness::Vrf);
+    }
+
+    #[test]
+    fn test_unpack_vrf_result_from_before_operator_bonds() {
+        let v3 = VrfResultV3 {
+            account_type: StateAccountType::Vrf,
+            version: 3,
+            bump: 253,
+            is_fullfilled: false,
+            is_used: false,
+            marked_for_close: false,
+            owner: Pubkey::new_unique(),
+            game: Pubkey::new_unique(),
+            bet_id: 4,
+            alpha: [1; 72],
+            beta: [0; 64],
+            pi: [0; 80],
+            locked_bettor_lamports: 2000,
+            locked_host_lamports: 1980,
+            bet_input: BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+            randomness: Randomness::HashChain { index: 3 },
+        };
+        let state = VrfResult::unpack(&v3.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, VrfResult::VERSION);
+        assert_eq!(state.bump, 253);
+        assert_eq!(state.randomness, Randomness::HashChain { index: 3 });
+        assert_eq!(state.slashed_lamports, 0);
+    }
+    #[test]
+    fn test_unpack_vrf_result_from_before_close_on_resolve() {
+        let v4 = VrfResultV4 {
+            account_type: StateAccountType::Vrf,
+            version: 4,
+            bump: 252,
+            is_fullfilled: true,
+            is_used: false,
+            marked_for_close: true,
+            owner: Pubkey::new_unique(),
+            game: Pubkey::new_unique(),
+            bet_id: 5,
+            alpha: [1; 72],
+            beta: [2; 64],
+            pi: [3; 80],
+            locked_bettor_lamports: 2000,
+            locked_host_lamports: 1980,
+            bet_input: BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+            randomness: Randomness::Vrf,
+            slashed_lamports: 700,
+        };
+        let state = VrfResult::unpack(&v4.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, VrfResult::VERSION);
+        assert!(state.marked_for_close);
+        assert_eq!(state.slashed_lamports, 700);
+        assert!(!state.close_on_resolve);
+    }
+    #[test]
+    fn test_unpack_vrf_result_from_before_crank_tips() {
+        let v5 = VrfResultV5 {
+            account_type: StateAccountType::Vrf,
+            version: 5,
+            bump: 251,
+            is_fullfilled: false,
+            is_used: false,
+            marked_for_close: false,
+            owner: Pubkey::new_unique(),
+            game: Pubkey::new_unique(),
+            bet_id: 6,
+            alpha: [1; 72],
+            beta: [0; 64],
+            pi: [0; 80],
+            locked_bettor_lamports: 2000,
+            locked_host_lamports: 1980,
+            bet_input: BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 2000,
+                    side: CoinFlipSide::Head,
+                },
+            },
+            randomness: Randomness::Vrf,
+            slashed_lamports: 0,
+            close_on_resolve: true,
+        };
+        let state = VrfResult::unpack(&v5.try_to_vec().unwrap()).unwrap();
+        assert_eq!(state.version, VrfResult::VERSION);
+        assert!(state.close_on_resolve);
+        assert_eq!(state.crank_tip_lamports, 0);
+    }
+}
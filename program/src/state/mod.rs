@@ -1,11 +1,20 @@
+pub mod bankroll;
 pub mod game;
+pub mod legacy;
+pub mod operator_bond;
+pub mod randomness;
+pub mod randomness_request;
+pub mod referral;
 pub mod stats;
 pub mod user_account;
+pub mod user_limits;
+pub mod username;
 pub mod vrf_result;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{account_info::AccountInfo, msg, program_error::ProgramError};
-use std::fmt::Display;
+use bytemuck::Pod;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError};
+use std::{cell::RefMut, fmt::Display};
 
 use crate::error::BettingError;
 
@@ -16,6 +25,15 @@ pub enum StateAccountType {
     UserAccount,
     Vrf,
     Game,
+    Username,
+    Referral,
+    ReferralConfig,
+    UserLimits,
+    GameBankroll,
+    BankrollShare,
+    OracleConfig,
+    OperatorBond,
+    RandomnessRequest,
 }
 
 impl Display for StateAccountType {
@@ -26,36 +44,114 @@ impl Display for StateAccountType {
             StateAccountType::Stats => write!(f, "Stats"),
             StateAccountType::Vrf => write!(f, "VrfResult"),
             StateAccountType::Game => write!(f, "Game"),
+            StateAccountType::Username => write!(f, "Username"),
+            StateAccountType::Referral => write!(f, "Referral"),
+            StateAccountType::ReferralConfig => write!(f, "ReferralConfig"),
+            StateAccountType::UserLimits => write!(f, "UserLimits"),
+            StateAccountType::GameBankroll => write!(f, "GameBankroll"),
+            StateAccountType::BankrollShare => write!(f, "BankrollShare"),
+            StateAccountType::OracleConfig => write!(f, "OracleConfig"),
+            StateAccountType::OperatorBond => write!(f, "OperatorBond"),
+            StateAccountType::RandomnessRequest => write!(f, "RandomnessRequest"),
         }
     }
 }
 
+/// The only legacy layout decoding the whole account data. A versioned layout only matches its own version field, the
+/// layout written before the version byte has none and is told apart by its length, so the first byte of one of its
+/// fields never selects another layout
+pub(crate) fn unique_legacy_layout<T>(layouts: impl IntoIterator<Item = Option<T>>) -> Result<T, ProgramError> {
+    let mut layouts = layouts.into_iter().flatten();
+    match (layouts.next(), layouts.next()) {
+        (Some(state), None) => Ok(state),
+        (Some(_), Some(_)) => {
+            msg!("Account data matches several legacy layouts");
+            Err(ProgramError::InvalidAccountData)
+        }
+        (None, _) => Err(ProgramError::InvalidAccountData),
+    }
+}
+
 pub trait BettingAccount: BorshDeserialize {
     const ACCOUNT_TYPE: StateAccountType;
+    const VERSION: u8;
+    /// Deserialize account data of a previous version, upgrading it to the latest layout
+    fn try_from_legacy_slice(data: &[u8]) -> Result<Self, ProgramError>;
+    /// Deserialize account data of any known version, upgrading it to the latest layout. The current layout is matched
+    /// first, the legacy layouts are only tried when its version or length differ
+    fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() > 1 && data[1] == Self::VERSION {
+            if let Ok(state) = Self::try_from_slice(data) {
+                return Ok(state);
+            }
+        }
+        Self::try_from_legacy_slice(data)
+    }
     fn try_from_account_info(account_info: &AccountInfo) -> Result<Self, ProgramError> {
-        if account_info.owner != &crate::ID {
-            msg!("Expect account {} to be owned by this program", account_info.key);
-            Err(ProgramError::from(BettingError::WrongAccountOwner))
-        } else if account_info.data_is_empty() {
+        check_account_type(account_info, Self::ACCOUNT_TYPE)?;
+        match Self::unpack(&account_info.try_borrow_data()?) {
+            Ok(state) => Ok(state),
+            Err(_) => {
+                msg!("Error deserializing account {}", account_info.key);
+                Err(ProgramError::InvalidAccountData)
+            }
+        }
+    }
+}
+
+/// Fixed-layout accounts, borrowed in place from the account data instead of being deserialized and written back
+pub trait ZeroCopyAccount: BettingAccount + Pod {
+    fn load_mut<'a>(account_info: &'a AccountInfo) -> Result<RefMut<'a, Self>, ProgramError> {
+        check_account_type(account_info, Self::ACCOUNT_TYPE)?;
+        let data = account_info.try_borrow_mut_data()?;
+        if bytemuck::try_from_bytes::<Self>(&data).is_err() || data[1] != Self::VERSION {
+            msg!("Expect account {} to be migrated to version {}", account_info.key, Self::VERSION);
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(RefMut::map(data, |data| bytemuck::from_bytes_mut(data)))
+    }
+}
+
+fn check_account_type(account_info: &AccountInfo, account_type: StateAccountType) -> ProgramResult {
+    if account_info.owner != &crate::ID {
+        msg!("Expect account {} to be owned by this program", account_info.key);
+        Err(ProgramError::from(BettingError::WrongAccountOwner))
+    } else if account_info.data_is_empty() {
+        msg!("Expect account {} to be initialized", account_info.key);
+        Err(ProgramError::UninitializedAccount)
+    } else {
+        let data = account_info.try_borrow_data()?;
+        if data[0] == StateAccountType::Uninitialized as u8 {
             msg!("Expect account {} to be initialized", account_info.key);
             Err(ProgramError::UninitializedAccount)
+        } else if data[0] != account_type as u8 {
+            msg!("Expect account {} to be of type {}", account_info.key, account_type);
+            Err(ProgramError::InvalidAccountData)
         } else {
-            let data = account_info.data.borrow();
-            if data[0] == StateAccountType::Uninitialized as u8 {
-                msg!("Expect account {} to be initialized", account_info.key);
-                Err(ProgramError::UninitializedAccount)
-            } else if data[0] != Self::ACCOUNT_TYPE as u8 {
-                msg!("Expect account {} to be of type {}", account_info.key, Self::ACCOUNT_TYPE);
-                Err(ProgramError::InvalidAccountData)
-            } else {
-                match Self::try_from_slice(&data) {
-                    Ok(state) => Ok(state),
-                    Err(_) => {
-                        msg!("Error deserializing account {}");
-                        Err(ProgramError::InvalidAccountData)
-                    }
+            Ok(())
+        }
+    }
+}
+
+/// Borsh encoding of a fixed-layout account, its bytes as laid out in memory
+macro_rules! impl_pod_borsh {
+    ($ty:ty) => {
+        impl borsh::BorshSerialize for $ty {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                writer.write_all(bytemuck::bytes_of(self))
+            }
+        }
+        impl borsh::BorshDeserialize for $ty {
+            fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+                let len = std::mem::size_of::<Self>();
+                if buf.len() < len {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Unexpected length of input"));
                 }
+                let state = bytemuck::pod_read_unaligned(&buf[..len]);
+                *buf = &buf[len..];
+                Ok(state)
             }
         }
-    }
+    };
 }
+pub(crate) use impl_pod_borsh;
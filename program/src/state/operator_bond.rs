@@ -0,0 +1,184 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankAccount;
+use solana_program::{account_info::AccountInfo, msg, program_error::ProgramError};
+
+use crate::{
+    error::BettingError,
+    math::{mul_div, CheckedMath},
+};
+
+use super::{BettingAccount, StateAccountType};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperatorBondConfig {
+    // bets settled with the VRF are only accepted while this many lamports are bonded and not being withdrawn
+    pub min_bond_lamports: u64,
+    // seconds between a withdrawal request and the withdrawal
+    pub withdrawal_delay: i64,
+    // seconds the operator has to fullfill a bet before it can be slashed
+    pub fullfill_deadline: i64,
+    // basis points of the wager paid to the bettor of a late bet
+    pub slash_penalty: u64,
+}
+
+/// Lamports bonded by the operator, paid to the bettors of the bets it fails to fullfill in time.
+///
+/// Only late fullfillments can be slashed, the VRF proof is not checked on-chain so an invalid one is not provable here.
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct OperatorBond {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub bump: u8,
+    pub config: OperatorBondConfig,
+    // lamports that can be slashed, the rent of the account excluded
+    pub bonded_lamports: u64,
+    pub pending_withdrawal_lamports: u64,
+    // 0 when there is no pending withdrawal
+    pub withdrawal_unlocks_at: i64,
+    pub total_slashed_lamports: u64,
+}
+
+impl OperatorBond {
+    pub fn new(config: OperatorBondConfig, bump: u8) -> Self {
+        Self {
+            account_type: StateAccountType::OperatorBond,
+            version: Self::VERSION,
+            bump,
+            config,
+            bonded_lamports: 0,
+            pending_withdrawal_lamports: 0,
+            withdrawal_unlocks_at: 0,
+            total_slashed_lamports: 0,
+        }
+    }
+    pub fn check_config(config: &OperatorBondConfig) -> Result<(), ProgramError> {
+        if config.withdrawal_delay < 0 || config.fullfill_deadline <= 0 || config.slash_penalty > 10000 {
+            msg!("Invalid operator bond config {:?}", config);
+            return Err(ProgramError::from(BettingError::InvalidOperatorBondConfig));
+        }
+        Ok(())
+    }
+    /// Whether enough lamports stay bonded for new bets once the pending withdrawal goes through
+    pub fn is_bonded(&self) -> bool {
+        self.bonded_lamports.saturating_sub(self.pending_withdrawal_lamports) >= self.config.min_bond_lamports
+    }
+    /// Take the penalty of a late bet out of the bond, as much as is left of it
+    pub fn slash(&mut self, wager: u64) -> Result<u64, ProgramError> {
+        let penalty = mul_div(wager, self.config.slash_penalty, 10000)?.min(self.bonded_lamports);
+        self.bonded_lamports = self.bonded_lamports.try_sub(penalty)?;
+        self.pending_withdrawal_lamports = self.pending_withdrawal_lamports.min(self.bonded_lamports);
+        self.total_slashed_lamports = self.total_slashed_lamports.try_add(penalty)?;
+        Ok(penalty)
+    }
+    /// Start the delay of a withdrawal, replacing the pending one
+    pub fn request_withdrawal(&mut self, now: i64, lamports: u64) -> Result<(), ProgramError> {
+        if lamports > self.bonded_lamports {
+            msg!("Only {} lamports are bonded", self.bonded_lamports);
+            return Err(ProgramError::InsufficientFunds);
+        }
+        self.pending_withdrawal_lamports = lamports;
+        self.withdrawal_unlocks_at = now.try_add(self.config.withdrawal_delay)?;
+        Ok(())
+    }
+    /// Release the pending withdrawal once its delay has passed
+    pub fn withdraw(&mut self, now: i64) -> Result<u64, ProgramError> {
+        if self.pending_withdrawal_lamports == 0 || now < self.withdrawal_unlocks_at {
+            msg!("No withdrawal is unlocked");
+            return Err(ProgramError::from(BettingError::OperatorBondWithdrawalLocked));
+        }
+        let lamports = self.pending_withdrawal_lamports;
+        self.bonded_lamports = self.bonded_lamports.try_sub(lamports)?;
+        self.pending_withdrawal_lamports = 0;
+        self.withdrawal_unlocks_at = 0;
+        Ok(lamports)
+    }
+    /// Deserialize the operator bond PDA, `None` until the operator configures it
+    pub fn load(operator_bond_account_info: &AccountInfo) -> Result<Option<Self>, ProgramError> {
+        if operator_bond_account_info.data_is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self::try_from_account_info(operator_bond_account_info)?))
+    }
+}
+impl BettingAccount for OperatorBond {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::OperatorBond;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::program_error::ProgramError;
+
+    use crate::error::BettingError;
+
+    use super::{OperatorBond, OperatorBondConfig};
+
+    fn config() -> OperatorBondConfig {
+        OperatorBondConfig {
+            min_bond_lamports: 1000,
+            withdrawal_delay: 100,
+            fullfill_deadline: 60,
+            slash_penalty: 5000,
+        }
+    }
+
+    #[test]
+    fn test_operator_bond_check_config() {
+        assert!(OperatorBond::check_config(&config()).is_ok());
+        let invalid_configs = [
+            OperatorBondConfig {
+                withdrawal_delay: -1,
+                ..config()
+            },
+            OperatorBondConfig {
+                fullfill_deadline: 0,
+                ..config()
+            },
+            OperatorBondConfig {
+                slash_penalty: 10001,
+                ..config()
+            },
+        ];
+        for config in invalid_configs {
+            assert_eq!(
+                OperatorBond::check_config(&config).unwrap_err(),
+                ProgramError::from(BettingError::InvalidOperatorBondConfig)
+            );
+        }
+    }
+
+    #[test]
+    fn test_operator_bond_slash() {
+        let mut bond = OperatorBond::new(config(), 255);
+        bond.bonded_lamports = 1500;
+        bond.request_withdrawal(0, 500).unwrap();
+        // half of the wager
+        assert_eq!(bond.slash(1200).unwrap(), 600);
+        assert_eq!(bond.bonded_lamports, 900);
+        assert!(!bond.is_bonded());
+        // no more than what is left, the pending withdrawal shrinks with the bond
+        assert_eq!(bond.slash(4000).unwrap(), 900);
+        assert_eq!(bond.bonded_lamports, 0);
+        assert_eq!(bond.pending_withdrawal_lamports, 0);
+        assert_eq!(bond.total_slashed_lamports, 1500);
+    }
+
+    #[test]
+    fn test_operator_bond_withdrawal() {
+        let mut bond = OperatorBond::new(config(), 255);
+        bond.bonded_lamports = 3000;
+        assert!(bond.is_bonded());
+        assert_eq!(bond.request_withdrawal(10, 3001).unwrap_err(), ProgramError::InsufficientFunds);
+        bond.request_withdrawal(10, 2500).unwrap();
+        // the withdrawn lamports stop counting towards the minimum bond as soon as they are requested
+        assert!(!bond.is_bonded());
+        assert_eq!(bond.withdraw(109).unwrap_err(), ProgramError::from(BettingError::OperatorBondWithdrawalLocked));
+        assert_eq!(bond.withdraw(110).unwrap(), 2500);
+        assert_eq!(bond.bonded_lamports, 500);
+        assert_eq!(bond.withdraw(200).unwrap_err(), ProgramError::from(BettingError::OperatorBondWithdrawalLocked));
+    }
+}
@@ -0,0 +1,205 @@
+//! Sources of the randomness a bet is settled with.
+//!
+//! Every source ends in a seed that `Rng` expands into the draws of the game:
+//! - `Vrf`: the output of the operator's VRF over the alpha of the bet. The proof is stored next to it for anyone to
+//!   check off-chain, the program only checks that the operator signed it.
+//! - `HashChain`: the host commits to the head of a hash chain when creating the game and reveals one preimage per bet,
+//!   in placement order. The seed is `sha256(server seed || alpha || bet id)`, the alpha acting as the client seed.
+//! - `Oracles`: the XOR of the outputs of `threshold` distinct oracles of the oracle config.
+use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankAccount;
+use solana_program::{hash::hashv, msg, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{constants::MAX_ORACLES, error::BettingError};
+
+use super::{vrf_result::VrfResult, BettingAccount, StateAccountType};
+
+pub const RANDOMNESS_SOURCE_VRF: u8 = 0;
+pub const RANDOMNESS_SOURCE_HASH_CHAIN: u8 = 1;
+pub const RANDOMNESS_SOURCE_ORACLES: u8 = 2;
+
+/// Randomness source of a game, chosen at creation
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RandomnessSourceConfig {
+    Vrf,
+    HashChain { head: [u8; 32] },
+    Oracles { threshold: u8 },
+}
+
+/// Randomness of a bet, assigned by its game when it is placed
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Randomness {
+    Vrf,
+    HashChain { index: u32 },
+    // bitmap of the oracles of the oracle config that contributed
+    Oracles { threshold: u8, contributions: u8 },
+}
+impl Randomness {
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Randomness::Oracles { threshold, contributions } => contributions.count_ones() >= *threshold as u32,
+            _ => true,
+        }
+    }
+}
+
+pub trait RandomnessSource {
+    /// Seed of the draws of a fullfilled bet
+    fn seed(&self, vrf_result: &VrfResult) -> Result<Vec<u8>, ProgramError>;
+}
+impl RandomnessSource for Randomness {
+    fn seed(&self, vrf_result: &VrfResult) -> Result<Vec<u8>, ProgramError> {
+        if !self.is_complete() {
+            return Err(ProgramError::from(BettingError::VrfResultNotFullfilled));
+        }
+        match self {
+            Randomness::Vrf | Randomness::Oracles { .. } => Ok(vrf_result.beta.to_vec()),
+            Randomness::HashChain { .. } => Ok(hashv(&[&vrf_result.beta[..32], &vrf_result.alpha, &vrf_result.bet_id.to_le_bytes()])
+                .to_bytes()
+                .to_vec()),
+        }
+    }
+}
+
+/// Check that a revealed server seed is the preimage of the current head of a hash chain
+pub fn check_hash_chain_link(head: &[u8; 32], server_seed: &[u8; 32]) -> Result<(), ProgramError> {
+    if hashv(&[server_seed]).to_bytes() == *head {
+        Ok(())
+    } else {
+        msg!("Server seed does not hash to the head of the hash chain");
+        Err(ProgramError::from(BettingError::InvalidHashChainLink))
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct OracleConfig {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub oracles: Vec<Pubkey>,
+}
+
+impl OracleConfig {
+    pub fn new(oracles: Vec<Pubkey>) -> Self {
+        Self {
+            account_type: StateAccountType::OracleConfig,
+            version: Self::VERSION,
+            oracles,
+        }
+    }
+    pub fn check_oracles(oracles: &[Pubkey]) -> Result<(), ProgramError> {
+        let is_valid = !oracles.is_empty() && oracles.len() <= MAX_ORACLES && oracles.iter().enumerate().all(|(i, oracle)| !oracles[..i].contains(oracle));
+        if is_valid {
+            Ok(())
+        } else {
+            msg!("Oracles must be distinct and at most {}", MAX_ORACLES);
+            Err(ProgramError::from(BettingError::InvalidOracleConfig))
+        }
+    }
+    pub fn get_oracle_index(&self, oracle: &Pubkey) -> Option<usize> {
+        self.oracles.iter().position(|key| key == oracle)
+    }
+}
+impl BettingAccount for OracleConfig {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::OracleConfig;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::{hash::hashv, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::{
+        error::BettingError,
+        state::{
+            game::{
+                coinflip::{CoinFlipInput, CoinFlipSide},
+                BetInput,
+            },
+            vrf_result::VrfResult,
+        },
+    };
+
+    use super::{check_hash_chain_link, OracleConfig, Randomness, RandomnessSource};
+
+    fn vrf_result() -> VrfResult {
+        let mut vrf_result = VrfResult::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            7,
+            [3; 72],
+            100,
+            100,
+            BetInput::CoinFlip {
+                input: CoinFlipInput {
+                    wager: 100,
+                    side: CoinFlipSide::Head,
+                },
+            },
+        );
+        vrf_result.beta = [5; 64];
+        vrf_result
+    }
+
+    #[test]
+    fn test_randomness_seed() {
+        let vrf_result = vrf_result();
+        assert_eq!(Randomness::Vrf.seed(&vrf_result).unwrap(), [5; 64]);
+        // the revealed server seed is mixed with the client seed and the bet id
+        assert_eq!(
+            Randomness::HashChain { index: 0 }.seed(&vrf_result).unwrap(),
+            hashv(&[&[5; 32], &[3; 72], &7u32.to_le_bytes()]).to_bytes()
+        );
+        let randomness = Randomness::Oracles {
+            threshold: 2,
+            contributions: 0b100,
+        };
+        assert!(!randomness.is_complete());
+        assert_eq!(
+            randomness.seed(&vrf_result).unwrap_err(),
+            ProgramError::from(BettingError::VrfResultNotFullfilled)
+        );
+        let randomness = Randomness::Oracles {
+            threshold: 2,
+            contributions: 0b101,
+        };
+        assert!(randomness.is_complete());
+        assert_eq!(randomness.seed(&vrf_result).unwrap(), [5; 64]);
+    }
+
+    #[test]
+    fn test_check_hash_chain_link() {
+        let server_seed = [9; 32];
+        let head = hashv(&[&hashv(&[&server_seed]).to_bytes()]).to_bytes();
+        // the chain is revealed from the link right below the head
+        let link = hashv(&[&server_seed]).to_bytes();
+        assert!(check_hash_chain_link(&head, &link).is_ok());
+        assert!(check_hash_chain_link(&link, &server_seed).is_ok());
+        assert_eq!(
+            check_hash_chain_link(&head, &server_seed).unwrap_err(),
+            ProgramError::from(BettingError::InvalidHashChainLink)
+        );
+    }
+
+    #[test]
+    fn test_oracle_config_check_oracles() {
+        let oracles: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        assert!(OracleConfig::check_oracles(&oracles).is_ok());
+        let config = OracleConfig::new(oracles.clone());
+        assert_eq!(config.get_oracle_index(&oracles[3]), Some(3));
+        assert_eq!(config.get_oracle_index(&Pubkey::new_unique()), None);
+        // no oracles, too many oracles and duplicates
+        let mut too_many = oracles.clone();
+        too_many.push(Pubkey::new_unique());
+        let duplicates = vec![oracles[0], oracles[1], oracles[0]];
+        for oracles in [vec![], too_many, duplicates] {
+            assert_eq!(
+                OracleConfig::check_oracles(&oracles).unwrap_err(),
+                ProgramError::from(BettingError::InvalidOracleConfig)
+            );
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankAccount;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    constants::{MAX_CALLBACK_ACCOUNTS, MAX_CALLBACK_DATA_LEN},
+    error::BettingError,
+};
+
+use super::{BettingAccount, StateAccountType};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CallbackAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+/// Instruction invoked once a request is fullfilled, signed by the request account
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct RandomnessCallback {
+    pub program_id: Pubkey,
+    // passed after the request account
+    pub accounts: Vec<CallbackAccount>,
+    // the beta is appended to it
+    pub instruction_data: Vec<u8>,
+}
+
+impl RandomnessCallback {
+    pub fn check_callback(&self) -> Result<(), ProgramError> {
+        if self.accounts.len() > MAX_CALLBACK_ACCOUNTS || self.instruction_data.len() > MAX_CALLBACK_DATA_LEN || self.program_id == crate::id() {
+            msg!(
+                "Callbacks take at most {} accounts and {} bytes of data, and can not call back into this program",
+                MAX_CALLBACK_ACCOUNTS,
+                MAX_CALLBACK_DATA_LEN
+            );
+            return Err(ProgramError::from(BettingError::InvalidCallback));
+        }
+        Ok(())
+    }
+}
+
+/// Randomness requested by another program, fullfilled by the operator's VRF like the bets
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct RandomnessRequest {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub bump: u8,
+    pub is_fullfilled: bool,
+    // signer of the request, usually a PDA of the calling program
+    pub requester: Pubkey,
+    pub seed: [u8; 32],
+    pub alpha: [u8; 72],
+    pub beta: [u8; 64],
+    pub pi: [u8; 80],
+    pub callback: Option<RandomnessCallback>,
+}
+
+impl RandomnessRequest {
+    pub fn new(requester: Pubkey, seed: [u8; 32], alpha: [u8; 72], callback: Option<RandomnessCallback>, bump: u8) -> Self {
+        Self {
+            account_type: StateAccountType::RandomnessRequest,
+            version: Self::VERSION,
+            bump,
+            is_fullfilled: false,
+            requester,
+            seed,
+            alpha,
+            beta: [0; 64],
+            pi: [0; 80],
+            callback,
+        }
+    }
+    pub fn find_pda(requester: &Pubkey, seed: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"RandomnessRequest".as_ref(), requester.as_ref(), seed.as_ref()], &crate::id())
+    }
+    /// Callback instruction of a fullfilled request, the request account comes first as a signer
+    pub fn callback_instruction(&self, request: &Pubkey) -> Option<Instruction> {
+        self.callback.as_ref().map(|callback| {
+            let mut accounts = vec![AccountMeta::new_readonly(*request, true)];
+            accounts.extend(callback.accounts.iter().map(|account| AccountMeta {
+                pubkey: account.pubkey,
+                is_signer: false,
+                is_writable: account.is_writable,
+            }));
+            Instruction {
+                program_id: callback.program_id,
+                accounts,
+                data: [callback.instruction_data.as_slice(), &self.beta].concat(),
+            }
+        })
+    }
+}
+impl BettingAccount for RandomnessRequest {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::RandomnessRequest;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::{instruction::AccountMeta, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::error::BettingError;
+
+    use super::{CallbackAccount, RandomnessCallback, RandomnessRequest};
+
+    #[test]
+    fn test_randomness_request_callback_instruction() {
+        let requester = Pubkey::new_unique();
+        let (request, bump) = RandomnessRequest::find_pda(&requester, &[1; 32]);
+        let mut request_state = RandomnessRequest::new(requester, [1; 32], [2; 72], None, bump);
+        assert!(request_state.callback_instruction(&request).is_none());
+
+        let callback = RandomnessCallback {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![CallbackAccount {
+                pubkey: Pubkey::new_unique(),
+                is_writable: true,
+            }],
+            instruction_data: vec![7, 8],
+        };
+        callback.check_callback().unwrap();
+        request_state.callback = Some(callback.clone());
+        request_state.beta = [3; 64];
+        let instruction = request_state.callback_instruction(&request).unwrap();
+        assert_eq!(instruction.program_id, callback.program_id);
+        assert_eq!(
+            instruction.accounts,
+            [AccountMeta::new_readonly(request, true), AccountMeta::new(callback.accounts[0].pubkey, false)]
+        );
+        // the beta follows the registered data
+        assert_eq!(instruction.data[..2], [7, 8]);
+        assert_eq!(instruction.data[2..], [3; 64]);
+    }
+
+    #[test]
+    fn test_randomness_callback_check_callback() {
+        let callback = RandomnessCallback {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![],
+            instruction_data: vec![],
+        };
+        let invalid_callbacks = [
+            RandomnessCallback {
+                accounts: vec![
+                    CallbackAccount {
+                        pubkey: Pubkey::new_unique(),
+                        is_writable: false,
+                    };
+                    9
+                ],
+                ..callback.clone()
+            },
+            RandomnessCallback {
+                instruction_data: vec![0; 65],
+                ..callback.clone()
+            },
+            RandomnessCallback {
+                program_id: crate::id(),
+                ..callback.clone()
+            },
+        ];
+        assert!(callback.check_callback().is_ok());
+        for callback in invalid_callbacks {
+            assert_eq!(callback.check_callback().unwrap_err(), ProgramError::from(BettingError::InvalidCallback));
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankAccount;
+use solana_program::{
+    account_info::AccountInfo, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar,
+};
+
+use crate::{
+    checks::{check_is_writable, check_pda_cannonical_bump},
+    constants::MAX_REFERRAL_TIERS,
+    error::BettingError,
+};
+
+use super::{BettingAccount, StateAccountType};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct Referral {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub referrer: Pubkey,
+    pub referred_users: u32,
+    pub referred_volume: u64,
+    pub total_lamports_earned: u64,
+    pub unclaimed_lamports: u64,
+}
+
+impl Referral {
+    pub fn new(referrer: Pubkey) -> Self {
+        Self {
+            account_type: StateAccountType::Referral,
+            version: Self::VERSION,
+            referrer,
+            referred_users: 0,
+            referred_volume: 0,
+            total_lamports_earned: 0,
+            unclaimed_lamports: 0,
+        }
+    }
+    /// Deserialize the referral PDA of a referrer, `None` for referrers of users created before the referral PDAs
+    pub fn load(referral_account_info: &AccountInfo) -> Result<Option<Self>, ProgramError> {
+        if referral_account_info.data_is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self::try_from_account_info(referral_account_info)?))
+    }
+    /// Deserialize the referral PDA of a referrer, creating it first if it does not exist yet
+    pub fn load_or_create<'a>(referrer: &Pubkey, referral_account_info: &AccountInfo<'a>, payer_account_info: &AccountInfo<'a>) -> Result<Self, ProgramError> {
+        check_is_writable(referral_account_info)?;
+        let referral_pda_bump = check_pda_cannonical_bump(referral_account_info, &[b"Referral".as_ref(), referrer.as_ref()])?;
+        if !referral_account_info.data_is_empty() {
+            return Self::try_from_account_info(referral_account_info);
+        }
+
+        let referral_state = Self::new(*referrer);
+        let referral_data = referral_state.try_to_vec()?;
+        let referral_data_len = referral_data.len();
+        let min_rent = Rent::get()?.minimum_balance(referral_data_len);
+        let referral_pda_signer_seeds = &[b"Referral".as_ref(), referrer.as_ref(), &[referral_pda_bump]];
+        let referral_create_ix = system_instruction::create_account(
+            payer_account_info.key,
+            referral_account_info.key,
+            min_rent,
+            referral_data_len.try_into().unwrap(),
+            &crate::id(),
+        );
+        invoke_signed(
+            &referral_create_ix,
+            &[payer_account_info.clone(), referral_account_info.clone()],
+            &[referral_pda_signer_seeds],
+        )?;
+        referral_account_info.data.borrow_mut().copy_from_slice(&referral_data);
+
+        Ok(referral_state)
+    }
+}
+impl BettingAccount for Referral {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::Referral;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReferralTier {
+    pub min_referred_volume: u64,
+    // basis points of the profit share paid to the referrer
+    pub share: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct ReferralConfig {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub tiers: Vec<ReferralTier>,
+}
+
+impl ReferralConfig {
+    pub fn new(tiers: Vec<ReferralTier>) -> Self {
+        Self {
+            account_type: StateAccountType::ReferralConfig,
+            version: Self::VERSION,
+            tiers,
+        }
+    }
+    pub fn check_tiers(tiers: &[ReferralTier]) -> Result<(), ProgramError> {
+        let is_valid = !tiers.is_empty()
+            && tiers.len() <= MAX_REFERRAL_TIERS
+            && tiers[0].min_referred_volume == 0
+            && tiers.windows(2).all(|pair| pair[0].min_referred_volume < pair[1].min_referred_volume)
+            && tiers.iter().all(|tier| tier.share <= 10000);
+        if is_valid {
+            Ok(())
+        } else {
+            msg!("Referral tiers must start at 0 volume, be sorted by volume and have shares of at most 10000");
+            Err(ProgramError::from(BettingError::InvalidReferralTiers))
+        }
+    }
+    pub fn get_share(&self, referred_volume: u64) -> u64 {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.min_referred_volume <= referred_volume)
+            .map(|tier| tier.share)
+            .unwrap_or(0)
+    }
+}
+impl BettingAccount for ReferralConfig {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::ReferralConfig;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::program_error::ProgramError;
+
+    use crate::error::BettingError;
+
+    use super::{ReferralConfig, ReferralTier};
+
+    #[test]
+    fn test_referral_config_get_share() {
+        let config = ReferralConfig::new(vec![
+            ReferralTier {
+                min_referred_volume: 0,
+                share: 2000,
+            },
+            ReferralTier {
+                min_referred_volume: 1000,
+                share: 3500,
+            },
+            ReferralTier {
+                min_referred_volume: 10000,
+                share: 5000,
+            },
+        ]);
+        assert!(ReferralConfig::check_tiers(&config.tiers).is_ok());
+        assert_eq!(config.get_share(0), 2000);
+        assert_eq!(config.get_share(999), 2000);
+        assert_eq!(config.get_share(1000), 3500);
+        assert_eq!(config.get_share(u64::MAX), 5000);
+    }
+    #[test]
+    fn test_referral_config_check_tiers() {
+        // no tiers
+        assert_eq!(
+            ReferralConfig::check_tiers(&[]).unwrap_err(),
+            ProgramError::from(BettingError::InvalidReferralTiers)
+        );
+        // first tier does not start at 0
+        assert_eq!(
+            ReferralConfig::check_tiers(&[ReferralTier {
+                min_referred_volume: 10,
+                share: 2000
+            }])
+            .unwrap_err(),
+            ProgramError::from(BettingError::InvalidReferralTiers)
+        );
+        // not sorted
+        assert_eq!(
+            ReferralConfig::check_tiers(&[
+                ReferralTier {
+                    min_referred_volume: 0,
+                    share: 2000
+                },
+                ReferralTier {
+                    min_referred_volume: 0,
+                    share: 3000
+                }
+            ])
+            .unwrap_err(),
+            ProgramError::from(BettingError::InvalidReferralTiers)
+        );
+        // share too high
+        assert_eq!(
+            ReferralConfig::check_tiers(&[ReferralTier {
+                min_referred_volume: 0,
+                share: 10001
+            }])
+            .unwrap_err(),
+            ProgramError::from(BettingError::InvalidReferralTiers)
+        );
+    }
+}
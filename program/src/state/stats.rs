@@ -1,33 +1,61 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::BorshDeserialize;
+use bytemuck::{Pod, Zeroable};
 use shank::ShankAccount;
 
-use super::{BettingAccount, StateAccountType};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
-#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+use super::{
+    impl_pod_borsh,
+    legacy::{StatsV0, StatsV1, StatsV2, StatsV3},
+    unique_legacy_layout, BettingAccount, StateAccountType, ZeroCopyAccount,
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, ShankAccount)]
 pub struct Stats {
-    pub account_type: StateAccountType,
+    pub account_type: u8,
+    pub version: u8,
+    pub bump: u8,
+    pub padding: [u8; 5],
     pub total_games: u64,
     pub total_users: u32,
     pub total_bets: u32,
     pub total_wager: u64,
-    pub total_lamports_won_by_bettors: u64,
+    // net lamports won by the bettors, negative when the hosts are ahead
+    pub total_bettor_pnl: i64,
+    pub total_host_pnl: i64,
     pub total_lamports_deposited: u64,
     pub total_lamports_withdrew: u64,
+    pub total_host_commission_lamports: u64,
+    pub total_operator_commission_lamports: u64,
 }
 impl Stats {
     pub fn new() -> Self {
+        Self::new_with_bump(Self::find_pda().1)
+    }
+    pub fn new_with_bump(bump: u8) -> Self {
         Self {
-            account_type: StateAccountType::Stats,
+            account_type: StateAccountType::Stats as u8,
+            version: Self::VERSION,
+            bump,
+            padding: [0; 5],
             total_games: 0,
             total_bets: 0,
             total_wager: 0,
-            total_lamports_won_by_bettors: 0,
+            total_bettor_pnl: 0,
+            total_host_pnl: 0,
             total_users: 0,
             total_lamports_deposited: 0,
             total_lamports_withdrew: 0,
+            total_host_commission_lamports: 0,
+            total_operator_commission_lamports: 0,
         }
     }
+    pub fn find_pda() -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"Stats".as_ref()], &crate::id())
+    }
 }
+impl_pod_borsh!(Stats);
 impl Default for Stats {
     fn default() -> Self {
         Self::new()
@@ -35,4 +63,16 @@ impl Default for Stats {
 }
 impl BettingAccount for Stats {
     const ACCOUNT_TYPE: StateAccountType = StateAccountType::Stats;
+    const VERSION: u8 = 4;
+
+    fn try_from_legacy_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        unique_legacy_layout([
+            StatsV3::try_from_slice(data).ok().filter(|v3| v3.version == 3),
+            StatsV2::try_from_slice(data).ok().filter(|v2| v2.version == 2).map(StatsV3::from),
+            StatsV1::try_from_slice(data).ok().filter(|v1| v1.version == 1).map(StatsV3::from),
+            StatsV0::try_from_slice(data).ok().map(StatsV3::from),
+        ])
+        .map(Stats::from)
+    }
 }
+impl ZeroCopyAccount for Stats {}
@@ -1,52 +1,370 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::BorshDeserialize;
+use bytemuck::{Pod, Zeroable};
 use shank::ShankAccount;
-use solana_program::pubkey::Pubkey;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
-use crate::constants::PROFIT_SHARE;
+use crate::{
+    constants::{MAX_AVATAR_URI_LEN, MAX_USERNAME_LEN, MIN_USERNAME_LEN, PROFIT_SHARE},
+    error::BettingError,
+    math::{mul_div, signed_diff, to_signed, CheckedMath},
+};
 
-use super::{BettingAccount, StateAccountType};
+use super::{
+    impl_pod_borsh,
+    legacy::{UserAccountV0, UserAccountV1, UserAccountV2, UserAccountV3},
+    unique_legacy_layout, BettingAccount, StateAccountType, ZeroCopyAccount,
+};
 
-#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub const DISPLAY_FLAG_HIDE_FROM_LEADERBOARD: u8 = 1 << 0;
+pub const DISPLAY_FLAG_HIDE_STATS: u8 = 1 << 1;
+pub const DISPLAY_FLAGS_ALL: u8 = DISPLAY_FLAG_HIDE_FROM_LEADERBOARD | DISPLAY_FLAG_HIDE_STATS;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, ShankAccount)]
 pub struct UserAccount {
-    pub account_type: StateAccountType,
-    pub authority: Pubkey,
+    pub account_type: u8,
+    pub version: u8,
+    pub bump: u8,
+    pub display_flags: u8,
     pub total_bets: u32,
+    pub authority: Pubkey,
     pub current_lamports: u64,
     pub lamports_deposited: u64,
     pub lamports_withdrew: u64,
     pub active_vrf_results: u32,
     pub games_hosted: u32,
-    pub referral: Option<Pubkey>,
-    pub username: Option<String>,
+    // realized net winnings of the bets placed by the user
+    pub betting_pnl: i64,
+    // net lamports received from the game bankrolls staked by the user
+    pub hosting_pnl: i64,
+    // betting profit already charged with the profit share
+    pub profit_high_water_mark: i64,
+    // the default pubkey when the user has no referral
+    pub referral_key: Pubkey,
+    // the strings are unset when their length is zero
+    pub username_len: u8,
+    // shank only reads literal lengths, MAX_USERNAME_LEN and MAX_AVATAR_URI_LEN
+    pub username_bytes: [u8; 32],
+    pub avatar_uri_len: u8,
+    pub avatar_uri_bytes: [u8; 200],
+    pub padding: [u8; 6],
 }
 
 impl UserAccount {
     pub fn new(authority: Pubkey, referral: Option<Pubkey>, username: Option<String>) -> Self {
-        Self {
-            account_type: StateAccountType::UserAccount,
-            authority,
+        Self::new_with_bump(authority, referral, username, Self::find_pda(&authority).1)
+    }
+    pub fn new_with_bump(authority: Pubkey, referral: Option<Pubkey>, username: Option<String>, bump: u8) -> Self {
+        let mut user_account = Self {
+            account_type: StateAccountType::UserAccount as u8,
+            version: Self::VERSION,
+            bump,
+            display_flags: 0,
             total_bets: 0,
+            authority,
             current_lamports: 0,
             lamports_deposited: 0,
             lamports_withdrew: 0,
             active_vrf_results: 0,
             games_hosted: 0,
-            referral,
-            username,
+            betting_pnl: 0,
+            hosting_pnl: 0,
+            profit_high_water_mark: 0,
+            referral_key: referral.unwrap_or_default(),
+            username_len: 0,
+            username_bytes: [0; MAX_USERNAME_LEN],
+            avatar_uri_len: 0,
+            avatar_uri_bytes: [0; MAX_AVATAR_URI_LEN],
+            padding: [0; 6],
+        };
+        user_account.set_username(username.as_deref());
+        user_account
+    }
+    pub fn find_pda(authority: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"UserAccount".as_ref(), authority.as_ref()], &crate::id())
+    }
+    pub fn referral(&self) -> Option<Pubkey> {
+        Some(self.referral_key).filter(|referral| referral != &Pubkey::default())
+    }
+    pub fn username(&self) -> Option<&str> {
+        read_str(&self.username_bytes, self.username_len)
+    }
+    /// Usernames not fitting the fixed field are dropped, the instructions check them beforehand
+    pub fn set_username(&mut self, username: Option<&str>) {
+        self.username_len = write_str(&mut self.username_bytes, username);
+    }
+    pub fn avatar_uri(&self) -> Option<&str> {
+        read_str(&self.avatar_uri_bytes, self.avatar_uri_len)
+    }
+    /// Avatar URIs not fitting the fixed field are dropped, the instructions check them beforehand
+    pub fn set_avatar_uri(&mut self, avatar_uri: Option<&str>) {
+        self.avatar_uri_len = write_str(&mut self.avatar_uri_bytes, avatar_uri);
+    }
+    pub fn check_username(username: &str) -> Result<(), ProgramError> {
+        if username.len() < MIN_USERNAME_LEN
+            || username.len() > MAX_USERNAME_LEN
+            || !username.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-')
+        {
+            Err(ProgramError::from(BettingError::InvalidUsername))
+        } else {
+            Ok(())
         }
     }
-    pub fn get_profit_share(&self, withdraw_amount: u64) -> u64 {
-        assert!(withdraw_amount <= self.current_lamports);
-        let profit = if withdraw_amount + self.lamports_withdrew <= self.lamports_deposited {
-            0
-        } else if self.lamports_withdrew <= self.lamports_deposited && self.lamports_withdrew + withdraw_amount > self.lamports_deposited {
-            self.lamports_withdrew + withdraw_amount - self.lamports_deposited
+    pub fn check_avatar_uri(avatar_uri: &str) -> Result<(), ProgramError> {
+        if avatar_uri.is_empty() || avatar_uri.len() > MAX_AVATAR_URI_LEN || !avatar_uri.bytes().all(|c| c.is_ascii_graphic()) {
+            Err(ProgramError::from(BettingError::InvalidAvatarUri))
         } else {
-            withdraw_amount
-        };
-        profit * PROFIT_SHARE / 10000
+            Ok(())
+        }
+    }
+    pub fn check_display_flags(display_flags: u8) -> Result<(), ProgramError> {
+        if display_flags & !DISPLAY_FLAGS_ALL != 0 {
+            Err(ProgramError::InvalidArgument)
+        } else {
+            Ok(())
+        }
+    }
+    /// Betting profit above the high-water mark realized by a withdrawal, the balance counts as principal first
+    pub fn get_withdrawn_profit(&self, withdraw_amount: u64) -> Result<u64, ProgramError> {
+        if withdraw_amount > self.current_lamports {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        let unmarked_profit = u64::try_from(self.betting_pnl.try_sub(self.profit_high_water_mark)?)
+            .unwrap_or(0)
+            .min(self.current_lamports);
+        let principal = self.current_lamports - unmarked_profit;
+        Ok(withdraw_amount.saturating_sub(principal))
+    }
+    pub fn get_profit_share(&self, withdraw_amount: u64) -> Result<u64, ProgramError> {
+        mul_div(self.get_withdrawn_profit(withdraw_amount)?, PROFIT_SHARE, 10000)
+    }
+    /// Debit a withdrawal and raise the high-water mark by the profit it realizes, returning the profit share
+    pub fn record_withdraw(&mut self, withdraw_amount: u64) -> Result<u64, ProgramError> {
+        let profit = self.get_withdrawn_profit(withdraw_amount)?;
+        self.profit_high_water_mark = self.profit_high_water_mark.try_add(to_signed(profit)?)?;
+        self.current_lamports = self.current_lamports.try_sub(withdraw_amount)?;
+        self.lamports_withdrew = self.lamports_withdrew.try_add(withdraw_amount)?;
+        mul_div(profit, PROFIT_SHARE, 10000)
+    }
+    pub fn record_bet_result(&mut self, wager: u64, payout: u64) -> Result<(), ProgramError> {
+        self.betting_pnl = self.betting_pnl.try_add(signed_diff(payout, wager)?)?;
+        Ok(())
     }
 }
+impl_pod_borsh!(UserAccount);
 impl BettingAccount for UserAccount {
     const ACCOUNT_TYPE: StateAccountType = StateAccountType::UserAccount;
+    const VERSION: u8 = 4;
+
+    fn try_from_legacy_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        unique_legacy_layout([
+            UserAccountV3::try_from_slice(data).ok().filter(|v3| v3.version == 3),
+            UserAccountV2::try_from_slice(data).ok().filter(|v2| v2.version == 2).map(UserAccountV3::from),
+            UserAccountV1::try_from_slice(data).ok().filter(|v1| v1.version == 1).map(UserAccountV3::from),
+            UserAccountV0::try_from_slice(data).ok().map(UserAccountV3::from),
+        ])?
+        .try_into()
+    }
+}
+impl ZeroCopyAccount for UserAccount {}
+
+fn read_str(bytes: &[u8], len: u8) -> Option<&str> {
+    bytes
+        .get(..len as usize)
+        .filter(|bytes| !bytes.is_empty())
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+}
+fn write_str(bytes: &mut [u8], value: Option<&str>) -> u8 {
+    bytes.fill(0);
+    match value.filter(|value| value.len() <= bytes.len()) {
+        Some(value) => {
+            bytes[..value.len()].copy_from_slice(value.as_bytes());
+            value.len() as u8
+        }
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use borsh::BorshSerialize;
+    use proptest::prelude::*;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::{constants::PROFIT_SHARE, error::BettingError, state::BettingAccount};
+
+    use super::{UserAccount, DISPLAY_FLAG_HIDE_STATS};
+
+    #[derive(Clone, Debug)]
+    enum Action {
+        Deposit(u64),
+        Withdraw(u64),
+        Bet { wager: u64, payout: u64 },
+    }
+
+    fn deposit(user_account: &mut UserAccount, lamports: u64) {
+        user_account.current_lamports += lamports;
+        user_account.lamports_deposited += lamports;
+    }
+
+    fn bet(user_account: &mut UserAccount, wager: u64, payout: u64) {
+        user_account.current_lamports = user_account.current_lamports - wager + payout;
+        user_account.record_bet_result(wager, payout).unwrap();
+    }
+
+    fn action_strategy(max_payout_rate: u64) -> impl Strategy<Value = Action> {
+        prop_oneof![
+            (1..100_000u64).prop_map(Action::Deposit),
+            (1..100_000u64).prop_map(Action::Withdraw),
+            (1..100_000u64)
+                .prop_flat_map(move |wager| (Just(wager), 0..=wager * max_payout_rate))
+                .prop_map(|(wager, payout)| Action::Bet { wager, payout }),
+        ]
+    }
+
+    /// Run the actions, returning the profit charged by each withdrawal and the peak betting profit
+    fn run_actions(user_account: &mut UserAccount, actions: &[Action]) -> (Vec<u64>, i64) {
+        let mut charged_profits = vec![];
+        let mut peak_betting_pnl = 0;
+        for action in actions {
+            match *action {
+                Action::Deposit(lamports) => deposit(user_account, lamports),
+                Action::Withdraw(lamports) if lamports > user_account.current_lamports => {
+                    assert_eq!(user_account.record_withdraw(lamports).unwrap_err(), ProgramError::InsufficientFunds);
+                }
+                Action::Withdraw(lamports) => {
+                    let profit = user_account.get_withdrawn_profit(lamports).unwrap();
+                    assert!(profit <= lamports);
+                    assert_eq!(user_account.record_withdraw(lamports).unwrap(), profit * PROFIT_SHARE / 10000);
+                    charged_profits.push(profit);
+                }
+                Action::Bet { wager, .. } if wager > user_account.current_lamports => {}
+                Action::Bet { wager, payout } => bet(user_account, wager, payout),
+            }
+            peak_betting_pnl = peak_betting_pnl.max(user_account.betting_pnl);
+            // the balance is always the principal plus the betting result
+            assert_eq!(
+                user_account.current_lamports as i64,
+                user_account.lamports_deposited as i64 - user_account.lamports_withdrew as i64 + user_account.betting_pnl
+            );
+        }
+        (charged_profits, peak_betting_pnl)
+    }
+
+    proptest! {
+        #[test]
+        fn test_user_account_profit_share_never_above_peak_profit(actions in prop::collection::vec(action_strategy(3), 1..50)) {
+            let mut user_account = UserAccount::new(Pubkey::new_unique(), None, None);
+            let (charged_profits, peak_betting_pnl) = run_actions(&mut user_account, &actions);
+            // the same profit is never charged twice
+            let total_charged_profit: u64 = charged_profits.iter().sum();
+            prop_assert_eq!(user_account.profit_high_water_mark, total_charged_profit as i64);
+            prop_assert!(user_account.profit_high_water_mark <= peak_betting_pnl);
+        }
+
+        #[test]
+        fn test_user_account_profit_share_not_charged_without_winnings(actions in prop::collection::vec(action_strategy(1), 1..50)) {
+            let mut user_account = UserAccount::new(Pubkey::new_unique(), None, None);
+            let (charged_profits, _) = run_actions(&mut user_account, &actions);
+            // bets paying at most the wager back never make a profit
+            prop_assert!(charged_profits.iter().all(|profit| *profit == 0));
+        }
+
+        #[test]
+        fn test_user_account_profit_share_ignores_hosting(actions in prop::collection::vec(action_strategy(3), 1..50), hosting_gain in 0..1_000_000u64) {
+            let mut user_account = UserAccount::new(Pubkey::new_unique(), None, None);
+            run_actions(&mut user_account, &actions);
+            let profit = user_account.get_withdrawn_profit(user_account.current_lamports).unwrap();
+            // lamports returned by a game bankroll are not betting profit
+            user_account.current_lamports += hosting_gain;
+            user_account.hosting_pnl += hosting_gain as i64;
+            prop_assert_eq!(user_account.get_withdrawn_profit(user_account.current_lamports).unwrap(), profit);
+        }
+    }
+
+    #[test]
+    fn test_user_account_profit_share_high_water_mark() {
+        let mut user_account = UserAccount::new(Pubkey::new_unique(), None, None);
+        deposit(&mut user_account, 10000);
+        bet(&mut user_account, 2000, 4000);
+        // the principal is withdrawn first
+        assert_eq!(user_account.record_withdraw(10000).unwrap(), 0);
+        assert_eq!(user_account.record_withdraw(2000).unwrap(), 2000 * PROFIT_SHARE / 10000);
+        assert_eq!(user_account.profit_high_water_mark, 2000);
+        // a redeposit is principal
+        deposit(&mut user_account, 5000);
+        assert_eq!(user_account.get_profit_share(5000).unwrap(), 0);
+        // the winnings recovering a loss are below the mark
+        bet(&mut user_account, 3000, 0);
+        bet(&mut user_account, 2000, 4000);
+        assert_eq!(user_account.betting_pnl, 1000);
+        assert_eq!(user_account.get_profit_share(4000).unwrap(), 0);
+        // only the profit above the mark is charged
+        bet(&mut user_account, 2000, 5000);
+        assert_eq!(user_account.betting_pnl, 4000);
+        assert_eq!(user_account.get_withdrawn_profit(7000).unwrap(), 2000);
+        assert_eq!(user_account.record_withdraw(7000).unwrap(), 2000 * PROFIT_SHARE / 10000);
+        assert_eq!(user_account.profit_high_water_mark, 4000);
+        assert_eq!(user_account.record_withdraw(1).unwrap_err(), ProgramError::InsufficientFunds);
+    }
+
+    #[test]
+    fn test_user_account_check_username() {
+        assert!(UserAccount::check_username("Username").is_ok());
+        assert!(UserAccount::check_username("user_name-42").is_ok());
+        // too short
+        assert_eq!(
+            UserAccount::check_username("ab").unwrap_err(),
+            ProgramError::from(BettingError::InvalidUsername)
+        );
+        // too long
+        assert_eq!(
+            UserAccount::check_username(&"a".repeat(33)).unwrap_err(),
+            ProgramError::from(BettingError::InvalidUsername)
+        );
+        // invalid characters
+        assert_eq!(
+            UserAccount::check_username("user name").unwrap_err(),
+            ProgramError::from(BettingError::InvalidUsername)
+        );
+        assert_eq!(
+            UserAccount::check_username("üser").unwrap_err(),
+            ProgramError::from(BettingError::InvalidUsername)
+        );
+    }
+    #[test]
+    fn test_user_account_check_avatar_uri_and_display_flags() {
+        assert!(UserAccount::check_avatar_uri("https://example.com/avatar.png").is_ok());
+        assert_eq!(
+            UserAccount::check_avatar_uri("").unwrap_err(),
+            ProgramError::from(BettingError::InvalidAvatarUri)
+        );
+        assert_eq!(
+            UserAccount::check_avatar_uri("https://example.com/my avatar.png").unwrap_err(),
+            ProgramError::from(BettingError::InvalidAvatarUri)
+        );
+        assert!(UserAccount::check_display_flags(DISPLAY_FLAG_HIDE_STATS).is_ok());
+        assert_eq!(UserAccount::check_display_flags(1 << 7).unwrap_err(), ProgramError::InvalidArgument);
+    }
+    #[test]
+    fn test_user_account_fixed_layout_fields() {
+        let referral = Pubkey::new_unique();
+        let mut user_account = UserAccount::new(Pubkey::new_unique(), Some(referral), Some("Username".to_string()));
+        assert_eq!(user_account.referral(), Some(referral));
+        assert_eq!(user_account.username(), Some("Username"));
+        assert_eq!(user_account.avatar_uri(), None);
+        user_account.set_avatar_uri(Some("https://example.com/avatar.png"));
+        user_account.set_username(None);
+        assert_eq!(user_account.avatar_uri(), Some("https://example.com/avatar.png"));
+        assert_eq!(user_account.username(), None);
+        // strings not fitting the fixed fields are dropped
+        user_account.set_username(Some(&"a".repeat(33)));
+        assert_eq!(user_account.username(), None);
+        assert_eq!(UserAccount::new(Pubkey::new_unique(), None, None).referral(), None);
+        // the borsh encoding is the in-memory layout
+        let data = user_account.try_to_vec().unwrap();
+        assert_eq!(data.len(), std::mem::size_of::<UserAccount>());
+        assert_eq!(data, bytemuck::bytes_of(&user_account));
+        assert_eq!(UserAccount::unpack(&data).unwrap().avatar_uri(), Some("https://example.com/avatar.png"));
+    }
 }
@@ -0,0 +1,366 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankAccount;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
+    system_instruction, sysvar::Sysvar,
+};
+
+use crate::{
+    checks::{check_is_writable, check_pda_cannonical_bump},
+    constants::{LIMIT_LOOSEN_DELAY, LIMIT_WINDOW_BUCKETS, SECONDS_PER_DAY, SECONDS_PER_WEEK},
+    error::BettingError,
+};
+
+use super::{BettingAccount, StateAccountType};
+
+pub const NO_LIMIT: u64 = u64::MAX;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitSettings {
+    pub daily_deposit_cap: u64,
+    pub weekly_deposit_cap: u64,
+    pub daily_wager_cap: u64,
+    pub weekly_wager_cap: u64,
+    pub daily_loss_cap: u64,
+    pub weekly_loss_cap: u64,
+}
+
+impl LimitSettings {
+    pub fn none() -> Self {
+        Self {
+            daily_deposit_cap: NO_LIMIT,
+            weekly_deposit_cap: NO_LIMIT,
+            daily_wager_cap: NO_LIMIT,
+            weekly_wager_cap: NO_LIMIT,
+            daily_loss_cap: NO_LIMIT,
+            weekly_loss_cap: NO_LIMIT,
+        }
+    }
+    /// The stricter of each pair of caps
+    pub fn tightest(&self, other: &Self) -> Self {
+        Self {
+            daily_deposit_cap: self.daily_deposit_cap.min(other.daily_deposit_cap),
+            weekly_deposit_cap: self.weekly_deposit_cap.min(other.weekly_deposit_cap),
+            daily_wager_cap: self.daily_wager_cap.min(other.daily_wager_cap),
+            weekly_wager_cap: self.weekly_wager_cap.min(other.weekly_wager_cap),
+            daily_loss_cap: self.daily_loss_cap.min(other.daily_loss_cap),
+            weekly_loss_cap: self.weekly_loss_cap.min(other.weekly_loss_cap),
+        }
+    }
+}
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// Amounts of the last window split into buckets, buckets older than the window are cleared as time passes
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RollingWindow {
+    pub newest_bucket: i64,
+    pub buckets: [u64; LIMIT_WINDOW_BUCKETS],
+}
+
+impl RollingWindow {
+    fn roll(&mut self, now: i64, window: i64) {
+        let bucket = now.div_euclid(window / LIMIT_WINDOW_BUCKETS as i64);
+        let elapsed = bucket.saturating_sub(self.newest_bucket);
+        if elapsed >= LIMIT_WINDOW_BUCKETS as i64 {
+            self.buckets = [0; LIMIT_WINDOW_BUCKETS];
+        } else {
+            for expired in self.newest_bucket + 1..=bucket {
+                self.buckets[expired.rem_euclid(LIMIT_WINDOW_BUCKETS as i64) as usize] = 0;
+            }
+        }
+        self.newest_bucket = self.newest_bucket.max(bucket);
+    }
+    pub fn total(&self, now: i64, window: i64) -> u64 {
+        let mut rolled = *self;
+        rolled.roll(now, window);
+        rolled.buckets.iter().fold(0, |total, amount| total.saturating_add(*amount))
+    }
+    pub fn add(&mut self, now: i64, window: i64, amount: u64) {
+        self.roll(now, window);
+        let index = self.newest_bucket.rem_euclid(LIMIT_WINDOW_BUCKETS as i64) as usize;
+        self.buckets[index] = self.buckets[index].saturating_add(amount);
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct UserLimits {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub user_account: Pubkey,
+    pub limits: LimitSettings,
+    pub pending_limits: LimitSettings,
+    // 0 when there are no pending limits
+    pub pending_limits_effective_at: i64,
+    pub excluded_until: i64,
+    pub daily_deposits: RollingWindow,
+    pub weekly_deposits: RollingWindow,
+    pub daily_wagers: RollingWindow,
+    pub weekly_wagers: RollingWindow,
+    pub daily_payouts: RollingWindow,
+    pub weekly_payouts: RollingWindow,
+}
+
+impl UserLimits {
+    pub fn new(user_account: Pubkey) -> Self {
+        Self {
+            account_type: StateAccountType::UserLimits,
+            version: Self::VERSION,
+            user_account,
+            limits: LimitSettings::none(),
+            pending_limits: LimitSettings::none(),
+            pending_limits_effective_at: 0,
+            excluded_until: 0,
+            daily_deposits: RollingWindow::default(),
+            weekly_deposits: RollingWindow::default(),
+            daily_wagers: RollingWindow::default(),
+            weekly_wagers: RollingWindow::default(),
+            daily_payouts: RollingWindow::default(),
+            weekly_payouts: RollingWindow::default(),
+        }
+    }
+    /// Deserialize the limits PDA of a user account, users who never set any limit have no limits account
+    pub fn load(user_account: &Pubkey, user_limits_account_info: &AccountInfo) -> Result<Option<Self>, ProgramError> {
+        check_pda_cannonical_bump(user_limits_account_info, &[b"UserLimits".as_ref(), user_account.as_ref()])?;
+        if user_limits_account_info.data_is_empty() {
+            return Ok(None);
+        }
+        check_is_writable(user_limits_account_info)?;
+        Ok(Some(Self::try_from_account_info(user_limits_account_info)?))
+    }
+    /// Deserialize the limits PDA of a user account, creating it first if it does not exist yet
+    pub fn load_or_create<'a>(
+        user_account: &Pubkey,
+        user_limits_account_info: &AccountInfo<'a>,
+        payer_account_info: &AccountInfo<'a>,
+    ) -> Result<Self, ProgramError> {
+        check_is_writable(user_limits_account_info)?;
+        let user_limits_pda_bump = check_pda_cannonical_bump(user_limits_account_info, &[b"UserLimits".as_ref(), user_account.as_ref()])?;
+        if !user_limits_account_info.data_is_empty() {
+            return Self::try_from_account_info(user_limits_account_info);
+        }
+
+        let user_limits_state = Self::new(*user_account);
+        let user_limits_data = user_limits_state.try_to_vec()?;
+        let user_limits_data_len = user_limits_data.len();
+        let min_rent = Rent::get()?.minimum_balance(user_limits_data_len);
+        let user_limits_pda_signer_seeds = &[b"UserLimits".as_ref(), user_account.as_ref(), &[user_limits_pda_bump]];
+        let user_limits_create_ix = system_instruction::create_account(
+            payer_account_info.key,
+            user_limits_account_info.key,
+            min_rent,
+            user_limits_data_len.try_into().unwrap(),
+            &crate::id(),
+        );
+        invoke_signed(
+            &user_limits_create_ix,
+            &[payer_account_info.clone(), user_limits_account_info.clone()],
+            &[user_limits_pda_signer_seeds],
+        )?;
+        user_limits_account_info.data.borrow_mut().copy_from_slice(&user_limits_data);
+
+        Ok(user_limits_state)
+    }
+    /// Apply the pending limits once their delay has passed
+    pub fn refresh(&mut self, now: i64) {
+        if self.pending_limits_effective_at != 0 && now >= self.pending_limits_effective_at {
+            self.limits = self.pending_limits;
+            self.pending_limits = LimitSettings::none();
+            self.pending_limits_effective_at = 0;
+        }
+    }
+    /// Tightened caps apply immediately, loosened caps only after `LIMIT_LOOSEN_DELAY`
+    pub fn set_limits(&mut self, limits: LimitSettings, now: i64) {
+        self.refresh(now);
+        self.limits = self.limits.tightest(&limits);
+        if self.limits == limits {
+            self.pending_limits = LimitSettings::none();
+            self.pending_limits_effective_at = 0;
+        } else {
+            self.pending_limits = limits;
+            self.pending_limits_effective_at = now.saturating_add(LIMIT_LOOSEN_DELAY);
+        }
+    }
+    /// Exclusions can only be extended, a cool-off is a short self-exclusion
+    pub fn self_exclude(&mut self, until: i64, now: i64) -> ProgramResult {
+        if until <= now || until < self.excluded_until {
+            msg!("Self-exclusion can only be extended, currently excluded until {}", self.excluded_until);
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.excluded_until = until;
+        Ok(())
+    }
+    fn check_not_excluded(&self, now: i64) -> ProgramResult {
+        if now < self.excluded_until {
+            msg!("User account {} is self-excluded until {}", self.user_account, self.excluded_until);
+            Err(ProgramError::from(BettingError::SelfExcluded))
+        } else {
+            Ok(())
+        }
+    }
+    fn check_cap(name: &str, total: u64, amount: u64, cap: u64) -> ProgramResult {
+        if total.saturating_add(amount) > cap {
+            msg!("{} of {} lamports would exceed the cap of {} lamports", name, total.saturating_add(amount), cap);
+            Err(ProgramError::from(BettingError::LimitExceeded))
+        } else {
+            Ok(())
+        }
+    }
+    pub fn record_deposit(&mut self, now: i64, lamports: u64) -> ProgramResult {
+        self.refresh(now);
+        self.check_not_excluded(now)?;
+        Self::check_cap(
+            "Daily deposits",
+            self.daily_deposits.total(now, SECONDS_PER_DAY),
+            lamports,
+            self.limits.daily_deposit_cap,
+        )?;
+        Self::check_cap(
+            "Weekly deposits",
+            self.weekly_deposits.total(now, SECONDS_PER_WEEK),
+            lamports,
+            self.limits.weekly_deposit_cap,
+        )?;
+        self.daily_deposits.add(now, SECONDS_PER_DAY, lamports);
+        self.weekly_deposits.add(now, SECONDS_PER_WEEK, lamports);
+        Ok(())
+    }
+    /// The whole wager counts towards the loss caps until its payout is recorded
+    pub fn record_wager(&mut self, now: i64, lamports: u64) -> ProgramResult {
+        self.refresh(now);
+        self.check_not_excluded(now)?;
+        let daily_wagers = self.daily_wagers.total(now, SECONDS_PER_DAY);
+        let weekly_wagers = self.weekly_wagers.total(now, SECONDS_PER_WEEK);
+        Self::check_cap("Daily wagers", daily_wagers, lamports, self.limits.daily_wager_cap)?;
+        Self::check_cap("Weekly wagers", weekly_wagers, lamports, self.limits.weekly_wager_cap)?;
+        Self::check_cap(
+            "Daily net loss",
+            daily_wagers.saturating_sub(self.daily_payouts.total(now, SECONDS_PER_DAY)),
+            lamports,
+            self.limits.daily_loss_cap,
+        )?;
+        Self::check_cap(
+            "Weekly net loss",
+            weekly_wagers.saturating_sub(self.weekly_payouts.total(now, SECONDS_PER_WEEK)),
+            lamports,
+            self.limits.weekly_loss_cap,
+        )?;
+        self.daily_wagers.add(now, SECONDS_PER_DAY, lamports);
+        self.weekly_wagers.add(now, SECONDS_PER_WEEK, lamports);
+        Ok(())
+    }
+    pub fn record_payout(&mut self, now: i64, lamports: u64) {
+        self.daily_payouts.add(now, SECONDS_PER_DAY, lamports);
+        self.weekly_payouts.add(now, SECONDS_PER_WEEK, lamports);
+    }
+}
+impl BettingAccount for UserLimits {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::UserLimits;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::{
+        constants::{LIMIT_LOOSEN_DELAY, SECONDS_PER_DAY, SECONDS_PER_WEEK},
+        error::BettingError,
+    };
+
+    use super::{LimitSettings, RollingWindow, UserLimits, NO_LIMIT};
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn test_rolling_window() {
+        let mut window = RollingWindow::default();
+        window.add(NOW, SECONDS_PER_DAY, 100);
+        window.add(NOW + 3600, SECONDS_PER_DAY, 200);
+        assert_eq!(window.total(NOW + 3600, SECONDS_PER_DAY), 300);
+        // the first amount leaves the window after a day
+        assert_eq!(window.total(NOW + SECONDS_PER_DAY, SECONDS_PER_DAY), 200);
+        assert_eq!(window.total(NOW + SECONDS_PER_DAY + 3600, SECONDS_PER_DAY), 0);
+        // a later add clears the expired buckets
+        window.add(NOW + SECONDS_PER_DAY, SECONDS_PER_DAY, 50);
+        assert_eq!(window.total(NOW + SECONDS_PER_DAY, SECONDS_PER_DAY), 250);
+    }
+    #[test]
+    fn test_user_limits_tighten_immediately_loosen_after_delay() {
+        let mut limits = UserLimits::new(Pubkey::new_unique());
+        limits.set_limits(
+            LimitSettings {
+                daily_deposit_cap: 1000,
+                ..LimitSettings::none()
+            },
+            NOW,
+        );
+        assert_eq!(limits.limits.daily_deposit_cap, 1000);
+        assert_eq!(limits.pending_limits_effective_at, 0);
+
+        limits.set_limits(
+            LimitSettings {
+                daily_deposit_cap: 5000,
+                daily_wager_cap: 2000,
+                ..LimitSettings::none()
+            },
+            NOW,
+        );
+        // the wager cap applies now, the higher deposit cap after the delay
+        assert_eq!(limits.limits.daily_deposit_cap, 1000);
+        assert_eq!(limits.limits.daily_wager_cap, 2000);
+        assert_eq!(limits.pending_limits_effective_at, NOW + LIMIT_LOOSEN_DELAY);
+        limits.refresh(NOW + LIMIT_LOOSEN_DELAY - 1);
+        assert_eq!(limits.limits.daily_deposit_cap, 1000);
+        limits.refresh(NOW + LIMIT_LOOSEN_DELAY);
+        assert_eq!(limits.limits.daily_deposit_cap, 5000);
+        assert_eq!(limits.limits.weekly_deposit_cap, NO_LIMIT);
+        assert_eq!(limits.pending_limits_effective_at, 0);
+    }
+    #[test]
+    fn test_user_limits_record() {
+        let mut limits = UserLimits::new(Pubkey::new_unique());
+        limits.set_limits(
+            LimitSettings {
+                daily_deposit_cap: 1000,
+                weekly_loss_cap: 1500,
+                ..LimitSettings::none()
+            },
+            NOW,
+        );
+        limits.record_deposit(NOW, 1000).unwrap();
+        assert_eq!(limits.record_deposit(NOW, 1).unwrap_err(), ProgramError::from(BettingError::LimitExceeded));
+        limits.record_deposit(NOW + SECONDS_PER_DAY, 1000).unwrap();
+
+        limits.record_wager(NOW, 1000).unwrap();
+        assert_eq!(limits.record_wager(NOW, 600).unwrap_err(), ProgramError::from(BettingError::LimitExceeded));
+        // payouts reduce the net loss
+        limits.record_payout(NOW, 800);
+        limits.record_wager(NOW, 600).unwrap();
+        assert_eq!(
+            limits.record_wager(NOW + SECONDS_PER_DAY, 800).unwrap_err(),
+            ProgramError::from(BettingError::LimitExceeded)
+        );
+        limits.record_wager(NOW + SECONDS_PER_WEEK + SECONDS_PER_DAY, 1500).unwrap();
+    }
+    #[test]
+    fn test_user_limits_self_exclude() {
+        let mut limits = UserLimits::new(Pubkey::new_unique());
+        limits.self_exclude(NOW + SECONDS_PER_DAY, NOW).unwrap();
+        assert_eq!(limits.record_deposit(NOW, 1).unwrap_err(), ProgramError::from(BettingError::SelfExcluded));
+        assert_eq!(limits.record_wager(NOW, 1).unwrap_err(), ProgramError::from(BettingError::SelfExcluded));
+        // the exclusion cannot be shortened
+        assert_eq!(limits.self_exclude(NOW + 3600, NOW).unwrap_err(), ProgramError::InvalidArgument);
+        limits.self_exclude(NOW + SECONDS_PER_WEEK, NOW).unwrap();
+        limits.record_deposit(NOW + SECONDS_PER_WEEK, 1).unwrap();
+    }
+}
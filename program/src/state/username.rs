@@ -0,0 +1,119 @@
+use std::convert::TryInto;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankAccount;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    checks::{check_is_writable, check_pda_cannonical_bump},
+    error::BettingError,
+    math::CheckedMath,
+};
+
+use super::{BettingAccount, StateAccountType};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
+pub struct UsernameRecord {
+    pub account_type: StateAccountType,
+    pub version: u8,
+    pub user_account: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl UsernameRecord {
+    pub fn new(user_account: Pubkey, authority: Pubkey) -> Self {
+        Self {
+            account_type: StateAccountType::Username,
+            version: Self::VERSION,
+            user_account,
+            authority,
+        }
+    }
+    /// Usernames are unique regardless of case
+    pub fn seed(username: &str) -> String {
+        username.to_ascii_lowercase()
+    }
+    /// Find the registry PDA of a username, clients can fetch it to look up the user account holding the name
+    pub fn find_pda(username: &str) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"Username".as_ref(), Self::seed(username).as_bytes()], &crate::id())
+    }
+    /// Create the registry PDA of a username for a user account, paid by the user wallet
+    pub fn claim<'a>(
+        username: &str,
+        username_account_info: &AccountInfo<'a>,
+        user_account_info: &AccountInfo<'a>,
+        user_wallet_account_info: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let seed = Self::seed(username);
+        check_is_writable(username_account_info)?;
+        let username_pda_bump = check_pda_cannonical_bump(username_account_info, &[b"Username".as_ref(), seed.as_bytes()])?;
+        if !username_account_info.data_is_empty() {
+            msg!("Username {} is already taken", username);
+            return Err(ProgramError::from(BettingError::UsernameTaken));
+        }
+
+        let username_data = Self::new(*user_account_info.key, *user_wallet_account_info.key).try_to_vec()?;
+        let username_data_len = username_data.len();
+        let min_rent = Rent::get()?.minimum_balance(username_data_len);
+        let username_pda_signer_seeds = &[b"Username".as_ref(), seed.as_bytes(), &[username_pda_bump]];
+        // lamports sent to the PDA beforehand would make `create_account` fail and block the username, so the PDA is
+        // topped up to the rent, allocated and assigned instead
+        let lamports_to_transfer = min_rent.saturating_sub(username_account_info.lamports());
+        if lamports_to_transfer > 0 {
+            let lamports_transfer_ix = system_instruction::transfer(user_wallet_account_info.key, username_account_info.key, lamports_to_transfer);
+            invoke(&lamports_transfer_ix, &[user_wallet_account_info.clone(), username_account_info.clone()])?;
+        }
+        let username_allocate_ix = system_instruction::allocate(username_account_info.key, username_data_len.try_into().unwrap());
+        invoke_signed(&username_allocate_ix, std::slice::from_ref(username_account_info), &[username_pda_signer_seeds])?;
+        let username_assign_ix = system_instruction::assign(username_account_info.key, &crate::id());
+        invoke_signed(&username_assign_ix, std::slice::from_ref(username_account_info), &[username_pda_signer_seeds])?;
+        username_account_info.data.borrow_mut().copy_from_slice(&username_data);
+
+        Ok(())
+    }
+    /// Close the registry PDA of a username held by a user account, refunding the rent to the user wallet
+    pub fn release<'a>(
+        username: &str,
+        username_account_info: &AccountInfo<'a>,
+        user_account_info: &AccountInfo<'a>,
+        user_wallet_account_info: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let seed = Self::seed(username);
+        check_is_writable(username_account_info)?;
+        check_pda_cannonical_bump(username_account_info, &[b"Username".as_ref(), seed.as_bytes()])?;
+        // usernames set before the registry was introduced might not have been claimed
+        if username_account_info.data_is_empty() {
+            return Ok(());
+        }
+        let username_state = Self::try_from_account_info(username_account_info)?;
+        if &username_state.user_account != user_account_info.key {
+            msg!("Username {} is held by user account {}", username, username_state.user_account);
+            return Err(ProgramError::from(BettingError::WrongPubkey));
+        }
+
+        username_account_info.data.borrow_mut().fill(0);
+        username_account_info.realloc(0, false)?;
+        **user_wallet_account_info.lamports.borrow_mut() = user_wallet_account_info.lamports().try_add(username_account_info.lamports())?;
+        **username_account_info.lamports.borrow_mut() = 0;
+
+        Ok(())
+    }
+}
+impl BettingAccount for UsernameRecord {
+    const ACCOUNT_TYPE: StateAccountType = StateAccountType::Username;
+    const VERSION: u8 = 1;
+
+    fn try_from_legacy_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
@@ -1,12 +1,21 @@
+use std::convert::TryInto;
+
 use borsh::{BorshDeserialize, BorshSerialize};
 use shank::ShankAccount;
-use solana_program::pubkey::Pubkey;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
-use super::{game::BetInput, BettingAccount, StateAccountType};
+use super::{
+    game::BetInput,
+    legacy::{VrfResultV0, VrfResultV1, VrfResultV2, VrfResultV3, VrfResultV4, VrfResultV5},
+    randomness::Randomness,
+    unique_legacy_layout, BettingAccount, StateAccountType,
+};
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, ShankAccount)]
 pub struct VrfResult {
     pub account_type: StateAccountType,
+    pub version: u8,
+    pub bump: u8,
     pub is_fullfilled: bool,
     pub is_used: bool,
     pub marked_for_close: bool,
@@ -19,12 +28,35 @@ pub struct VrfResult {
     pub locked_bettor_lamports: u64,
     pub locked_host_lamports: u64,
     pub bet_input: BetInput,
+    pub randomness: Randomness,
+    // penalty paid to the bettor out of the operator bond when the fullfillment was late
+    pub slashed_lamports: u64,
+    // closed when the bet is settled, the rent going back to the bettor's balance
+    pub close_on_resolve: bool,
+    // escrowed in the stats account for whoever resolves the bet, from the bettor and the game
+    pub crank_tip_lamports: u64,
 }
 
 impl VrfResult {
     pub fn new(owner: Pubkey, game: Pubkey, bet_id: u32, alpha: [u8; 72], locked_bettor_lamports: u64, locked_host_lamports: u64, bet_input: BetInput) -> Self {
+        let bump = Self::find_pda(&game, &owner, bet_id).1;
+        Self::new_with_bump(owner, game, bet_id, alpha, locked_bettor_lamports, locked_host_lamports, bet_input, bump)
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_bump(
+        owner: Pubkey,
+        game: Pubkey,
+        bet_id: u32,
+        alpha: [u8; 72],
+        locked_bettor_lamports: u64,
+        locked_host_lamports: u64,
+        bet_input: BetInput,
+        bump: u8,
+    ) -> Self {
         Self {
             account_type: StateAccountType::Vrf,
+            version: Self::VERSION,
+            bump,
             is_fullfilled: false,
             is_used: false,
             marked_for_close: false,
@@ -37,10 +69,33 @@ impl VrfResult {
             locked_bettor_lamports,
             locked_host_lamports,
             bet_input,
+            randomness: Randomness::Vrf,
+            slashed_lamports: 0,
+            close_on_resolve: false,
+            crank_tip_lamports: 0,
         }
     }
+    /// Unix timestamp of the bet, the first bytes of the alpha
+    pub fn placed_at(&self) -> i64 {
+        i64::from_le_bytes(self.alpha[..8].try_into().unwrap())
+    }
+    pub fn find_pda(game: &Pubkey, owner: &Pubkey, bet_id: u32) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"VrfResult".as_ref(), game.as_ref(), owner.as_ref(), &bet_id.to_le_bytes()], &crate::id())
+    }
 }
 
 impl BettingAccount for VrfResult {
     const ACCOUNT_TYPE: StateAccountType = StateAccountType::Vrf;
+    const VERSION: u8 = 6;
+
+    fn try_from_legacy_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        unique_legacy_layout([
+            VrfResultV5::try_from_slice(data).ok().filter(|v5| v5.version == 5).map(VrfResult::from),
+            VrfResultV4::try_from_slice(data).ok().filter(|v4| v4.version == 4).map(VrfResult::from),
+            VrfResultV3::try_from_slice(data).ok().filter(|v3| v3.version == 3).map(VrfResult::from),
+            VrfResultV2::try_from_slice(data).ok().filter(|v2| v2.version == 2).map(VrfResult::from),
+            VrfResultV1::try_from_slice(data).ok().filter(|v1| v1.version == 1).map(VrfResult::from),
+            VrfResultV0::try_from_slice(data).ok().map(|v0| VrfResultV1::from(v0).into()),
+        ])
+    }
 }